@@ -13,16 +13,51 @@ use super::{Expression, statements::BlockStatement};
 #[derive(Clone)]
 pub struct IdentifierExp {
     value: String,
+    // The (depth, slot) of the variable, it's set by the resolver.
+    slot: Option<(usize, usize)>,
+    // The line and column of the identifier in the source code, it's set by the parser.
+    position: Option<(usize, usize)>,
 }
 
 impl IdentifierExp {
     pub fn new(value: String) -> Self {
-        Self { value }
+        Self {
+            value,
+            slot: None,
+            position: None,
+        }
+    }
+
+    /// Create an identifier that has been resolved to a variable by the resolver.
+    pub fn resolved(value: String, depth: usize, slot: usize) -> Self {
+        Self {
+            value,
+            slot: Some((depth, slot)),
+            position: None,
+        }
+    }
+
+    /// Set the line and column where the identifier is written, both of them start from 1.
+    pub fn with_position(mut self, line: usize, column: usize) -> Self {
+        self.position = Some((line, column));
+        self
     }
 
     pub fn value(&self) -> &str {
         &self.value
     }
+
+    /// Get the location of the variable as (depth, slot), or `None` if the identifier is not resolved.
+    /// The depth is the number of scopes between the use and the definition of the variable,
+    /// and the slot is the index of the variable in that scope.
+    pub fn slot(&self) -> Option<(usize, usize)> {
+        self.slot
+    }
+
+    /// Get the (line, column) of the identifier, or `None` if it's not created by the parser.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.position
+    }
 }
 
 impl Debug for IdentifierExp {
@@ -137,6 +172,99 @@ impl Display for StringExp {
     }
 }
 
+/// The char expression represents a char value: 'a', '\n'.
+#[derive(Clone)]
+pub struct CharExp {
+    value: char,
+}
+
+impl CharExp {
+    pub fn new(value: char) -> Self {
+        Self { value }
+    }
+
+    pub fn value(&self) -> char {
+        self.value
+    }
+}
+
+impl Debug for CharExp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.value)
+    }
+}
+
+impl Display for CharExp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.value)
+    }
+}
+
+/// The byte expression represents a byte value: b'a', b'\n', b'\xFF'.
+#[derive(Clone)]
+pub struct ByteExp {
+    value: u8,
+}
+
+impl ByteExp {
+    pub fn new(value: u8) -> Self {
+        Self { value }
+    }
+
+    pub fn value(&self) -> u8 {
+        self.value
+    }
+}
+
+impl Debug for ByteExp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "b'{}'", self.value.escape_ascii())
+    }
+}
+
+impl Display for ByteExp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "b'{}'", self.value.escape_ascii())
+    }
+}
+
+/// The interpolated string expression is a string with the embedded expressions:
+/// "Hello, ${name}! You have ${count + 1} items".
+/// The parts are the string segments and the embedded expressions in order,
+/// and each part is converted to a string when it's evaluated.
+#[derive(Clone)]
+pub struct InterpolatedExp {
+    parts: Vec<Expression>,
+}
+
+impl InterpolatedExp {
+    pub fn new(parts: Vec<Expression>) -> Self {
+        Self { parts }
+    }
+
+    pub fn parts(&self) -> &Vec<Expression> {
+        &self.parts
+    }
+}
+
+impl Debug for InterpolatedExp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self.parts)
+    }
+}
+
+impl Display for InterpolatedExp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for part in self.parts.iter() {
+            match part {
+                Expression::Str(s) => write!(f, "{}", s)?,
+                exp => write!(f, "${{{}}}", exp)?,
+            }
+        }
+        Ok(())
+    }
+}
+
 /// The array expression represents an array of elements.
 #[derive(Clone)]
 pub struct ArrayExp {
@@ -223,6 +351,8 @@ pub struct IfExp {
     condition: Box<Expression>,
     consequence: BlockStatement,
     else_exp: Option<ElseExp>,
+    // The line and column of the `if` keyword, it's set by the parser.
+    position: Option<(usize, usize)>,
 }
 
 impl IfExp {
@@ -235,9 +365,16 @@ impl IfExp {
             condition: Box::new(condition),
             consequence,
             else_exp,
+            position: None,
         }
     }
 
+    /// Set the line and column of the `if` keyword, both of them start from 1.
+    pub fn with_position(mut self, line: usize, column: usize) -> Self {
+        self.position = Some((line, column));
+        self
+    }
+
     pub fn condition(&self) -> &Expression {
         &self.condition
     }
@@ -249,6 +386,11 @@ impl IfExp {
     pub fn else_exp(&self) -> Option<&ElseExp> {
         self.else_exp.as_ref()
     }
+
+    /// Get the (line, column) of the `if` keyword, or `None` if it's not created by the parser.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.position
+    }
 }
 
 impl Debug for IfExp {
@@ -345,11 +487,17 @@ impl Display for ElseExp {
 pub struct CallExp {
     name: IdentifierExp,
     arguments: Vec<Expression>,
+    // The line number of the call site in the source file.
+    line: usize,
 }
 
 impl CallExp {
-    pub fn new(name: IdentifierExp, arguments: Vec<Expression>) -> Self {
-        Self { name, arguments }
+    pub fn new(name: IdentifierExp, arguments: Vec<Expression>, line: usize) -> Self {
+        Self {
+            name,
+            arguments,
+            line,
+        }
     }
 
     pub fn name(&self) -> &IdentifierExp {
@@ -359,6 +507,10 @@ impl CallExp {
     pub fn arguments(&self) -> &Vec<Expression> {
         &self.arguments
     }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
 }
 
 impl Debug for CallExp {
@@ -388,6 +540,8 @@ impl Display for CallExp {
 pub struct PrefixExp {
     operator: TokenType,
     right: Box<Expression>,
+    // The line and column of the operator, it's set by the parser.
+    position: Option<(usize, usize)>,
 }
 
 impl PrefixExp {
@@ -395,9 +549,16 @@ impl PrefixExp {
         Self {
             operator,
             right: Box::new(right),
+            position: None,
         }
     }
 
+    /// Set the line and column of the operator, both of them start from 1.
+    pub fn with_position(mut self, line: usize, column: usize) -> Self {
+        self.position = Some((line, column));
+        self
+    }
+
     pub fn operator(&self) -> &TokenType {
         &self.operator
     }
@@ -405,6 +566,11 @@ impl PrefixExp {
     pub fn right(&self) -> &Expression {
         &self.right
     }
+
+    /// Get the (line, column) of the operator, or `None` if it's not created by the parser.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.position
+    }
 }
 
 impl Debug for PrefixExp {
@@ -425,6 +591,8 @@ pub struct InfixExp {
     left: Box<Expression>,
     operator: TokenType,
     right: Box<Expression>,
+    // The line and column of the operator, it's set by the parser.
+    position: Option<(usize, usize)>,
 }
 
 impl InfixExp {
@@ -433,9 +601,16 @@ impl InfixExp {
             left: Box::new(left),
             operator,
             right: Box::new(right),
+            position: None,
         }
     }
 
+    /// Set the line and column of the operator, both of them start from 1.
+    pub fn with_position(mut self, line: usize, column: usize) -> Self {
+        self.position = Some((line, column));
+        self
+    }
+
     pub fn left(&self) -> &Expression {
         &self.left
     }
@@ -447,6 +622,11 @@ impl InfixExp {
     pub fn right(&self) -> &Expression {
         &self.right
     }
+
+    /// Get the (line, column) of the operator, or `None` if it's not created by the parser.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.position
+    }
 }
 
 impl Debug for InfixExp {
@@ -2,6 +2,7 @@ use std::fmt::{Debug, Display};
 
 pub mod expressions;
 pub mod statements;
+pub mod types;
 
 /// This is the AST (Abstract Syntax Tree) version 2. It is a simplified version compared to AST version 1.
 /// The AST version 1 uses dynamic dispatch to handle different types of nodes, which leads to increased complexity and slower performance due to runtime type checking.
@@ -41,6 +42,8 @@ impl Display for Node {
 ///     - Basic calculate operators: x + y, x - y, x * y, x / y
 ///     - Logical operators: x == y, x != y, x < y, x > y, x <= y, x >= y, x && y, x || y
 ///     The above infix expressions will be parsed into the InfixExp node.
+/// - InterpolatedExp: "Hello, ${name}!"
+/// - CharExp and ByteExp: 'a', b'a'
 /// - IfExp and ElseExp: if (x < y) { return x; } else { return y; }
 /// - FuncExp: fn add(x, y) { return x + y; }
 #[derive(Clone)]
@@ -49,6 +52,9 @@ pub enum Expression {
     Num(expressions::NumExp),
     Boolean(expressions::BooleanExp),
     Str(expressions::StringExp),
+    Char(expressions::CharExp),
+    Byte(expressions::ByteExp),
+    Interpolated(expressions::InterpolatedExp),
     Arr(expressions::ArrayExp),
     ArrIndex(expressions::ArrayIndexExp),
     If(expressions::IfExp),
@@ -64,6 +70,9 @@ impl Debug for Expression {
             Expression::Num(num) => write!(f, "{:?}", num),
             Expression::Boolean(bool_exp) => write!(f, "{:?}", bool_exp),
             Expression::Str(str_exp) => write!(f, "{:?}", str_exp),
+            Expression::Char(char_exp) => write!(f, "{:?}", char_exp),
+            Expression::Byte(byte_exp) => write!(f, "{:?}", byte_exp),
+            Expression::Interpolated(interp_exp) => write!(f, "{:?}", interp_exp),
             Expression::Arr(arr_exp) => write!(f, "{:?}", arr_exp),
             Expression::ArrIndex(arr_index_exp) => write!(f, "{:?}", arr_index_exp),
             Expression::If(if_exp) => write!(f, "{:?}", if_exp),
@@ -81,6 +90,9 @@ impl Display for Expression {
             Expression::Num(num) => write!(f, "{}", num),
             Expression::Boolean(bool_exp) => write!(f, "{}", bool_exp),
             Expression::Str(str_exp) => write!(f, "{}", str_exp),
+            Expression::Char(char_exp) => write!(f, "{}", char_exp),
+            Expression::Byte(byte_exp) => write!(f, "{}", byte_exp),
+            Expression::Interpolated(interp_exp) => write!(f, "{}", interp_exp),
             Expression::Arr(arr_exp) => write!(f, "{}", arr_exp),
             Expression::ArrIndex(arr_index_exp) => write!(f, "{}", arr_index_exp),
             Expression::If(if_exp) => write!(f, "{}", if_exp),
@@ -95,12 +107,18 @@ impl Display for Expression {
 /// For example:
 /// - LetStatement: let x = 822;
 /// - ReturnStatement: return x;
+/// - ThrowStatement: throw error("ValueError", "bad value");
+/// - TryStatement: try { risky(); } catch (e) { log(e); } finally { close(); }
+/// - ErrorStatement: the code that fails to parse, the parser goes on with the next statement.
 #[derive(Clone)]
 pub enum Statement {
     Let(statements::LetStatement),
     Return(statements::ReturnStatement),
     Block(statements::BlockStatement),
     Func(statements::FuncStatement),
+    Throw(statements::ThrowStatement),
+    Try(statements::TryStatement),
+    Error(statements::ErrorStatement),
 }
 
 impl Debug for Statement {
@@ -110,6 +128,9 @@ impl Debug for Statement {
             Statement::Return(return_stmt) => write!(f, "{:?}", return_stmt),
             Statement::Block(block_stmt) => write!(f, "{:?}", block_stmt),
             Statement::Func(func_stmt) => write!(f, "{:?}", func_stmt),
+            Statement::Throw(throw_stmt) => write!(f, "{:?}", throw_stmt),
+            Statement::Try(try_stmt) => write!(f, "{:?}", try_stmt),
+            Statement::Error(error_stmt) => write!(f, "{:?}", error_stmt),
         }
     }
 }
@@ -121,6 +142,9 @@ impl Display for Statement {
             Statement::Return(return_stmt) => write!(f, "{}", return_stmt),
             Statement::Block(block_stmt) => write!(f, "{}", block_stmt),
             Statement::Func(func_stmt) => write!(f, "{}", func_stmt),
+            Statement::Throw(throw_stmt) => write!(f, "{}", throw_stmt),
+            Statement::Try(try_stmt) => write!(f, "{}", try_stmt),
+            Statement::Error(error_stmt) => write!(f, "{}", error_stmt),
         }
     }
 }
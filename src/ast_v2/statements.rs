@@ -1,18 +1,41 @@
 use std::fmt::{Debug, Display};
+use std::rc::Rc;
 
-use super::{Expression, expressions::IdentifierExp, Node};
+use super::expressions::{IdentifierExp, IfExp};
+use super::{types::Type, Expression, Node, Statement};
 
 /// Let statement is a statement that binds a value to a name.
-/// For example: let x = 822;
+/// For example: let x = 822; let y: float = 8.22;
 #[derive(Clone)]
 pub struct LetStatement {
     ident: IdentifierExp,
+    // The type annotation after the name.
+    annotation: Option<Type>,
     value: Option<Expression>,
+    // The doc comments before the let statement.
+    doc: Option<String>,
 }
 
 impl LetStatement {
     pub fn new(ident: IdentifierExp, value: Option<Expression>) -> Self {
-        Self { ident, value }
+        Self {
+            ident,
+            annotation: None,
+            value,
+            doc: None,
+        }
+    }
+
+    /// Set the type annotation of the let statement.
+    pub fn with_annotation(mut self, annotation: Option<Type>) -> Self {
+        self.annotation = annotation;
+        self
+    }
+
+    /// Attach the doc comments to the let statement.
+    pub fn with_doc(mut self, doc: Option<String>) -> Self {
+        self.doc = doc;
+        self
     }
 
     /// Get the name of the let statement.
@@ -21,35 +44,52 @@ impl LetStatement {
         self.ident.value()
     }
 
+    /// Get the identifier of the let statement.
+    pub fn ident(&self) -> &IdentifierExp {
+        &self.ident
+    }
+
+    /// Get the type annotation of the let statement.
+    /// For example: let x: int = 822; -> int
+    pub fn annotation(&self) -> Option<&Type> {
+        self.annotation.as_ref()
+    }
+
     /// Get the value of the let statement.
     /// For example: let x = 822; -> 822
     pub fn value(&self) -> Option<&Expression> {
         self.value.as_ref()
     }
+
+    /// Get the doc comments of the let statement, the lines are joined by '\n'.
+    /// For example: /// The answer. let x = 42; -> The answer.
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
 }
 
 impl Debug for LetStatement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "let {}", self.ident)?;
+        if let Some(annotation) = &self.annotation {
+            write!(f, ": {}", annotation)?;
+        }
         match &self.value {
-            Some(value) => {
-                write!(f, "let {} = {};", self.ident, value)
-            }
-            None => {
-                write!(f, "let {};", self.ident)
-            }
+            Some(value) => write!(f, " = {};", value),
+            None => write!(f, ";"),
         }
     }
 }
 
 impl Display for LetStatement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "let {}", self.ident)?;
+        if let Some(annotation) = &self.annotation {
+            write!(f, ": {}", annotation)?;
+        }
         match &self.value {
-            Some(value) => {
-                write!(f, "let {} = {};", self.ident, value)
-            }
-            None => {
-                write!(f, "let {};", self.ident)
-            }
+            Some(value) => write!(f, " = {};", value),
+            None => write!(f, ";"),
         }
     }
 }
@@ -58,11 +98,22 @@ impl Display for LetStatement {
 #[derive(Clone)]
 pub struct ReturnStatement {
     value: Option<Expression>,
+    // The line and column of the `return` keyword, it's set by the parser.
+    position: Option<(usize, usize)>,
 }
 
 impl ReturnStatement {
     pub fn new(value: Option<Expression>) -> Self {
-        Self { value }
+        Self {
+            value,
+            position: None,
+        }
+    }
+
+    /// Set the line and column of the `return` keyword, both of them start from 1.
+    pub fn with_position(mut self, line: usize, column: usize) -> Self {
+        self.position = Some((line, column));
+        self
     }
 
     /// Get the value of the return statement.
@@ -70,6 +121,11 @@ impl ReturnStatement {
     pub fn value(&self) -> Option<&Expression> {
         self.value.as_ref()
     }
+
+    /// Get the (line, column) of the `return` keyword, or `None` if it's not created by the parser.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.position
+    }
 }
 
 impl Debug for ReturnStatement {
@@ -122,6 +178,41 @@ impl BlockStatement {
 
         self.statements.as_mut().unwrap().push(stmt);
     }
+
+    /// Check whether every path of the block ends with a `return` or `throw`,
+    /// the nested functions are not included.
+    pub fn always_returns(&self) -> bool {
+        self.statements
+            .iter()
+            .flatten()
+            .any(|node| match node.as_ref() {
+                Node::Stmt(Statement::Return(_)) | Node::Stmt(Statement::Throw(_)) => true,
+                Node::Stmt(Statement::Block(block)) => block.always_returns(),
+                // The error of the body goes to the catch block if there is one, and the finally block
+                // ends all the paths if it returns.
+                Node::Stmt(Statement::Try(try_stmt)) => {
+                    let body_returns = try_stmt.body().always_returns()
+                        && try_stmt
+                            .catch()
+                            .is_none_or(|(_, block)| block.always_returns());
+                    body_returns || try_stmt.finally().is_some_and(|b| b.always_returns())
+                }
+                Node::Exp(Expression::If(if_exp)) => if_always_returns(if_exp),
+                _ => false,
+            })
+    }
+}
+
+fn if_always_returns(if_exp: &IfExp) -> bool {
+    let else_returns = match if_exp.else_exp() {
+        Some(else_exp) => match (else_exp.if_exp(), else_exp.consequence()) {
+            (Some(if_exp), _) => if_always_returns(if_exp),
+            (None, Some(block)) => block.always_returns(),
+            (None, None) => false,
+        },
+        None => false,
+    };
+    else_returns && if_exp.consequence().always_returns()
 }
 
 impl Debug for BlockStatement {
@@ -161,12 +252,18 @@ impl Display for BlockStatement {
 }
 
 /// Function statement is a statement that defines a function.
-/// For example: fn add(x, y) { return x + y; }
+/// For example: fn add(x, y) { return x + y; } func add(x: int, y: int) -> int { return x + y; }
 #[derive(Clone)]
 pub struct FuncStatement {
     ident: IdentifierExp,
     params: Option<Vec<IdentifierExp>>,
-    body: BlockStatement,
+    // The type annotations of the parameters, in the same order as the parameters.
+    param_types: Vec<Option<Type>>,
+    return_type: Option<Type>,
+    // The body is shared by the functions created by this statement, so they're the same definition.
+    body: Rc<BlockStatement>,
+    // The doc comments before the function statement.
+    doc: Option<String>,
 }
 
 impl FuncStatement {
@@ -175,64 +272,270 @@ impl FuncStatement {
         params: Option<Vec<IdentifierExp>>,
         body: BlockStatement,
     ) -> Self {
+        let param_types = vec![None; params.as_ref().map_or(0, |p| p.len())];
         Self {
             ident,
             params,
-            body,
+            param_types,
+            return_type: None,
+            body: Rc::new(body),
+            doc: None,
         }
     }
 
+    /// Set the type annotations of the parameters and the return value.
+    pub fn with_types(mut self, param_types: Vec<Option<Type>>, return_type: Option<Type>) -> Self {
+        self.param_types = param_types;
+        self.return_type = return_type;
+        self
+    }
+
+    /// Attach the doc comments to the function statement.
+    pub fn with_doc(mut self, doc: Option<String>) -> Self {
+        self.doc = doc;
+        self
+    }
+
     /// Get the name of the function statement.
     /// For example: fn add(x, y) { return x + y; } -> add
     pub fn name(&self) -> &str {
         self.ident.value()
     }
 
+    /// Get the identifier of the function statement.
+    pub fn ident(&self) -> &IdentifierExp {
+        &self.ident
+    }
+
     /// Get the parameters of the function statement.
     /// For example: fn add(x, y) { return x + y; } -> x, y
     pub fn params(&self) -> Option<&Vec<IdentifierExp>> {
         self.params.as_ref()
     }
 
+    /// Get the type annotations of the parameters, `None` is a parameter without annotation.
+    /// For example: fn add(x: int, y) { return x + y; } -> int, None
+    pub fn param_types(&self) -> &[Option<Type>] {
+        &self.param_types
+    }
+
+    /// Get the type annotation of the return value.
+    /// For example: fn add(x, y) -> int { return x + y; } -> int
+    pub fn return_type(&self) -> Option<&Type> {
+        self.return_type.as_ref()
+    }
+
     /// Get the body of the function statement.
     /// For example: fn add(x, y) { return x + y; } -> { return x + y; }
     pub fn body(&self) -> &BlockStatement {
         &self.body
     }
+
+    /// Get the body that is shared by the functions created by this statement.
+    pub fn shared_body(&self) -> &Rc<BlockStatement> {
+        &self.body
+    }
+
+    /// Get the doc comments of the function statement, the lines are joined by '\n'.
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+
+    // The parameters with their type annotations, they're separated by commas.
+    fn params_str(&self) -> String {
+        self.params
+            .iter()
+            .flatten()
+            .zip(&self.param_types)
+            .map(|(param, ty)| match ty {
+                Some(ty) => format!("{}: {}", param, ty),
+                None => param.to_string(),
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
 }
 
 impl Debug for FuncStatement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &self.params {
-            Some(params) => {
-                let params_str = params
-                    .iter()
-                    .map(|p| p.to_string())
-                    .collect::<Vec<String>>()
-                    .join(", ");
-                write!(f, "func {}({}) {:?}", self.ident, params_str, self.body)
-            }
-            None => {
-                write!(f, "func {}() {:?}", self.ident, self.body)
-            }
+        write!(f, "func {}({})", self.ident, self.params_str())?;
+        if let Some(return_type) = &self.return_type {
+            write!(f, " -> {}", return_type)?;
         }
+        write!(f, " {:?}", self.body)
     }
 }
 
 impl Display for FuncStatement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &self.params {
-            Some(params) => {
-                let params_str = params
-                    .iter()
-                    .map(|p| p.to_string())
-                    .collect::<Vec<String>>()
-                    .join(", ");
-                write!(f, "func {}({}) {:?}", self.ident, params_str, self.body)
-            }
-            None => {
-                write!(f, "func {}() {:?}", self.ident, self.body)
-            }
+        write!(f, "func {}({})", self.ident, self.params_str())?;
+        if let Some(return_type) = &self.return_type {
+            write!(f, " -> {}", return_type)?;
+        }
+        write!(f, " {:?}", self.body)
+    }
+}
+
+/// Throw statement is a statement that raises an error, it unwinds until a `try` catches it.
+/// For example: throw error("ValueError", "bad value"); throw "oops";
+#[derive(Clone)]
+pub struct ThrowStatement {
+    value: Expression,
+    // The line and column of the `throw` keyword, it's set by the parser.
+    position: Option<(usize, usize)>,
+}
+
+impl ThrowStatement {
+    pub fn new(value: Expression) -> Self {
+        Self {
+            value,
+            position: None,
         }
     }
+
+    /// Set the line and column of the `throw` keyword, both of them start from 1.
+    pub fn with_position(mut self, line: usize, column: usize) -> Self {
+        self.position = Some((line, column));
+        self
+    }
+
+    /// Get the value of the throw statement.
+    /// For example: throw "oops"; -> "oops"
+    pub fn value(&self) -> &Expression {
+        &self.value
+    }
+
+    /// Get the (line, column) of the `throw` keyword, or `None` if it's not created by the parser.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.position
+    }
+}
+
+impl Debug for ThrowStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "throw {:?};", self.value)
+    }
+}
+
+impl Display for ThrowStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "throw {};", self.value)
+    }
+}
+
+/// Try statement is a statement that catches the errors thrown in its body.
+/// It has a `catch` block, a `finally` block or both of them.
+/// For example: try { risky(); } catch (e) { log(e); } finally { close(); }
+#[derive(Clone)]
+pub struct TryStatement {
+    body: BlockStatement,
+    // The name bound to the caught error and the block that handles it.
+    catch: Option<(IdentifierExp, BlockStatement)>,
+    finally: Option<BlockStatement>,
+}
+
+impl TryStatement {
+    pub fn new(
+        body: BlockStatement,
+        catch: Option<(IdentifierExp, BlockStatement)>,
+        finally: Option<BlockStatement>,
+    ) -> Self {
+        Self {
+            body,
+            catch,
+            finally,
+        }
+    }
+
+    /// Get the body of the try statement.
+    pub fn body(&self) -> &BlockStatement {
+        &self.body
+    }
+
+    /// Get the name of the caught error and the catch block.
+    /// For example: try {} catch (e) { return e; } -> e, { return e; }
+    pub fn catch(&self) -> Option<(&IdentifierExp, &BlockStatement)> {
+        self.catch.as_ref().map(|(ident, block)| (ident, block))
+    }
+
+    /// Get the finally block, it runs whether the body throws or not.
+    pub fn finally(&self) -> Option<&BlockStatement> {
+        self.finally.as_ref()
+    }
+}
+
+impl Debug for TryStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "try {:?}", self.body)?;
+        if let Some((ident, block)) = &self.catch {
+            write!(f, " catch ({}) {:?}", ident, block)?;
+        }
+        if let Some(block) = &self.finally {
+            write!(f, " finally {:?}", block)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for TryStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "try {}", self.body)?;
+        if let Some((ident, block)) = &self.catch {
+            write!(f, " catch ({}) {}", ident, block)?;
+        }
+        if let Some(block) = &self.finally {
+            write!(f, " finally {}", block)?;
+        }
+        Ok(())
+    }
+}
+
+/// Error statement is the code that the parser fails to parse, it's skipped to the start of the next statement.
+/// It keeps the source text, so the tools can still work on the rest of a broken file.
+/// For example: let = 5; -> the error is `expected next token to be Ident`
+#[derive(Clone)]
+pub struct ErrorStatement {
+    message: String,
+    text: String,
+    // The position of the first token of the statement.
+    line: usize,
+    column: usize,
+}
+
+impl ErrorStatement {
+    pub fn new(message: &str, text: &str, line: usize, column: usize) -> Self {
+        Self {
+            message: message.to_string(),
+            text: text.to_string(),
+            line,
+            column,
+        }
+    }
+
+    /// Get the first error found in the statement.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Get the source text of the statement.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Get the line and the column of the statement, both of them start from 1.
+    pub fn position(&self) -> (usize, usize) {
+        (self.line, self.column)
+    }
+}
+
+impl Debug for ErrorStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "error({}) {}", self.message, self.text)
+    }
+}
+
+impl Display for ErrorStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.text)
+    }
 }
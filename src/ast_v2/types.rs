@@ -0,0 +1,85 @@
+use std::fmt::{Display, Formatter};
+
+/// The type of a value, it's written in the type annotations and inferred by the type checker.
+/// For example: let x: int = 1; func first(arr: [str]) -> str { return arr[0]; }
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Int,
+    Float,
+    Bool,
+    Str,
+    Char,
+    Byte,
+    Null,
+    Array(Box<Type>),
+    /// The function that takes the parameters of the types and returns the value of the type.
+    /// It can't be written in the annotations, it's the type of the functions.
+    Func(Vec<Type>, Box<Type>),
+    /// The type that is unknown until the code runs, it's compatible with all the types.
+    /// The code without annotations is `any`, so the checker only checks what it knows.
+    Any,
+}
+
+impl Type {
+    /// Get the type by its name in the annotations, the array types are parsed by the parser.
+    pub fn from_name(name: &str) -> Option<Type> {
+        match name {
+            "int" => Some(Type::Int),
+            "float" => Some(Type::Float),
+            "bool" => Some(Type::Bool),
+            "str" => Some(Type::Str),
+            "char" => Some(Type::Char),
+            "byte" => Some(Type::Byte),
+            "null" => Some(Type::Null),
+            "any" => Some(Type::Any),
+            _ => None,
+        }
+    }
+
+    /// Check whether a value of the other type can be used where this type is expected.
+    /// An integer can be used as a float, the same as the arithmetic of the evaluator.
+    pub fn accepts(&self, other: &Type) -> bool {
+        match (self, other) {
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::Float, Type::Int) => true,
+            (Type::Array(expected), Type::Array(actual)) => expected.accepts(actual),
+            (Type::Func(expected_params, expected_ret), Type::Func(params, ret)) => {
+                expected_params.len() == params.len()
+                    && expected_params
+                        .iter()
+                        .zip(params)
+                        .all(|(e, p)| p.accepts(e))
+                    && expected_ret.accepts(ret)
+            }
+            _ => self == other,
+        }
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, Type::Int | Type::Float)
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Int => write!(f, "int"),
+            Type::Float => write!(f, "float"),
+            Type::Bool => write!(f, "bool"),
+            Type::Str => write!(f, "str"),
+            Type::Char => write!(f, "char"),
+            Type::Byte => write!(f, "byte"),
+            Type::Null => write!(f, "null"),
+            Type::Array(element) => write!(f, "[{}]", element),
+            Type::Func(params, ret) => {
+                let params_str = params
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, "func({}) -> {}", params_str, ret)
+            }
+            Type::Any => write!(f, "any"),
+        }
+    }
+}
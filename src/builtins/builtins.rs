@@ -0,0 +1,356 @@
+use std::fmt::{self, Debug, Display, Formatter};
+
+use crate::object::object::{Array, BaseValue, Error, Object, ObjectType, Str, Value};
+
+/// A builtin function, it's a value that can be called like a function defined in the code.
+/// The invalid arguments result in a thrown error, it can be caught by `try` like the other errors.
+#[derive(Clone, Copy)]
+pub struct Builtin {
+    name: &'static str,
+    func: fn(&[Object]) -> Object,
+}
+
+impl Builtin {
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn call(&self, args: &[Object]) -> Object {
+        (self.func)(args)
+    }
+}
+
+impl Debug for Builtin {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "builtin {}", self.name)
+    }
+}
+
+impl Display for Builtin {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "builtin {}", self.name)
+    }
+}
+
+/// All the builtin functions. The bytecode refers to a builtin function by its index,
+/// so the new functions must be appended to the end.
+pub const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "int",
+        func: to_int,
+    },
+    Builtin {
+        name: "char",
+        func: to_char,
+    },
+    Builtin {
+        name: "byte",
+        func: to_byte,
+    },
+    Builtin {
+        name: "str",
+        func: to_str,
+    },
+    Builtin {
+        name: "float",
+        func: to_float,
+    },
+    Builtin {
+        name: "bool",
+        func: to_bool,
+    },
+    Builtin {
+        name: "type",
+        func: type_of,
+    },
+    Builtin {
+        name: "is_null",
+        func: |args| is_type("is_null", args, ObjectType::Null),
+    },
+    Builtin {
+        name: "is_int",
+        func: |args| is_type("is_int", args, ObjectType::Integer),
+    },
+    Builtin {
+        name: "is_float",
+        func: |args| is_type("is_float", args, ObjectType::Float),
+    },
+    Builtin {
+        name: "is_bool",
+        func: |args| is_type("is_bool", args, ObjectType::Boolean),
+    },
+    Builtin {
+        name: "is_char",
+        func: |args| is_type("is_char", args, ObjectType::Char),
+    },
+    Builtin {
+        name: "is_byte",
+        func: |args| is_type("is_byte", args, ObjectType::Byte),
+    },
+    Builtin {
+        name: "is_str",
+        func: |args| is_type("is_str", args, ObjectType::String),
+    },
+    Builtin {
+        name: "is_array",
+        func: |args| is_type("is_array", args, ObjectType::Array),
+    },
+    Builtin {
+        name: "is_func",
+        func: |args| is_type("is_func", args, ObjectType::Func),
+    },
+    Builtin {
+        name: "error",
+        func: new_error,
+    },
+    Builtin {
+        name: "error_kind",
+        func: |args| {
+            error_field("error_kind", args, |err| {
+                Object::Str(Str::new(err.kind().to_string()))
+            })
+        },
+    },
+    Builtin {
+        name: "error_message",
+        func: |args| {
+            error_field("error_message", args, |err| {
+                Object::Str(Str::new(err.message().to_string()))
+            })
+        },
+    },
+    Builtin {
+        name: "error_trace",
+        func: |args| {
+            error_field("error_trace", args, |err| {
+                let trace = err
+                    .trace()
+                    .iter()
+                    .map(|frame| Object::Str(Str::new(frame.name().to_string())));
+                Object::Array(Array::new(trace.collect()))
+            })
+        },
+    },
+    Builtin {
+        name: "is_error",
+        func: |args| is_type("is_error", args, ObjectType::Error),
+    },
+];
+
+/// Get the index of the builtin function by its name.
+pub fn lookup(name: &str) -> Option<usize> {
+    BUILTINS.iter().position(|builtin| builtin.name == name)
+}
+
+// =================== Conversions ===================
+
+// Convert a number, bool, char, byte or numeric string to an integer, the char is converted to its code point.
+fn to_int(args: &[Object]) -> Object {
+    let arg = match single("int", args) {
+        Ok(arg) => arg,
+        Err(err) => return err,
+    };
+
+    let value = match arg {
+        Object::Base(BaseValue::Integer(v)) => Some(*v.value()),
+        Object::Base(BaseValue::Float(v)) if in_int_range(*v.value()) => {
+            Some(v.value().trunc() as i64)
+        }
+        Object::Base(BaseValue::Boolean(v)) => Some(*v.value() as i64),
+        Object::Base(BaseValue::Char(v)) => Some(*v.value() as i64),
+        Object::Base(BaseValue::Byte(v)) => Some(*v.value() as i64),
+        Object::Str(s) => s.value().trim().parse::<i64>().ok(),
+        _ => None,
+    };
+
+    match value {
+        Some(v) => Object::Base(BaseValue::Integer(Value::new(v))),
+        None => cannot_convert(arg, "int"),
+    }
+}
+
+// Convert a number, bool, byte or numeric string to a float.
+fn to_float(args: &[Object]) -> Object {
+    let arg = match single("float", args) {
+        Ok(arg) => arg,
+        Err(err) => return err,
+    };
+
+    let value = match arg {
+        Object::Base(BaseValue::Integer(v)) => Some(*v.value() as f64),
+        Object::Base(BaseValue::Float(v)) => Some(*v.value()),
+        Object::Base(BaseValue::Boolean(v)) => Some(*v.value() as i64 as f64),
+        Object::Base(BaseValue::Byte(v)) => Some(*v.value() as f64),
+        Object::Str(s) => s.value().trim().parse::<f64>().ok(),
+        _ => None,
+    };
+
+    match value {
+        Some(v) => Object::Base(BaseValue::Float(Value::new(v))),
+        None => cannot_convert(arg, "float"),
+    }
+}
+
+// Convert a number to whether it's not zero, `null` to false, and the string "true" or "false" to its bool.
+fn to_bool(args: &[Object]) -> Object {
+    let arg = match single("bool", args) {
+        Ok(arg) => arg,
+        Err(err) => return err,
+    };
+
+    let value = match arg {
+        Object::Null => Some(false),
+        Object::Base(BaseValue::Integer(v)) => Some(*v.value() != 0),
+        Object::Base(BaseValue::Float(v)) => Some(*v.value() != 0.0),
+        Object::Base(BaseValue::Boolean(v)) => Some(*v.value()),
+        Object::Base(BaseValue::Byte(v)) => Some(*v.value() != 0),
+        Object::Str(s) => s.value().trim().parse::<bool>().ok(),
+        _ => None,
+    };
+
+    match value {
+        Some(b) => Object::Base(BaseValue::Boolean(Value::new(b))),
+        None => cannot_convert(arg, "bool"),
+    }
+}
+
+// Convert a code point, byte or single-char string to a char.
+fn to_char(args: &[Object]) -> Object {
+    let arg = match single("char", args) {
+        Ok(arg) => arg,
+        Err(err) => return err,
+    };
+
+    let value = match arg {
+        Object::Base(BaseValue::Integer(v)) => {
+            u32::try_from(*v.value()).ok().and_then(char::from_u32)
+        }
+        Object::Base(BaseValue::Char(v)) => Some(*v.value()),
+        Object::Base(BaseValue::Byte(v)) => Some(*v.value() as char),
+        Object::Str(s) => single_char(s),
+        _ => None,
+    };
+
+    match value {
+        Some(c) => Object::Base(BaseValue::Char(Value::new(c))),
+        None => cannot_convert(arg, "char"),
+    }
+}
+
+// Convert an integer in 0..=255, or a char whose code point is in that range to a byte.
+fn to_byte(args: &[Object]) -> Object {
+    let arg = match single("byte", args) {
+        Ok(arg) => arg,
+        Err(err) => return err,
+    };
+
+    let value = match arg {
+        Object::Base(BaseValue::Integer(v)) => u8::try_from(*v.value()).ok(),
+        Object::Base(BaseValue::Char(v)) => u8::try_from(*v.value()).ok(),
+        Object::Base(BaseValue::Byte(v)) => Some(*v.value()),
+        Object::Str(s) => single_char(s).and_then(|c| u8::try_from(c).ok()),
+        _ => None,
+    };
+
+    match value {
+        Some(b) => Object::Base(BaseValue::Byte(Value::new(b))),
+        None => cannot_convert(arg, "byte"),
+    }
+}
+
+// Convert any value to a string by its `Display`.
+fn to_str(args: &[Object]) -> Object {
+    match single("str", args) {
+        Ok(arg) => Object::Str(Str::new(arg.to_string())),
+        Err(err) => err,
+    }
+}
+
+// =================== Type Introspection ===================
+
+// Get the type name of the value, it's the same as the name in the type annotations.
+fn type_of(args: &[Object]) -> Object {
+    match single("type", args) {
+        Ok(arg) => Object::Str(Str::new(arg.obj_type().to_string())),
+        Err(err) => err,
+    }
+}
+
+// Check whether the only argument is of the type.
+fn is_type(name: &str, args: &[Object], obj_type: ObjectType) -> Object {
+    match single(name, args) {
+        Ok(arg) => Object::Base(BaseValue::Boolean(Value::new(arg.obj_type() == obj_type))),
+        Err(err) => err,
+    }
+}
+
+// =================== Errors ===================
+
+// Create an error value from its kind and message, it can be thrown by `throw`.
+fn new_error(args: &[Object]) -> Object {
+    match args {
+        [Object::Str(kind), Object::Str(message)] => {
+            Object::Error(Error::new(kind.value(), message.value().to_string()))
+        }
+        [_, _] => throw(
+            "ArgumentError",
+            "`error` takes the kind and message as strings".to_string(),
+        ),
+        _ => throw(
+            "ArgumentError",
+            format!("`error` takes 2 arguments, but {} were given", args.len()),
+        ),
+    }
+}
+
+// Get a field of the only argument, which must be an error value.
+fn error_field(name: &str, args: &[Object], field: fn(&Error) -> Object) -> Object {
+    match single(name, args) {
+        Ok(Object::Error(err)) => field(err),
+        Ok(arg) => throw(
+            "ArgumentError",
+            format!("`{}` takes an error, but got {:?}", name, arg),
+        ),
+        Err(err) => err,
+    }
+}
+
+// =================== Helper Functions ===================
+
+// Get the only argument, or the error if the number of arguments is wrong.
+fn single<'a>(name: &str, args: &'a [Object]) -> Result<&'a Object, Object> {
+    match args {
+        [arg] => Ok(arg),
+        _ => Err(throw(
+            "ArgumentError",
+            format!("`{}` takes 1 argument, but {} were given", name, args.len()),
+        )),
+    }
+}
+
+// Get the error of converting the value to the type.
+fn cannot_convert(arg: &Object, type_name: &str) -> Object {
+    throw(
+        "ConversionError",
+        format!("cannot convert {:?} to {}", arg, type_name),
+    )
+}
+
+// Throw a new error of the kind.
+fn throw(kind: &str, message: String) -> Object {
+    Object::Throw(Error::new(kind, message))
+}
+
+// Check whether the float can be truncated to an integer without overflow.
+fn in_int_range(value: f64) -> bool {
+    value.is_finite() && value >= i64::MIN as f64 && value < i64::MAX as f64
+}
+
+// Get the char of the string if it has exactly one char.
+fn single_char(s: &Str) -> Option<char> {
+    let mut chars = s.value().chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
@@ -0,0 +1,3 @@
+// The builtin functions can be called in any scope without being defined.
+// - builtins: The table of the builtin functions, it's shared by the evaluator and the virtual machine.
+pub mod builtins;
@@ -0,0 +1,572 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+use crate::ast_v2::expressions::{ElseExp, IdentifierExp, IfExp, InfixExp, PrefixExp};
+use crate::ast_v2::statements::{BlockStatement, FuncStatement};
+use crate::ast_v2::types::Type;
+use crate::ast_v2::{Expression, Node, Statement};
+use crate::builtins::builtins::BUILTINS;
+use crate::token::types::TokenType;
+
+/// An error found by the type checker, the position is the name, keyword or operator where it's found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeError {
+    message: String,
+    // The position where the error is found, both of them start from 1.
+    line: usize,
+    column: usize,
+}
+
+impl TypeError {
+    pub fn new(message: &str, line: usize, column: usize) -> Self {
+        Self {
+            message: message.to_string(),
+            line,
+            column,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl Display for TypeError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
+
+/// The type checker checks the types of the programs before they're evaluated, it works on the AST
+/// created by the parser, so the errors have positions.
+///
+/// It's gradual: the types of the variables are inferred from their values, and the parameters
+/// and the return values without annotations are `any`, which is compatible with all the types.
+/// So the code without annotations is only checked where the types are known.
+///
+/// The following errors are reported:
+/// - The value of a `let` statement, an argument or a returned value that doesn't match the annotation.
+/// - The operands that the operators can't be applied to, e.g. `1 + "a"` or `'a' < 1`.
+/// - The calls with the wrong number of arguments, and the calls of the values that aren't functions.
+/// - The index of an array that isn't an integer, and the index of a value that isn't an array.
+/// - The `if` conditions that are never true, e.g. a string.
+/// - The functions with a return type that end without returning a value on some path.
+///
+/// The scope rules are the same as the resolver, and the undefined names are left to the resolver.
+pub struct TypeChecker {
+    // The types of the names in the scopes, the first one is the builtin functions.
+    scopes: Vec<HashMap<String, Type>>,
+    // The return types of the functions that are being checked, the innermost one is the last.
+    return_types: Vec<Type>,
+    errors: Vec<TypeError>,
+}
+
+impl Default for TypeChecker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TypeChecker {
+    pub fn new() -> Self {
+        Self {
+            scopes: Vec::new(),
+            return_types: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Check the programs, all the errors found are returned in the order of the code.
+    pub fn check(mut self, programs: &[Node]) -> Result<(), Vec<TypeError>> {
+        let builtins = BUILTINS
+            .iter()
+            .map(|builtin| (builtin.name().to_string(), builtin_type(builtin.name())))
+            .collect();
+        self.scopes.push(builtins);
+
+        self.scopes.push(HashMap::new());
+        for node in programs {
+            self.check_node(node);
+        }
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self.errors)
+        }
+    }
+
+    fn check_node(&mut self, node: &Node) {
+        match node {
+            Node::Exp(exp) => {
+                self.check_exp(exp);
+            }
+            Node::Stmt(stmt) => self.check_stmt(stmt),
+        }
+    }
+
+    // =================== Check Statement ===================
+
+    fn check_stmt(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Let(let_stmt) => {
+                let value_type = match (let_stmt.value(), let_stmt.annotation()) {
+                    (Some(value), Some(annotation)) => self.check_value(value, annotation),
+                    (Some(value), None) => self.check_exp(value),
+                    (None, _) => Type::Any,
+                };
+
+                let var_type = match let_stmt.annotation() {
+                    Some(annotation) => {
+                        if !annotation.accepts(&value_type) {
+                            self.report(
+                                let_stmt.ident().position(),
+                                format!(
+                                    "variable `{}` is `{}`, but the value is `{}`",
+                                    let_stmt.name(),
+                                    annotation,
+                                    value_type
+                                ),
+                            );
+                        }
+                        annotation.clone()
+                    }
+                    None => value_type,
+                };
+                self.define(let_stmt.name(), var_type);
+            }
+            Statement::Return(ret_stmt) => {
+                let return_type = self.return_types.last().cloned();
+                let value_type = match (ret_stmt.value(), &return_type) {
+                    (Some(value), Some(return_type)) => self.check_value(value, return_type),
+                    (Some(value), None) => self.check_exp(value),
+                    (None, _) => Type::Null,
+                };
+
+                // The top-level `return` ends the script, its value can be anything.
+                let Some(return_type) = return_type else {
+                    return;
+                };
+                if !return_type.accepts(&value_type) {
+                    let msg = format!(
+                        "the function returns `{}`, but the value is `{}`",
+                        return_type, value_type
+                    );
+                    self.report(ret_stmt.position(), msg);
+                }
+            }
+            Statement::Block(block) => self.check_block(block),
+            Statement::Func(func_stmt) => self.check_func(func_stmt),
+            Statement::Throw(throw_stmt) => {
+                self.check_exp(throw_stmt.value());
+            }
+            Statement::Try(try_stmt) => {
+                self.check_block(try_stmt.body());
+                if let Some((ident, block)) = try_stmt.catch() {
+                    // The thrown value can be anything, so the caught error is `any`.
+                    // It's only visible in the catch block, the same as the resolver.
+                    let name = ident.value();
+                    let outer = self.scopes.last_mut().unwrap().remove(name);
+                    self.define(name, Type::Any);
+                    self.check_block(block);
+
+                    let scope = self.scopes.last_mut().unwrap();
+                    match outer {
+                        Some(ty) => scope.insert(name.to_string(), ty),
+                        None => scope.remove(name),
+                    };
+                }
+                if let Some(block) = try_stmt.finally() {
+                    self.check_block(block);
+                }
+            }
+            Statement::Error(_) => {}
+        }
+    }
+
+    fn check_block(&mut self, block: &BlockStatement) {
+        for stmt in block.statements().into_iter().flatten() {
+            self.check_node(stmt);
+        }
+    }
+
+    fn check_func(&mut self, func_stmt: &FuncStatement) {
+        let func_type = func_type(func_stmt);
+        let Type::Func(param_types, return_type) = &func_type else {
+            unreachable!("the type of a function is always `Type::Func`");
+        };
+
+        // The parameters and the function itself are defined in the scope of its body.
+        let mut scope = HashMap::new();
+        for (param, param_type) in func_stmt.params().into_iter().flatten().zip(param_types) {
+            scope.insert(param.value().to_string(), param_type.clone());
+        }
+        scope.insert(func_stmt.name().to_string(), func_type.clone());
+
+        self.scopes.push(scope);
+        self.return_types.push(return_type.as_ref().clone());
+        self.check_block(func_stmt.body());
+        self.return_types.pop();
+        self.scopes.pop();
+
+        // The function that ends without `return` results in null.
+        if !return_type.accepts(&Type::Null) && !returns_value(func_stmt.body()) {
+            self.report(
+                func_stmt.ident().position(),
+                format!(
+                    "function `{}` returns `{}`, but not all paths return a value",
+                    func_stmt.name(),
+                    return_type
+                ),
+            );
+        }
+
+        self.define(func_stmt.name(), func_type);
+    }
+
+    // =================== Check Expression ===================
+
+    // Check the expression and infer its type.
+    fn check_exp(&mut self, exp: &Expression) -> Type {
+        match exp {
+            Expression::Identifier(ident) => self.lookup(ident),
+            Expression::Num(num) => match num.integer_value() {
+                Some(_) => Type::Int,
+                None => Type::Float,
+            },
+            Expression::Boolean(_) => Type::Bool,
+            Expression::Str(_) => Type::Str,
+            Expression::Char(_) => Type::Char,
+            Expression::Byte(_) => Type::Byte,
+            Expression::Interpolated(interp_exp) => {
+                for part in interp_exp.parts() {
+                    self.check_exp(part);
+                }
+                Type::Str
+            }
+            Expression::Arr(arr_exp) => {
+                let element_types: Vec<Type> = arr_exp
+                    .elements()
+                    .iter()
+                    .map(|element| self.check_exp(element))
+                    .collect();
+                Type::Array(Box::new(common_type(&element_types)))
+            }
+            Expression::ArrIndex(arr_index) => {
+                let index_type = self.check_exp(arr_index.index());
+                if !Type::Int.accepts(&index_type) {
+                    self.report(
+                        arr_index.name().position(),
+                        format!("the index must be `int`, but it's `{}`", index_type),
+                    );
+                }
+
+                match self.lookup(arr_index.name()) {
+                    Type::Array(element) => *element,
+                    Type::Any => Type::Any,
+                    other => {
+                        self.report(
+                            arr_index.name().position(),
+                            format!(
+                                "`{}` is `{}`, only an array can be indexed",
+                                arr_index.name(),
+                                other
+                            ),
+                        );
+                        Type::Any
+                    }
+                }
+            }
+            Expression::If(if_exp) => {
+                self.check_if(if_exp);
+                Type::Any
+            }
+            Expression::Call(call_exp) => {
+                let arg_types: Vec<Type> = call_exp
+                    .arguments()
+                    .iter()
+                    .map(|arg| self.check_exp(arg))
+                    .collect();
+                self.check_call(call_exp.name(), &arg_types)
+            }
+            Expression::Prefix(pre_exp) => self.check_prefix(pre_exp),
+            Expression::Infix(infix_exp) => self.check_infix(infix_exp),
+        }
+    }
+
+    // Check the value of the expected type, the elements of an array literal are checked against
+    // the element type one by one, so the mixed elements don't pass as `[any]`.
+    // The type of the literal has the first element that doesn't fit, to be reported.
+    fn check_value(&mut self, value: &Expression, expected: &Type) -> Type {
+        let (Expression::Arr(arr_exp), Type::Array(element)) = (value, expected) else {
+            return self.check_exp(value);
+        };
+
+        let element_types: Vec<Type> = arr_exp
+            .elements()
+            .iter()
+            .map(|e| self.check_value(e, element))
+            .collect();
+        match element_types.iter().find(|ty| !element.accepts(ty)) {
+            Some(ty) => Type::Array(Box::new(ty.clone())),
+            None => Type::Array(Box::new(common_type(&element_types))),
+        }
+    }
+
+    fn check_if(&mut self, if_exp: &IfExp) {
+        let condition_type = self.check_exp(if_exp.condition());
+        if !is_truthy_type(&condition_type) {
+            self.report(
+                if_exp.position(),
+                format!(
+                    "the condition must be `bool`, `int` or `float`, but it's `{}`",
+                    condition_type
+                ),
+            );
+        }
+
+        self.check_block(if_exp.consequence());
+        if let Some(else_exp) = if_exp.else_exp() {
+            self.check_else(else_exp);
+        }
+    }
+
+    fn check_else(&mut self, else_exp: &ElseExp) {
+        if let Some(if_exp) = else_exp.if_exp() {
+            self.check_if(if_exp);
+        }
+        if let Some(block) = else_exp.consequence() {
+            self.check_block(block);
+        }
+    }
+
+    // Check the arguments of the call, the result is the return type of the function.
+    fn check_call(&mut self, name: &IdentifierExp, arg_types: &[Type]) -> Type {
+        let (param_types, return_type) = match self.lookup(name) {
+            Type::Func(param_types, return_type) => (param_types, *return_type),
+            Type::Any => return Type::Any,
+            other => {
+                self.report(
+                    name.position(),
+                    format!("`{}` is `{}`, it can't be called", name, other),
+                );
+                return Type::Any;
+            }
+        };
+
+        if param_types.len() != arg_types.len() {
+            self.report(
+                name.position(),
+                format!(
+                    "function `{}` takes {} argument(s), but {} were given",
+                    name,
+                    param_types.len(),
+                    arg_types.len()
+                ),
+            );
+        }
+
+        for (i, (param_type, arg_type)) in param_types.iter().zip(arg_types).enumerate() {
+            if !param_type.accepts(arg_type) {
+                self.report(
+                    name.position(),
+                    format!(
+                        "argument {} of function `{}` must be `{}`, but it's `{}`",
+                        i + 1,
+                        name,
+                        param_type,
+                        arg_type
+                    ),
+                );
+            }
+        }
+        return_type
+    }
+
+    fn check_prefix(&mut self, pre_exp: &PrefixExp) -> Type {
+        let right = self.check_exp(pre_exp.right());
+        let result = match (pre_exp.operator(), &right) {
+            (TokenType::Minus, Type::Int | Type::Float | Type::Any) => Some(right.clone()),
+            (TokenType::Not, _) => Some(Type::Bool),
+            _ => None,
+        };
+
+        result.unwrap_or_else(|| {
+            self.report(
+                pre_exp.position(),
+                format!("`{}` can't be applied to `{}`", pre_exp.operator(), right),
+            );
+            Type::Any
+        })
+    }
+
+    // The operands that the operators can be applied to are the same as `eval_infix_exp` of the evaluator,
+    // it results in null or false at runtime for the others.
+    fn check_infix(&mut self, infix_exp: &InfixExp) -> Type {
+        let left = self.check_exp(infix_exp.left());
+        let right = self.check_exp(infix_exp.right());
+
+        let result = match infix_exp.operator() {
+            TokenType::Plus | TokenType::Minus | TokenType::Asterisk | TokenType::Slash => {
+                match (&left, &right) {
+                    (Type::Int, Type::Int) => Some(Type::Int),
+                    (Type::Float, r) if r.is_number() => Some(Type::Float),
+                    (l, Type::Float) if l.is_number() => Some(Type::Float),
+                    (Type::Any, r) if r.is_number() || *r == Type::Any => Some(Type::Any),
+                    (l, Type::Any) if l.is_number() => Some(Type::Any),
+                    _ => None,
+                }
+            }
+            // Every value can be compared with `==`, the values of different types are never equal.
+            TokenType::EqualTo | TokenType::NotEqualTo => Some(Type::Bool),
+            TokenType::Less
+            | TokenType::LessThanOrEqualTo
+            | TokenType::Greater
+            | TokenType::GreaterThanOrEqualTo => is_ordered(&left, &right).then_some(Type::Bool),
+            // The result is one of the operands, it's the operand that decides the result.
+            TokenType::And | TokenType::Or => Some(common_type(&[left.clone(), right.clone()])),
+            _ => None,
+        };
+
+        result.unwrap_or_else(|| {
+            self.report(
+                infix_exp.position(),
+                format!(
+                    "`{}` can't be applied to `{}` and `{}`",
+                    infix_exp.operator(),
+                    left,
+                    right
+                ),
+            );
+            Type::Any
+        })
+    }
+
+    // =================== Helper Functions ===================
+
+    fn define(&mut self, name: &str, ty: Type) {
+        self.scopes.last_mut().unwrap().insert(name.to_string(), ty);
+    }
+
+    // Get the type of the name, the undefined name is reported by the resolver.
+    fn lookup(&self, ident: &IdentifierExp) -> Type {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(ident.value()))
+            .cloned()
+            .unwrap_or(Type::Any)
+    }
+
+    // The error without a position is from the nodes that are not created by the parser, it's at the start.
+    fn report(&mut self, position: Option<(usize, usize)>, message: String) {
+        let (line, column) = position.unwrap_or((1, 1));
+        self.errors.push(TypeError {
+            message,
+            line,
+            column,
+        });
+    }
+}
+
+/// Get the type of the function from its annotations, the ones without annotations are `any`.
+pub fn func_type(func_stmt: &FuncStatement) -> Type {
+    let param_types = func_stmt
+        .param_types()
+        .iter()
+        .map(|ty| ty.clone().unwrap_or(Type::Any))
+        .collect();
+    let return_type = func_stmt.return_type().cloned().unwrap_or(Type::Any);
+    Type::Func(param_types, Box::new(return_type))
+}
+
+// Check whether every path of the block ends with a `return`, a `throw`, or an expression
+// whose value is the result of the function, the same as the evaluator.
+fn returns_value(block: &BlockStatement) -> bool {
+    if block.always_returns() {
+        return true;
+    }
+    let last = block.statements().and_then(|stmts| stmts.last());
+    match last.map(|node| node.as_ref()) {
+        Some(Node::Exp(Expression::If(if_exp))) => if_returns_value(if_exp),
+        Some(Node::Exp(_)) => true,
+        Some(Node::Stmt(Statement::Block(block))) => returns_value(block),
+        _ => false,
+    }
+}
+
+fn if_returns_value(if_exp: &IfExp) -> bool {
+    let else_returns = match if_exp.else_exp() {
+        Some(else_exp) => match (else_exp.if_exp(), else_exp.consequence()) {
+            (Some(if_exp), _) => if_returns_value(if_exp),
+            (None, Some(block)) => returns_value(block),
+            (None, None) => false,
+        },
+        None => false,
+    };
+    else_returns && returns_value(if_exp.consequence())
+}
+
+// The builtin functions take any value, and the failed conversion is a runtime error.
+fn builtin_type(name: &str) -> Type {
+    let return_type = match name {
+        "int" => Type::Int,
+        "float" => Type::Float,
+        "bool" => Type::Bool,
+        "char" => Type::Char,
+        "byte" => Type::Byte,
+        "str" | "type" | "error_kind" | "error_message" => Type::Str,
+        "error_trace" => Type::Array(Box::new(Type::Str)),
+        "error" => return Type::Func(vec![Type::Str, Type::Str], Box::new(Type::Any)),
+        _ if name.starts_with("is_") => Type::Bool,
+        _ => Type::Any,
+    };
+    Type::Func(vec![Type::Any], Box::new(return_type))
+}
+
+// The values that are used as conditions, the others are always false.
+fn is_truthy_type(ty: &Type) -> bool {
+    matches!(ty, Type::Bool | Type::Int | Type::Float | Type::Any)
+}
+
+// Check whether the values can be compared by `<`, `>` and so on.
+fn is_ordered(left: &Type, right: &Type) -> bool {
+    match (left, right) {
+        (Type::Any, _) | (_, Type::Any) => true,
+        (l, r) if l.is_number() && r.is_number() => true,
+        (Type::Char, Type::Char) | (Type::Byte, Type::Byte) | (Type::Str, Type::Str) => true,
+        (Type::Array(l), Type::Array(r)) => is_ordered(l, r),
+        _ => false,
+    }
+}
+
+// Get the type that all the types can be used as, it's `any` if there isn't one.
+fn common_type(types: &[Type]) -> Type {
+    let Some(first) = types.first() else {
+        return Type::Any;
+    };
+
+    let mut common = first.clone();
+    for ty in &types[1..] {
+        if common.accepts(ty) {
+            continue;
+        } else if ty.accepts(&common) {
+            common = ty.clone();
+        } else {
+            return Type::Any;
+        }
+    }
+    common
+}
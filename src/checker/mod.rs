@@ -0,0 +1,3 @@
+// The type checker checks the optional type annotations and the inferred types of the AST before it's evaluated.
+// - checker: It infers the types of the expressions, and reports the type errors with their positions.
+pub mod checker;
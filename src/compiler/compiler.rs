@@ -0,0 +1,644 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::ast_v2::expressions::{CallExp, ElseExp, IdentifierExp, IfExp};
+use crate::ast_v2::statements::{BlockStatement, FuncStatement, TryStatement};
+use crate::ast_v2::{Expression, Node, Statement};
+use crate::builtins::builtins;
+use crate::compiler::opcode::{Capture, OpCode};
+use crate::object::object::{BaseValue, CompiledFunction, Object, Str, Value};
+use crate::token::types::TokenType;
+
+// The name of the function that holds the top-level code.
+pub const MAIN_FUNC_NAME: &str = "<main>";
+
+/// The compiled program.
+/// The first function in the function table is the top-level code of the program.
+#[derive(Debug)]
+pub struct Bytecode {
+    constants: Vec<Object>,
+    functions: Vec<Rc<CompiledFunction>>,
+    num_globals: u32,
+}
+
+impl Bytecode {
+    pub fn new(
+        constants: Vec<Object>,
+        functions: Vec<Rc<CompiledFunction>>,
+        num_globals: u32,
+    ) -> Self {
+        Self {
+            constants,
+            functions,
+            num_globals,
+        }
+    }
+
+    pub fn constants(&self) -> &Vec<Object> {
+        &self.constants
+    }
+
+    pub fn functions(&self) -> &Vec<Rc<CompiledFunction>> {
+        &self.functions
+    }
+
+    pub fn num_globals(&self) -> u32 {
+        self.num_globals
+    }
+
+    /// Get the function that holds the top-level code.
+    pub fn main(&self) -> &Rc<CompiledFunction> {
+        &self.functions[0]
+    }
+}
+
+// The state of a function that is being compiled.
+struct FuncScope {
+    name: String,
+    params: Vec<String>,
+    instructions: Vec<OpCode>,
+    // The slot indices of the local variables, the parameters take the first slots.
+    locals: HashMap<String, u32>,
+    num_locals: u32,
+    captures: Vec<Capture>,
+    capture_names: HashMap<String, u32>,
+    lines: Vec<(u32, u32)>,
+    // The finally blocks of the try regions that the code is in, the innermost one is the last.
+    // `None` is a region without a finally block.
+    tries: Vec<Option<BlockStatement>>,
+}
+
+impl FuncScope {
+    fn new(name: String, params: Vec<String>) -> Self {
+        let mut scope = Self {
+            name,
+            params: Vec::new(),
+            instructions: Vec::new(),
+            locals: HashMap::new(),
+            num_locals: 0,
+            captures: Vec::new(),
+            capture_names: HashMap::new(),
+            lines: Vec::new(),
+            tries: Vec::new(),
+        };
+
+        for param in params.iter() {
+            scope.define_local(param);
+        }
+        scope.params = params;
+
+        scope
+    }
+
+    fn define_local(&mut self, name: &str) -> u32 {
+        if let Some(slot) = self.locals.get(name) {
+            return *slot;
+        }
+
+        let slot = self.num_locals;
+        self.locals.insert(name.to_string(), slot);
+        self.num_locals += 1;
+        slot
+    }
+}
+
+/// The compiler translates the AST into bytecode which can be executed by the virtual machine.
+/// The semantics of the bytecode is the same as the [`crate::evaluator_v2::evaluator::Evaluator`]:
+/// - The variables defined in the top-level code are globals, and they are accessed by the slot index.
+/// - The variables defined in a function are locals of the function, a block does not create a new scope.
+/// - A nested function captures the variables of the enclosing functions by value when it's created.
+pub struct Compiler {
+    constants: Vec<Object>,
+    functions: Vec<Rc<CompiledFunction>>,
+    globals: HashMap<String, u32>,
+    // The number of the global slots, a name takes a new one every time it's defined.
+    num_globals: u32,
+    // The global slots of the definitions by their positions.
+    definitions: HashMap<(usize, usize), u32>,
+    // The stack of the functions being compiled, the first one is the top-level code.
+    scopes: Vec<FuncScope>,
+}
+
+impl Default for Compiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Compiler {
+    pub fn new() -> Self {
+        Self {
+            constants: Vec::new(),
+            functions: Vec::new(),
+            globals: HashMap::new(),
+            num_globals: 0,
+            definitions: HashMap::new(),
+            scopes: Vec::new(),
+        }
+    }
+
+    /// Compile the AST into bytecode.
+    pub fn compile(mut self, programs: &[Node]) -> Bytecode {
+        // Reserve the first slot of the function table for the top-level code.
+        self.functions.push(Rc::new(CompiledFunction::new(
+            MAIN_FUNC_NAME.to_string(),
+            Vec::new(),
+            0,
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+        )));
+        self.scopes
+            .push(FuncScope::new(MAIN_FUNC_NAME.to_string(), Vec::new()));
+
+        self.compile_nodes(programs.iter(), true);
+        self.emit(OpCode::Halt);
+
+        let main = self.scopes.pop().unwrap();
+        self.functions[0] = Rc::new(CompiledFunction::new(
+            main.name,
+            main.params,
+            main.num_locals,
+            main.instructions,
+            main.captures,
+            main.lines,
+        ));
+
+        Bytecode::new(self.constants, self.functions, self.num_globals)
+    }
+
+    // Compile the nodes in order, only the value of the last node is kept on the stack if `keep` is true.
+    fn compile_nodes<'a>(&mut self, nodes: impl ExactSizeIterator<Item = &'a Node>, keep: bool) {
+        let len = nodes.len();
+        if len == 0 && keep {
+            self.emit(OpCode::Null);
+        }
+
+        for (i, node) in nodes.enumerate() {
+            self.compile_node(node, keep && i == len - 1);
+        }
+    }
+
+    fn compile_node(&mut self, node: &Node, keep: bool) {
+        match node {
+            Node::Exp(exp) => {
+                self.compile_exp(exp);
+                if !keep {
+                    self.emit(OpCode::Pop);
+                }
+            }
+            Node::Stmt(stmt) => self.compile_stmt(stmt, keep),
+        }
+    }
+
+    // =================== Compile Statement ===================
+
+    fn compile_stmt(&mut self, stmt: &Statement, keep: bool) {
+        match stmt {
+            Statement::Let(let_stmt) => {
+                match let_stmt.value() {
+                    Some(value) => self.compile_exp(value),
+                    None => self.emit(OpCode::Null),
+                }
+                self.define_and_set(let_stmt.ident());
+
+                if keep {
+                    self.emit(OpCode::Null);
+                }
+            }
+            Statement::Return(ret_stmt) => {
+                // The call in tail position reuses the frame of the function,
+                // unless the function has to leave a `try` region after the call.
+                if let Some(Expression::Call(call_exp)) = ret_stmt.value() {
+                    if self.scopes.len() > 1 && self.scopes.last().unwrap().tries.is_empty() {
+                        self.compile_call(call_exp, true);
+                        return;
+                    }
+                }
+
+                match ret_stmt.value() {
+                    Some(value) => self.compile_exp(value),
+                    None => self.emit(OpCode::Null),
+                }
+                self.leave_tries();
+
+                // The `return` in the top-level code stops the program.
+                if self.scopes.len() == 1 {
+                    self.emit(OpCode::Exit);
+                } else {
+                    self.emit(OpCode::Return);
+                }
+            }
+            Statement::Block(block_stmt) => self.compile_block(block_stmt, keep),
+            Statement::Func(func_stmt) => {
+                self.compile_func(func_stmt);
+                self.define_and_set(func_stmt.ident());
+
+                if keep {
+                    self.emit(OpCode::Null);
+                }
+            }
+            Statement::Throw(throw_stmt) => {
+                self.compile_exp(throw_stmt.value());
+                self.emit(OpCode::Throw);
+            }
+            Statement::Try(try_stmt) => self.compile_try(try_stmt, keep),
+            // The code with errors is not compiled.
+            Statement::Error(_) => {
+                if keep {
+                    self.emit(OpCode::Null);
+                }
+            }
+        }
+    }
+
+    fn compile_block(&mut self, block: &BlockStatement, keep: bool) {
+        match block.statements() {
+            Some(stmts) => self.compile_nodes(stmts.iter().map(|s| s.as_ref()), keep),
+            None => {
+                if keep {
+                    self.emit(OpCode::Null);
+                }
+            }
+        }
+    }
+
+    // The body is protected by a handler that jumps to the catch block, or to the finally block
+    // that throws the error again if there is no catch block:
+    //
+    //     Try(handler) body EndTry [finally] Jump(end)
+    //     handler: [Try(rethrow)] SetLocal(e) catch [EndTry finally Jump(end)]
+    //     rethrow: finally Throw
+    //     end:
+    fn compile_try(&mut self, try_stmt: &TryStatement, keep: bool) {
+        let finally = try_stmt.finally();
+
+        let handler = self.emit_jump(OpCode::Try(0));
+        self.compile_region(try_stmt.body(), finally, keep);
+        self.emit(OpCode::EndTry);
+        if let Some(block) = finally {
+            self.compile_block(block, false);
+        }
+        let mut jumps_to_end = vec![self.emit_jump(OpCode::Jump(0))];
+
+        // The error is on the top of the stack when the handler is reached.
+        self.patch_jump(handler);
+        if let Some((ident, block)) = try_stmt.catch() {
+            // The error thrown in the catch block still runs the finally block.
+            let rethrow = finally.map(|_| self.emit_jump(OpCode::Try(0)));
+            // The caught error is only visible in the catch block, it takes a new slot,
+            // so the variable of the same name outside the block is kept.
+            let outer = self.take_binding(ident.value());
+            self.define_and_set(ident);
+            self.compile_region(block, finally, keep);
+            self.restore_binding(ident.value(), outer);
+            if let (Some(rethrow), Some(block)) = (rethrow, finally) {
+                self.emit(OpCode::EndTry);
+                self.compile_block(block, false);
+                jumps_to_end.push(self.emit_jump(OpCode::Jump(0)));
+                self.patch_jump(rethrow);
+            }
+        }
+        if let Some(block) = finally {
+            self.compile_block(block, false);
+            self.emit(OpCode::Throw);
+        }
+
+        for jump in jumps_to_end {
+            self.patch_jump(jump);
+        }
+    }
+
+    // Compile the block in a try region, the finally block runs when it's left by `return`.
+    fn compile_region(&mut self, block: &BlockStatement, finally: Option<&BlockStatement>, keep: bool) {
+        self.scopes.last_mut().unwrap().tries.push(finally.cloned());
+        self.compile_block(block, keep);
+        self.scopes.last_mut().unwrap().tries.pop();
+    }
+
+    // Leave all the try regions of the function before `return`, from the innermost one.
+    // Their handlers are removed and their finally blocks are run, the returned value stays on the stack.
+    fn leave_tries(&mut self) {
+        let tries = self.scopes.last().unwrap().tries.clone();
+        for finally in tries.iter().rev() {
+            // The finally block is not in its own region, a `return` in it doesn't run it again.
+            self.scopes.last_mut().unwrap().tries.pop();
+            self.emit(OpCode::EndTry);
+            if let Some(block) = finally {
+                self.compile_block(block, false);
+            }
+        }
+        self.scopes.last_mut().unwrap().tries = tries;
+    }
+
+    fn compile_func(&mut self, func_stmt: &FuncStatement) {
+        let params: Vec<String> = func_stmt
+            .params()
+            .map(|params| params.iter().map(|p| p.value().to_string()).collect())
+            .unwrap_or_default();
+
+        self.scopes
+            .push(FuncScope::new(func_stmt.name().to_string(), params));
+
+        self.compile_block(func_stmt.body(), true);
+        self.emit(OpCode::Return);
+
+        let scope = self.scopes.pop().unwrap();
+        let index = self.functions.len() as u32;
+        self.functions.push(Rc::new(CompiledFunction::new(
+            scope.name,
+            scope.params,
+            scope.num_locals,
+            scope.instructions,
+            scope.captures,
+            scope.lines,
+        )));
+
+        self.emit(OpCode::Closure(index));
+    }
+
+    // =================== Compile Expression ===================
+
+    fn compile_exp(&mut self, exp: &Expression) {
+        match exp {
+            Expression::Num(num) => {
+                let obj = match num.integer_value() {
+                    Some(value) => Object::Base(BaseValue::Integer(Value::new(value))),
+                    None => Object::Base(BaseValue::Float(Value::new(num.float_value().unwrap()))),
+                };
+                self.emit_constant(obj);
+            }
+            Expression::Boolean(b) => {
+                if b.value() {
+                    self.emit(OpCode::True);
+                } else {
+                    self.emit(OpCode::False);
+                }
+            }
+            Expression::Str(s) => self.emit_constant(Object::Str(Str::new(s.value().to_string()))),
+            Expression::Char(c) => {
+                self.emit_constant(Object::Base(BaseValue::Char(Value::new(c.value()))))
+            }
+            Expression::Byte(b) => {
+                self.emit_constant(Object::Base(BaseValue::Byte(Value::new(b.value()))))
+            }
+            Expression::Interpolated(interp_exp) => {
+                for part in interp_exp.parts() {
+                    self.compile_exp(part);
+                }
+                self.emit(OpCode::Concat(interp_exp.parts().len() as u32));
+            }
+            Expression::Arr(arr_exp) => {
+                for elem in arr_exp.elements() {
+                    self.compile_exp(elem);
+                }
+                self.emit(OpCode::Array(arr_exp.elements().len() as u32));
+            }
+            Expression::ArrIndex(arr_index) => {
+                self.compile_get(arr_index.name().value());
+                self.compile_exp(arr_index.index());
+                self.emit(OpCode::Index);
+            }
+            Expression::Identifier(ident) => self.compile_get(ident.value()),
+            Expression::Prefix(pre_exp) => {
+                self.compile_exp(pre_exp.right());
+                match pre_exp.operator() {
+                    TokenType::Not => self.emit(OpCode::Not),
+                    TokenType::Minus => self.emit(OpCode::Neg),
+                    // The other prefix operators are evaluated to null.
+                    _ => {
+                        self.emit(OpCode::Pop);
+                        self.emit(OpCode::Null);
+                    }
+                }
+            }
+            // The right operand is skipped if the left one decides the result of `&&` or `||`.
+            Expression::Infix(infix_exp)
+                if matches!(infix_exp.operator(), TokenType::And | TokenType::Or) =>
+            {
+                self.compile_exp(infix_exp.left());
+                let jump = match infix_exp.operator() {
+                    TokenType::And => self.emit_jump(OpCode::JumpIfFalseOrPop(0)),
+                    _ => self.emit_jump(OpCode::JumpIfTrueOrPop(0)),
+                };
+                self.compile_exp(infix_exp.right());
+                self.patch_jump(jump);
+            }
+            Expression::Infix(infix_exp) => {
+                self.compile_exp(infix_exp.left());
+                self.compile_exp(infix_exp.right());
+
+                let op = match infix_exp.operator() {
+                    TokenType::Plus => OpCode::Add,
+                    TokenType::Minus => OpCode::Sub,
+                    TokenType::Asterisk => OpCode::Mul,
+                    TokenType::Slash => OpCode::Div,
+                    TokenType::EqualTo => OpCode::Equal,
+                    TokenType::NotEqualTo => OpCode::NotEqual,
+                    TokenType::Less => OpCode::Less,
+                    TokenType::LessThanOrEqualTo => OpCode::LessEqual,
+                    TokenType::Greater => OpCode::Greater,
+                    TokenType::GreaterThanOrEqualTo => OpCode::GreaterEqual,
+                    // The other infix operators are evaluated to null.
+                    _ => {
+                        self.emit(OpCode::Pop);
+                        self.emit(OpCode::Pop);
+                        OpCode::Null
+                    }
+                };
+                self.emit(op);
+            }
+            Expression::If(if_exp) => self.compile_if(if_exp),
+            Expression::Call(call_exp) => self.compile_call(call_exp, false),
+        }
+    }
+
+    fn compile_call(&mut self, call_exp: &CallExp, tail: bool) {
+        self.compile_get(call_exp.name().value());
+        for arg in call_exp.arguments() {
+            self.compile_exp(arg);
+        }
+        self.mark_line(call_exp.line());
+
+        let argc = call_exp.arguments().len() as u32;
+        self.emit(if tail {
+            OpCode::TailCall(argc)
+        } else {
+            OpCode::Call(argc)
+        });
+    }
+
+    fn compile_if(&mut self, if_exp: &IfExp) {
+        self.compile_exp(if_exp.condition());
+        let jump_if_false = self.emit_jump(OpCode::JumpIfFalse(0));
+
+        self.compile_block(if_exp.consequence(), true);
+        let jump_to_end = self.emit_jump(OpCode::Jump(0));
+
+        self.patch_jump(jump_if_false);
+        match if_exp.else_exp() {
+            Some(else_exp) => self.compile_else(else_exp),
+            None => self.emit(OpCode::Null),
+        }
+        self.patch_jump(jump_to_end);
+    }
+
+    fn compile_else(&mut self, else_exp: &ElseExp) {
+        match (else_exp.if_exp(), else_exp.consequence()) {
+            (Some(if_exp), _) => self.compile_if(if_exp),
+            (None, Some(block)) => self.compile_block(block, true),
+            (None, None) => self.emit(OpCode::Null),
+        }
+    }
+
+    // =================== Helper Functions ===================
+
+    fn emit(&mut self, op: OpCode) {
+        self.scopes.last_mut().unwrap().instructions.push(op);
+    }
+
+    // Record the line number of the next instruction in the debug line table.
+    fn mark_line(&mut self, line: usize) {
+        let scope = self.scopes.last_mut().unwrap();
+        let index = scope.instructions.len() as u32;
+        scope.lines.push((index, line as u32));
+    }
+
+    fn emit_constant(&mut self, obj: Object) {
+        let index = self.constants.len() as u32;
+        self.constants.push(obj);
+        self.emit(OpCode::Constant(index));
+    }
+
+    // Emit a jump instruction with a placeholder target, and return its position to patch later.
+    fn emit_jump(&mut self, op: OpCode) -> usize {
+        self.emit(op);
+        self.scopes.last().unwrap().instructions.len() - 1
+    }
+
+    // Set the target of the jump instruction to the next instruction.
+    fn patch_jump(&mut self, pos: usize) {
+        let instructions = &mut self.scopes.last_mut().unwrap().instructions;
+        let target = instructions.len() as u32;
+        instructions[pos] = match instructions[pos] {
+            OpCode::Jump(_) => OpCode::Jump(target),
+            OpCode::JumpIfFalse(_) => OpCode::JumpIfFalse(target),
+            OpCode::JumpIfFalseOrPop(_) => OpCode::JumpIfFalseOrPop(target),
+            OpCode::JumpIfTrueOrPop(_) => OpCode::JumpIfTrueOrPop(target),
+            OpCode::Try(_) => OpCode::Try(target),
+            op => op,
+        };
+    }
+
+    fn global_slot(&mut self, name: &str) -> u32 {
+        if let Some(slot) = self.globals.get(name) {
+            return *slot;
+        }
+
+        let slot = self.num_globals;
+        self.globals.insert(name.to_string(), slot);
+        self.num_globals += 1;
+        slot
+    }
+
+    // Define the variable in the current scope, and set it with the top value of the stack.
+    fn define_and_set(&mut self, ident: &IdentifierExp) {
+        if self.scopes.len() == 1 {
+            let slot = self.define_global(ident);
+            self.emit(OpCode::SetGlobal(slot));
+        } else {
+            let slot = self.scopes.last_mut().unwrap().define_local(ident.value());
+            self.emit(OpCode::SetLocal(slot));
+        }
+    }
+
+    // The redefined global takes a new slot, the same as the snapshot of the evaluator,
+    // so the functions defined before keep reading the old value.
+    // A definition in a finally block is compiled more than once, and it keeps the slot of its position.
+    fn define_global(&mut self, ident: &IdentifierExp) -> u32 {
+        let slot = match ident.position() {
+            Some(position) => match self.definitions.get(&position) {
+                Some(slot) => *slot,
+                None => {
+                    let slot = self.num_globals;
+                    self.definitions.insert(position, slot);
+                    self.num_globals += 1;
+                    slot
+                }
+            },
+            None => return self.global_slot(ident.value()),
+        };
+
+        self.globals.insert(ident.value().to_string(), slot);
+        slot
+    }
+
+    // Remove the variable from the current scope, its slot is returned to be restored later.
+    fn take_binding(&mut self, name: &str) -> Option<u32> {
+        if self.scopes.len() == 1 {
+            self.globals.remove(name)
+        } else {
+            self.scopes.last_mut().unwrap().locals.remove(name)
+        }
+    }
+
+    // Bind the name to the slot that is taken before, or remove it if there is no slot.
+    fn restore_binding(&mut self, name: &str, slot: Option<u32>) {
+        let bindings = if self.scopes.len() == 1 {
+            &mut self.globals
+        } else {
+            &mut self.scopes.last_mut().unwrap().locals
+        };
+        match slot {
+            Some(slot) => bindings.insert(name.to_string(), slot),
+            None => bindings.remove(name),
+        };
+    }
+
+    // Push the value of the variable, the variable that is not found will be treated as a global.
+    fn compile_get(&mut self, name: &str) {
+        let depth = self.scopes.len() - 1;
+        let op = match self.resolve(depth, name) {
+            Some(op) => op,
+            // The builtin function is shadowed by the global variable that is defined before.
+            None => match builtins::lookup(name) {
+                Some(index) if !self.globals.contains_key(name) => OpCode::GetBuiltin(index as u32),
+                _ => OpCode::GetGlobal(self.global_slot(name)),
+            },
+        };
+        self.emit(op);
+    }
+
+    // Resolve the variable in the function at the depth, the top-level code is at depth 0.
+    fn resolve(&mut self, depth: usize, name: &str) -> Option<OpCode> {
+        if depth == 0 {
+            return None;
+        }
+
+        let scope = &self.scopes[depth];
+        if let Some(slot) = scope.locals.get(name) {
+            return Some(OpCode::GetLocal(*slot));
+        }
+        if scope.name == name {
+            return Some(OpCode::CurrentClosure);
+        }
+        if let Some(index) = scope.capture_names.get(name) {
+            return Some(OpCode::GetCapture(*index));
+        }
+
+        // Capture the variable from the enclosing function.
+        let capture = match self.resolve(depth - 1, name)? {
+            OpCode::GetLocal(slot) => Capture::Local(slot),
+            OpCode::GetCapture(index) => Capture::Outer(index),
+            OpCode::CurrentClosure => Capture::Current,
+            _ => return None,
+        };
+
+        let scope = &mut self.scopes[depth];
+        let index = scope.captures.len() as u32;
+        scope.captures.push(capture);
+        scope.capture_names.insert(name.to_string(), index);
+
+        Some(OpCode::GetCapture(index))
+    }
+}
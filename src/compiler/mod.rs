@@ -0,0 +1,7 @@
+// The compiler translates the AST into bytecode, which is executed by the virtual machine in the `vm` module.
+// - opcode: The instruction set of the virtual machine.
+// - compiler: It compiles the AST into the bytecode.
+// - wocc: The binary file format of the precompiled scripts.
+pub mod compiler;
+pub mod opcode;
+pub mod wocc;
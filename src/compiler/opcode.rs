@@ -0,0 +1,102 @@
+use std::fmt::{self, Display, Formatter};
+
+/// The instruction set of the virtual machine.
+/// The operand of an instruction is stored in the variant, so that an instruction is a fixed-size value,
+/// and the VM can decode it without reading extra bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpCode {
+    // ===== Constants =====
+    // Push the constant in the constant pool by its index.
+    Constant(u32),
+    Null,
+    True,
+    False,
+
+    // Discard the top value of the stack.
+    Pop,
+
+    // ===== Variables =====
+    GetGlobal(u32),
+    SetGlobal(u32),
+    GetLocal(u32),
+    SetLocal(u32),
+    // Get the value captured by the closure when it was created.
+    GetCapture(u32),
+    // Push the closure that is running, it's used by the recursive call of a nested function.
+    CurrentClosure,
+    // Push the builtin function by its index in the builtin table.
+    GetBuiltin(u32),
+
+    // ===== Array =====
+    // Build an array with the top n values of the stack.
+    Array(u32),
+    // Pop the index and the array, and push the element.
+    Index,
+
+    // ===== String =====
+    // Convert the top n values of the stack to strings, and push the concatenation of them.
+    Concat(u32),
+
+    // ===== Calculation =====
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Not,
+    Neg,
+
+    // ===== Control flow =====
+    // Jump to the instruction by its index in the function.
+    Jump(u32),
+    // Pop the condition and jump if it's not truthy.
+    JumpIfFalse(u32),
+    // Jump if the top value is not truthy and keep it, otherwise pop it. It's used by `&&`.
+    JumpIfFalseOrPop(u32),
+    // Jump if the top value is truthy and keep it, otherwise pop it. It's used by `||`.
+    JumpIfTrueOrPop(u32),
+    // Call the function with n arguments, the function is below the arguments in the stack.
+    Call(u32),
+    // Return the top value of the stack to the caller.
+    Return,
+    // Call the function with n arguments in place of the current frame, and return its result.
+    // It's used by `return f(...)`, so the tail recursion doesn't grow the frames.
+    TailCall(u32),
+    // Create a closure with the function in the function table by its index.
+    Closure(u32),
+    // Stop the program, the top value of the stack is the result.
+    Halt,
+    // Stop the program by a top-level `return`, the result is wrapped in `Object::Return`.
+    Exit,
+
+    // ===== Errors =====
+    // Install a handler that catches the errors until `EndTry`, the error is pushed and the
+    // execution jumps to the instruction by its index in the function.
+    Try(u32),
+    // Remove the handler installed by the last `Try`.
+    EndTry,
+    // Pop the value and throw it as an error.
+    Throw,
+}
+
+impl Display for OpCode {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Where a closure gets the captured value from when it's created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capture {
+    // A local variable of the enclosing function.
+    Local(u32),
+    // A value captured by the enclosing function.
+    Outer(u32),
+    // The enclosing function itself.
+    Current,
+}
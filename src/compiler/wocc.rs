@@ -0,0 +1,496 @@
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::builtins::builtins::BUILTINS;
+use crate::compiler::compiler::Bytecode;
+use crate::compiler::opcode::{Capture, OpCode};
+use crate::object::object::{BaseValue, CompiledFunction, Object, Str, Value};
+
+// The extension of the precompiled script file.
+pub const WOCC_EXTENSION: &str = "wocc";
+
+// The magic number at the beginning of every `.wocc` file.
+pub const MAGIC: &[u8; 4] = b"WOCC";
+
+// The version of the file format, a file with a different version is rejected.
+// It must be increased whenever the layout of the file or the instruction set changes.
+pub const FORMAT_VERSION: u16 = 4;
+
+// The flag shows that the file contains the debug line table.
+const FLAG_DEBUG: u16 = 0b1;
+
+// The tags of the constants in the constant pool.
+const CONST_INTEGER: u8 = 0;
+const CONST_FLOAT: u8 = 1;
+const CONST_STRING: u8 = 2;
+const CONST_CHAR: u8 = 3;
+const CONST_BYTE: u8 = 4;
+
+// The tags of the captures of a function.
+const CAPTURE_LOCAL: u8 = 0;
+const CAPTURE_OUTER: u8 = 1;
+const CAPTURE_CURRENT: u8 = 2;
+
+/// Serialize the bytecode into the `.wocc` binary format.
+/// All the integers are stored in little-endian, and the layout is:
+///
+/// ```text
+/// header:         magic "WOCC" | version: u16 | flags: u16 | num_globals: u32
+/// constant pool:  count: u32 | (tag: u8 | value)*
+/// function table: count: u32 | (name | params | num_locals: u32 | captures | instructions)*
+/// debug lines:    (count: u32 | (instruction index: u32 | line: u32)*)* for each function, if flags has debug
+/// checksum:       u32, the FNV-1a hash of all the bytes before it
+/// ```
+///
+/// A string is stored as its length in bytes (u32) followed by the UTF-8 bytes.
+pub fn encode(bytecode: &Bytecode, debug: bool) -> Vec<u8> {
+    let mut w = Writer { buf: Vec::new() };
+
+    // ===== Header =====
+    w.buf.extend_from_slice(MAGIC);
+    w.u16(FORMAT_VERSION);
+    w.u16(if debug { FLAG_DEBUG } else { 0 });
+    w.u32(bytecode.num_globals());
+
+    // ===== Constant pool =====
+    w.u32(bytecode.constants().len() as u32);
+    for constant in bytecode.constants() {
+        match constant {
+            Object::Base(BaseValue::Integer(v)) => {
+                w.u8(CONST_INTEGER);
+                w.buf.extend_from_slice(&v.value().to_le_bytes());
+            }
+            Object::Base(BaseValue::Float(v)) => {
+                w.u8(CONST_FLOAT);
+                w.buf.extend_from_slice(&v.value().to_le_bytes());
+            }
+            Object::Str(s) => {
+                w.u8(CONST_STRING);
+                w.str(s.value());
+            }
+            Object::Base(BaseValue::Char(v)) => {
+                w.u8(CONST_CHAR);
+                w.u32(*v.value() as u32);
+            }
+            Object::Base(BaseValue::Byte(v)) => {
+                w.u8(CONST_BYTE);
+                w.u8(*v.value());
+            }
+            // The compiler only puts numbers, strings, chars and bytes into the constant pool.
+            _ => unreachable!("unsupported constant: {:?}", constant),
+        }
+    }
+
+    // ===== Function table =====
+    w.u32(bytecode.functions().len() as u32);
+    for func in bytecode.functions() {
+        w.str(func.name());
+        w.u32(func.params().len() as u32);
+        for param in func.params() {
+            w.str(param);
+        }
+        w.u32(func.num_locals());
+
+        w.u32(func.captures().len() as u32);
+        for capture in func.captures() {
+            match capture {
+                Capture::Local(slot) => {
+                    w.u8(CAPTURE_LOCAL);
+                    w.u32(*slot);
+                }
+                Capture::Outer(index) => {
+                    w.u8(CAPTURE_OUTER);
+                    w.u32(*index);
+                }
+                Capture::Current => w.u8(CAPTURE_CURRENT),
+            }
+        }
+
+        w.u32(func.instructions().len() as u32);
+        for op in func.instructions() {
+            w.op(op);
+        }
+    }
+
+    // ===== Debug line table =====
+    if debug {
+        for func in bytecode.functions() {
+            w.u32(func.lines().len() as u32);
+            for (index, line) in func.lines() {
+                w.u32(*index);
+                w.u32(*line);
+            }
+        }
+    }
+
+    let checksum = fnv1a(&w.buf);
+    w.u32(checksum);
+
+    w.buf
+}
+
+/// Deserialize the bytecode from the `.wocc` binary format.
+/// The file is validated before it's returned, so the VM can trust all the indices in the bytecode.
+pub fn decode(bytes: &[u8]) -> Result<Bytecode, String> {
+    // ===== Header =====
+    if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+        return Err("not a wocc file: bad magic number".to_string());
+    }
+    if bytes.len() < MAGIC.len() + 2 + 4 {
+        return Err("corrupt wocc file: truncated header".to_string());
+    }
+
+    let mut r = Reader {
+        bytes,
+        pos: MAGIC.len(),
+    };
+    let version = r.u16()?;
+    if version != FORMAT_VERSION {
+        return Err(format!(
+            "unsupported wocc version {}, expected version {}",
+            version, FORMAT_VERSION
+        ));
+    }
+
+    let (body, checksum) = bytes.split_at(bytes.len() - 4);
+    if fnv1a(body) != u32::from_le_bytes(checksum.try_into().unwrap()) {
+        return Err("corrupt wocc file: checksum mismatch".to_string());
+    }
+    // The checksum is not a part of the content.
+    r.bytes = body;
+
+    let flags = r.u16()?;
+    if flags & !FLAG_DEBUG != 0 {
+        return Err(format!("corrupt wocc file: unknown flags {:#06b}", flags));
+    }
+    let num_globals = r.u32()?;
+
+    // ===== Constant pool =====
+    let count = r.u32()?;
+    let mut constants = Vec::new();
+    for _ in 0..count {
+        let constant = match r.u8()? {
+            CONST_INTEGER => Object::Base(BaseValue::Integer(Value::new(i64::from_le_bytes(
+                r.take(8)?.try_into().unwrap(),
+            )))),
+            CONST_FLOAT => Object::Base(BaseValue::Float(Value::new(f64::from_le_bytes(
+                r.take(8)?.try_into().unwrap(),
+            )))),
+            CONST_STRING => Object::Str(Str::new(r.str()?)),
+            CONST_CHAR => match char::from_u32(r.u32()?) {
+                Some(c) => Object::Base(BaseValue::Char(Value::new(c))),
+                None => return Err("corrupt wocc file: invalid char constant".to_string()),
+            },
+            CONST_BYTE => Object::Base(BaseValue::Byte(Value::new(r.u8()?))),
+            tag => return Err(format!("corrupt wocc file: unknown constant tag {}", tag)),
+        };
+        constants.push(constant);
+    }
+
+    // ===== Function table =====
+    let count = r.u32()?;
+    if count == 0 {
+        return Err("corrupt wocc file: there is no main function".to_string());
+    }
+
+    let mut funcs = Vec::new();
+    for _ in 0..count {
+        let name = r.str()?;
+        let num_params = r.u32()?;
+        let mut params = Vec::new();
+        for _ in 0..num_params {
+            params.push(r.str()?);
+        }
+        let num_locals = r.u32()?;
+
+        let num_captures = r.u32()?;
+        let mut captures = Vec::new();
+        for _ in 0..num_captures {
+            let capture = match r.u8()? {
+                CAPTURE_LOCAL => Capture::Local(r.u32()?),
+                CAPTURE_OUTER => Capture::Outer(r.u32()?),
+                CAPTURE_CURRENT => Capture::Current,
+                tag => return Err(format!("corrupt wocc file: unknown capture tag {}", tag)),
+            };
+            captures.push(capture);
+        }
+
+        let num_instructions = r.u32()?;
+        let mut instructions = Vec::new();
+        for _ in 0..num_instructions {
+            instructions.push(r.op()?);
+        }
+
+        funcs.push((name, params, num_locals, captures, instructions));
+    }
+
+    // ===== Debug line table =====
+    let mut lines = vec![Vec::new(); funcs.len()];
+    if flags & FLAG_DEBUG != 0 {
+        for table in lines.iter_mut() {
+            let count = r.u32()?;
+            for _ in 0..count {
+                table.push((r.u32()?, r.u32()?));
+            }
+        }
+    }
+
+    if r.pos != r.bytes.len() {
+        return Err("corrupt wocc file: unexpected trailing bytes".to_string());
+    }
+
+    let functions: Vec<Rc<CompiledFunction>> = funcs
+        .into_iter()
+        .zip(lines)
+        .map(
+            |((name, params, num_locals, captures, instructions), lines)| {
+                Rc::new(CompiledFunction::new(
+                    name,
+                    params,
+                    num_locals,
+                    instructions,
+                    captures,
+                    lines,
+                ))
+            },
+        )
+        .collect();
+
+    let bytecode = Bytecode::new(constants, functions, num_globals);
+    validate(&bytecode)?;
+
+    Ok(bytecode)
+}
+
+/// Write the bytecode to the `.wocc` file.
+pub fn write_file(path: &Path, bytecode: &Bytecode, debug: bool) -> Result<(), String> {
+    fs::write(path, encode(bytecode, debug))
+        .map_err(|e| format!("failed to write '{}': {}", path.display(), e))
+}
+
+/// Read the bytecode from the `.wocc` file.
+pub fn read_file(path: &Path) -> Result<Bytecode, String> {
+    let bytes =
+        fs::read(path).map_err(|e| format!("failed to read '{}': {}", path.display(), e))?;
+    decode(&bytes).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// Check that all the indices in the instructions are in range.
+fn validate(bytecode: &Bytecode) -> Result<(), String> {
+    let num_constants = bytecode.constants().len() as u32;
+    let num_functions = bytecode.functions().len() as u32;
+
+    if bytecode.main().arity() != 0 || !bytecode.main().captures().is_empty() {
+        return Err("corrupt wocc file: invalid main function".to_string());
+    }
+
+    for func in bytecode.functions() {
+        let name = func.name();
+        let len = func.instructions().len() as u32;
+        if func.num_locals() < func.arity() as u32 {
+            return Err(format!("corrupt wocc file: invalid locals in `{}`", name));
+        }
+
+        for op in func.instructions() {
+            let valid = match *op {
+                OpCode::Constant(index) => index < num_constants,
+                OpCode::GetGlobal(slot) | OpCode::SetGlobal(slot) => slot < bytecode.num_globals(),
+                OpCode::GetLocal(slot) | OpCode::SetLocal(slot) => slot < func.num_locals(),
+                OpCode::GetCapture(index) => index < func.captures().len() as u32,
+                OpCode::GetBuiltin(index) => (index as usize) < BUILTINS.len(),
+                OpCode::Jump(target)
+                | OpCode::JumpIfFalse(target)
+                | OpCode::JumpIfFalseOrPop(target)
+                | OpCode::JumpIfTrueOrPop(target)
+                | OpCode::Try(target) => target < len,
+                OpCode::Closure(index) => index > 0 && index < num_functions,
+                _ => true,
+            };
+            if !valid {
+                return Err(format!(
+                    "corrupt wocc file: invalid instruction `{}` in `{}`",
+                    op, name
+                ));
+            }
+        }
+
+        // A function must end with an instruction that leaves it.
+        match func.instructions().last() {
+            Some(OpCode::Return) | Some(OpCode::Halt) | Some(OpCode::Exit) => {}
+            _ => {
+                return Err(format!(
+                    "corrupt wocc file: function `{}` does not end properly",
+                    name
+                ))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// The 32-bit FNV-1a hash.
+fn fnv1a(bytes: &[u8]) -> u32 {
+    let mut hash: u32 = 0x811c9dc5;
+    for b in bytes {
+        hash ^= *b as u32;
+        hash = hash.wrapping_mul(0x01000193);
+    }
+    hash
+}
+
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, v: u8) {
+        self.buf.push(v);
+    }
+
+    fn u16(&mut self, v: u16) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn u32(&mut self, v: u32) {
+        self.buf.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn str(&mut self, s: &str) {
+        self.u32(s.len() as u32);
+        self.buf.extend_from_slice(s.as_bytes());
+    }
+
+    // An instruction is stored as the opcode (u8) followed by the operand (u32) if it has one.
+    fn op(&mut self, op: &OpCode) {
+        let (code, operand) = match *op {
+            OpCode::Constant(v) => (0, Some(v)),
+            OpCode::Null => (1, None),
+            OpCode::True => (2, None),
+            OpCode::False => (3, None),
+            OpCode::Pop => (4, None),
+            OpCode::GetGlobal(v) => (5, Some(v)),
+            OpCode::SetGlobal(v) => (6, Some(v)),
+            OpCode::GetLocal(v) => (7, Some(v)),
+            OpCode::SetLocal(v) => (8, Some(v)),
+            OpCode::GetCapture(v) => (9, Some(v)),
+            OpCode::CurrentClosure => (10, None),
+            OpCode::Array(v) => (11, Some(v)),
+            OpCode::Index => (12, None),
+            OpCode::Add => (13, None),
+            OpCode::Sub => (14, None),
+            OpCode::Mul => (15, None),
+            OpCode::Div => (16, None),
+            OpCode::Equal => (17, None),
+            OpCode::NotEqual => (18, None),
+            OpCode::Less => (19, None),
+            OpCode::LessEqual => (20, None),
+            OpCode::Greater => (21, None),
+            OpCode::GreaterEqual => (22, None),
+            OpCode::JumpIfFalseOrPop(v) => (23, Some(v)),
+            OpCode::JumpIfTrueOrPop(v) => (24, Some(v)),
+            OpCode::Not => (25, None),
+            OpCode::Neg => (26, None),
+            OpCode::Jump(v) => (27, Some(v)),
+            OpCode::JumpIfFalse(v) => (28, Some(v)),
+            OpCode::Call(v) => (29, Some(v)),
+            OpCode::Return => (30, None),
+            OpCode::Closure(v) => (31, Some(v)),
+            OpCode::Halt => (32, None),
+            OpCode::Exit => (33, None),
+            OpCode::Concat(v) => (34, Some(v)),
+            OpCode::GetBuiltin(v) => (35, Some(v)),
+            OpCode::Try(v) => (36, Some(v)),
+            OpCode::EndTry => (37, None),
+            OpCode::Throw => (38, None),
+            OpCode::TailCall(v) => (39, Some(v)),
+        };
+
+        self.u8(code);
+        if let Some(v) = operand {
+            self.u32(v);
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], String> {
+        if self.bytes.len() - self.pos < len {
+            return Err("corrupt wocc file: unexpected end of file".to_string());
+        }
+        let slice = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn str(&mut self) -> Result<String, String> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?.to_vec();
+        String::from_utf8(bytes).map_err(|_| "corrupt wocc file: invalid UTF-8 string".to_string())
+    }
+
+    fn op(&mut self) -> Result<OpCode, String> {
+        let op = match self.u8()? {
+            0 => OpCode::Constant(self.u32()?),
+            1 => OpCode::Null,
+            2 => OpCode::True,
+            3 => OpCode::False,
+            4 => OpCode::Pop,
+            5 => OpCode::GetGlobal(self.u32()?),
+            6 => OpCode::SetGlobal(self.u32()?),
+            7 => OpCode::GetLocal(self.u32()?),
+            8 => OpCode::SetLocal(self.u32()?),
+            9 => OpCode::GetCapture(self.u32()?),
+            10 => OpCode::CurrentClosure,
+            11 => OpCode::Array(self.u32()?),
+            12 => OpCode::Index,
+            13 => OpCode::Add,
+            14 => OpCode::Sub,
+            15 => OpCode::Mul,
+            16 => OpCode::Div,
+            17 => OpCode::Equal,
+            18 => OpCode::NotEqual,
+            19 => OpCode::Less,
+            20 => OpCode::LessEqual,
+            21 => OpCode::Greater,
+            22 => OpCode::GreaterEqual,
+            23 => OpCode::JumpIfFalseOrPop(self.u32()?),
+            24 => OpCode::JumpIfTrueOrPop(self.u32()?),
+            25 => OpCode::Not,
+            26 => OpCode::Neg,
+            27 => OpCode::Jump(self.u32()?),
+            28 => OpCode::JumpIfFalse(self.u32()?),
+            29 => OpCode::Call(self.u32()?),
+            30 => OpCode::Return,
+            31 => OpCode::Closure(self.u32()?),
+            32 => OpCode::Halt,
+            33 => OpCode::Exit,
+            34 => OpCode::Concat(self.u32()?),
+            35 => OpCode::GetBuiltin(self.u32()?),
+            36 => OpCode::Try(self.u32()?),
+            37 => OpCode::EndTry,
+            38 => OpCode::Throw,
+            39 => OpCode::TailCall(self.u32()?),
+            code => return Err(format!("corrupt wocc file: unknown opcode {}", code)),
+        };
+        Ok(op)
+    }
+}
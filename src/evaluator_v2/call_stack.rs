@@ -0,0 +1,96 @@
+use std::cell::{Cell, RefCell};
+
+use crate::object::object::{CallFrame, Error};
+
+/// The maximum depth of the nested function calls by default.
+pub const DEFAULT_MAX_DEPTH: usize = 1000;
+
+/// The Rust stack that the nested calls can take by default. It's half of the 2 MiB stack of a
+/// thread spawned by `std::thread`, the other half is left for the host and the innermost call.
+pub const DEFAULT_STACK_LIMIT: usize = 1024 * 1024;
+
+/// The Rust stack that is enough for a call of the evaluator, it's about 7 KiB in the debug build,
+/// and the nested expressions in the function take more. The host that runs the scripts with a
+/// larger maximum depth should give the evaluator a thread with the stack of `depth * STACK_PER_CALL`,
+/// and raise the stack limit with it.
+pub const STACK_PER_CALL: usize = 64 * 1024;
+
+/// CallStack is the stack of the function calls that are being evaluated, the innermost one is on top.
+/// It's shared by the evaluators of all the functions, so that the depth of the recursion is limited,
+/// and a deep recursion of the script raises a "stack overflow" error instead of crashing the host.
+///
+/// Every call of the evaluator nests the Rust calls, and how much stack a call takes depends on
+/// the function, so besides the depth, the stack that the calls have taken is limited too.
+pub struct CallStack {
+    frames: RefCell<Vec<CallFrame>>,
+    max_depth: usize,
+    stack_limit: usize,
+    // The address in the Rust stack where the outermost call is entered.
+    stack_base: Cell<usize>,
+}
+
+impl CallStack {
+    pub fn new(max_depth: usize, stack_limit: usize) -> Self {
+        Self {
+            frames: RefCell::new(Vec::new()),
+            max_depth,
+            stack_limit,
+            stack_base: Cell::new(0),
+        }
+    }
+
+    /// Enter the function call, it fails if the stack is already at the maximum depth,
+    /// or the calls have taken more Rust stack than the limit.
+    pub fn push(&self, frame: CallFrame) -> Result<(), Error> {
+        let mut frames = self.frames.borrow_mut();
+        if frames.len() >= self.max_depth {
+            return Err(Error::new(
+                "RuntimeError",
+                format!("stack overflow, the maximum call depth is {}", self.max_depth),
+            ));
+        }
+
+        // The stack grows downwards on the common platforms, the distance is used either way.
+        let marker = 0u8;
+        let address = &marker as *const u8 as usize;
+        if frames.is_empty() {
+            self.stack_base.set(address);
+        } else if self.stack_base.get().abs_diff(address) > self.stack_limit {
+            return Err(Error::new(
+                "RuntimeError",
+                format!(
+                    "stack overflow, the calls take more than {} KiB of the stack",
+                    self.stack_limit / 1024
+                ),
+            ));
+        }
+
+        frames.push(frame);
+        Ok(())
+    }
+
+    /// Replace the innermost function call with the call in its tail position.
+    pub fn replace(&self, frame: CallFrame) {
+        if let Some(top) = self.frames.borrow_mut().last_mut() {
+            *top = frame;
+        }
+    }
+
+    /// Leave the innermost function call.
+    pub fn pop(&self) -> Option<CallFrame> {
+        self.frames.borrow_mut().pop()
+    }
+
+    /// Get the number of the function calls that are being evaluated.
+    pub fn depth(&self) -> usize {
+        self.frames.borrow().len()
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub fn stack_limit(&self) -> usize {
+        self.stack_limit
+    }
+}
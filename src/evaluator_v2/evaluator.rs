@@ -1,30 +1,86 @@
+use std::rc::Rc;
+
 use crate::ast_v2::{Expression, Node, Statement};
 use crate::ast_v2::expressions::{
-    ArrayExp, ArrayIndexExp, CallExp, ElseExp, IdentifierExp, IfExp, InfixExp, PrefixExp,
+    ArrayExp, ArrayIndexExp, CallExp, ElseExp, IdentifierExp, IfExp, InfixExp, InterpolatedExp,
+    PrefixExp,
+};
+use crate::ast_v2::statements::{
+    BlockStatement, FuncStatement, LetStatement, ReturnStatement, ThrowStatement, TryStatement,
 };
-use crate::ast_v2::statements::{BlockStatement, FuncStatement, LetStatement, ReturnStatement};
+use crate::builtins::builtins::BUILTINS;
+use crate::evaluator_v2::call_stack::{CallStack, DEFAULT_MAX_DEPTH, DEFAULT_STACK_LIMIT};
 use crate::evaluator_v2::scope::scope::Scope;
-use crate::object::object::{Array, BaseValue, Function, Object, Str, Value};
+use crate::object::object::{Array, BaseValue, CallFrame, Error, Function, Object, Str, Value};
 use crate::token::types::TokenType;
 
+/// The tree-walking evaluator, the programs must be resolved by the
+/// [`crate::resolver::resolver::Resolver`] first, so that the variables can be accessed by their slots.
 #[derive(Clone)]
 pub struct Evaluator {
     scope: Scope,
+    // The function calls that are being evaluated, it's shared with the evaluators of the functions.
+    calls: Rc<CallStack>,
 }
 
 impl Evaluator {
-    pub fn new(parent_scope: Option<Box<Scope>>) -> Self {
+    /// Creates a new [`Evaluator`], the top-level evaluator without the parent scope is enclosed by
+    /// the scope of the builtin functions, the same as the resolver.
+    pub fn new(parent_scope: Option<Rc<Scope>>) -> Self {
+        let parent_scope = parent_scope.or_else(|| {
+            let builtins = Scope::new(None);
+            for (slot, builtin) in BUILTINS.iter().enumerate() {
+                builtins.set(slot, Object::Builtin(*builtin));
+            }
+            Some(Rc::new(builtins))
+        });
+
         Self {
             scope: Scope::new(parent_scope),
+            calls: Rc::new(CallStack::new(DEFAULT_MAX_DEPTH, DEFAULT_STACK_LIMIT)),
         }
     }
 
+    /// Set the maximum depth of the nested function calls, a deeper call raises a "stack overflow"
+    /// error. The default is [`DEFAULT_MAX_DEPTH`].
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.calls = Rc::new(CallStack::new(max_depth, self.calls.stack_limit()));
+        self
+    }
+
+    /// Set how many bytes of the Rust stack the nested function calls can take, the calls beyond it
+    /// raise a "stack overflow" error. The default is [`DEFAULT_STACK_LIMIT`], which fits the stack
+    /// of a thread spawned by `std::thread`, the host that gives a larger stack can raise it.
+    pub fn with_stack_limit(mut self, stack_limit: usize) -> Self {
+        self.calls = Rc::new(CallStack::new(self.calls.max_depth(), stack_limit));
+        self
+    }
+
     pub fn scope(&self) -> &Scope {
         &self.scope
     }
 
+    pub fn call_stack(&self) -> &CallStack {
+        &self.calls
+    }
+
+    // Create the evaluator of a function body, it's enclosed by the scope and shares the call stack.
+    fn enclosed(&self, parent_scope: Rc<Scope>) -> Evaluator {
+        Self {
+            scope: Scope::new(Some(parent_scope)),
+            calls: self.calls.clone(),
+        }
+    }
+
     pub fn eval(&self, node: &Node) -> Object {
+        self.finish_tail_call(self.eval_node(node))
+    }
+
+    // Evaluate the node of a block, the tail call is returned as it is to leave the function.
+    // The `if` of an expression statement is a part of the block, so are the tail calls in it.
+    fn eval_node(&self, node: &Node) -> Object {
         match node {
+            Node::Exp(Expression::If(if_exp)) => self.eval_if_exp(if_exp),
             Node::Exp(exp) => self.eval_exp(exp),
             Node::Stmt(stmt) => self.eval_stmt(stmt),
         }
@@ -38,12 +94,16 @@ impl Evaluator {
             },
             Expression::Boolean(b) => Object::Base(BaseValue::Boolean(Value::new(b.value()))),
             Expression::Str(s) => Object::Str(Str::new(s.value().to_string())),
+            Expression::Char(c) => Object::Base(BaseValue::Char(Value::new(c.value()))),
+            Expression::Byte(b) => Object::Base(BaseValue::Byte(Value::new(b.value()))),
+            Expression::Interpolated(interp_exp) => self.eval_interpolated_exp(interp_exp),
             Expression::Arr(arr_exp) => self.eval_arr_exp(arr_exp),
             Expression::ArrIndex(arr_index) => self.eval_arr_index_exp(arr_index),
             Expression::Identifier(ident_exo) => self.eval_ident_exp(ident_exo),
             Expression::Prefix(pre_exp) => self.eval_prefix_exp(pre_exp),
             Expression::Infix(infix_exp) => self.eval_infix_exp(infix_exp),
-            Expression::If(if_exp) => self.eval_if_exp(if_exp),
+            // The value of the `if` is used, so the call in its branch is not in tail position.
+            Expression::If(if_exp) => self.finish_tail_call(self.eval_if_exp(if_exp)),
             Expression::Call(call_exp) => self.eval_call_exp(call_exp),
         }
     }
@@ -54,6 +114,10 @@ impl Evaluator {
             Statement::Return(ret_stmt) => self.eval_return_stmt(ret_stmt),
             Statement::Block(block_stmt) => self.eval_block_stmt(block_stmt),
             Statement::Func(func_stmt) => self.eval_func_stmt(func_stmt),
+            Statement::Throw(throw_stmt) => self.eval_throw_stmt(throw_stmt),
+            Statement::Try(try_stmt) => self.eval_try_stmt(try_stmt),
+            // The code with errors is not run, the error statement is only kept for the tools.
+            Statement::Error(_) => Object::Null,
         }
     }
 
@@ -62,29 +126,26 @@ impl Evaluator {
     fn eval_let_stmt(&self, stmt: &LetStatement) -> Object {
         let value = match self.eval_exp(stmt.value().unwrap()) {
             Object::Return(v) => *v,
+            Object::Throw(err) => return Object::Throw(err),
             v => v,
         };
-        self.scope.set(stmt.name().to_string(), value);
+        if let Some((_, slot)) = stmt.ident().slot() {
+            self.scope.set(slot, value);
+        }
         Object::Null
     }
 
     fn eval_return_stmt(&self, stmt: &ReturnStatement) -> Object {
         let ret_val = match stmt.value() {
+            // The function called in tail position is returned, and the caller calls it in a loop.
+            Some(Expression::Call(call_exp)) => self.prepare_call(call_exp),
             Some(v) => self.eval_exp(v),
             None => Object::Null,
         };
 
         match ret_val {
-            Object::Base(BaseValue::Integer(v)) => {
-                Object::Return(Box::new(Object::Base(BaseValue::Integer(v))))
-            }
-            Object::Base(BaseValue::Float(v)) => {
-                Object::Return(Box::new(Object::Base(BaseValue::Float(v))))
-            }
-            Object::Base(BaseValue::Boolean(v)) => {
-                Object::Return(Box::new(Object::Base(BaseValue::Boolean(v))))
-            }
-            _ => Object::Null,
+            Object::Return(_) | Object::Throw(_) | Object::TailCall(_) => ret_val,
+            v => Object::Return(Box::new(v)),
         }
     }
 
@@ -98,28 +159,60 @@ impl Evaluator {
             None => None,
         };
 
-        let body = func_stmt.body().clone();
+        let body = func_stmt.shared_body().clone();
         let func = Object::Func(Function::new(
             name.to_string(),
             params,
             body,
-            Some(Box::new(self.scope.clone())),
+            self.enclosed(self.scope.snapshot()),
         ));
 
-        self.scope.set(name.to_string(), func);
+        if let Some((_, slot)) = func_stmt.ident().slot() {
+            self.scope.set(slot, func);
+        }
 
         Object::Null
     }
 
+    fn eval_throw_stmt(&self, stmt: &ThrowStatement) -> Object {
+        match self.eval_exp(stmt.value()) {
+            Object::Throw(err) => Object::Throw(err),
+            v => Object::Throw(Error::thrown(v)),
+        }
+    }
+
+    // The error thrown in the body is bound to the name of `catch` and handled by the catch block.
+    // The finally block always runs at the end, its `return` or `throw` overrides the result.
+    // The tail calls in the blocks are called before leaving them, so that their errors are caught.
+    fn eval_try_stmt(&self, stmt: &TryStatement) -> Object {
+        let mut result = self.finish_tail_call(self.eval_block_stmt(stmt.body()));
+
+        if let (Object::Throw(err), Some((ident, block))) = (&result, stmt.catch()) {
+            if let Some((_, slot)) = ident.slot() {
+                self.scope.set(slot, Object::Error(err.clone()));
+            }
+            result = self.finish_tail_call(self.eval_block_stmt(block));
+        }
+
+        if let Some(block) = stmt.finally() {
+            let finally = self.finish_tail_call(self.eval_block_stmt(block));
+            if matches!(finally, Object::Return(_) | Object::Throw(_)) {
+                return finally;
+            }
+        }
+
+        result
+    }
+
     pub fn eval_block_stmt(&self, stmt: &BlockStatement) -> Object {
         let mut result = Object::Null;
         match stmt.statements() {
             Some(stmts) => {
                 for s in stmts {
-                    result = self.eval(s);
+                    result = self.eval_node(s);
 
                     match result {
-                        Object::Return(_) => return result,
+                        Object::Return(_) | Object::Throw(_) | Object::TailCall(_) => return result,
                         _ => {}
                     }
                 }
@@ -133,6 +226,20 @@ impl Evaluator {
 
     // =================== Evaluate Expression ===================
 
+    // Every part of the interpolated string is converted to a string by its `Display`.
+    fn eval_interpolated_exp(&self, interp_exp: &InterpolatedExp) -> Object {
+        let mut value = String::new();
+        for part in interp_exp.parts() {
+            match self.eval_exp(part) {
+                Object::Return(v) => value.push_str(&v.to_string()),
+                Object::Throw(err) => return Object::Throw(err),
+                v => value.push_str(&v.to_string()),
+            }
+        }
+
+        Object::Str(Str::new(value))
+    }
+
     fn eval_arr_exp(&self, arr_exp: &ArrayExp) -> Object {
         let objs: Vec<Object> = arr_exp
             .elements()
@@ -140,6 +247,10 @@ impl Evaluator {
             .map(|e| self.eval_exp(e))
             .collect();
 
+        if let Some(err) = objs.iter().find(|obj| obj.is_thrown()) {
+            return err.clone();
+        }
+
         if objs.len() == 0 {
             return Object::Null;
         }
@@ -148,11 +259,12 @@ impl Evaluator {
     }
 
     fn eval_arr_index_exp(&self, arr_index: &ArrayIndexExp) -> Object {
-        match self.scope.get(arr_index.name().value()) {
+        match self.lookup(arr_index.name()) {
             Some(v) => match v.as_ref() {
                 Object::Array(a) => {
                     let index = match self.eval_exp(arr_index.index()) {
                         Object::Base(BaseValue::Integer(v)) => v.value().clone(),
+                        Object::Throw(err) => return Object::Throw(err),
                         _ => return Object::Null,
                     };
 
@@ -168,8 +280,7 @@ impl Evaluator {
     }
 
     fn eval_ident_exp(&self, exp: &IdentifierExp) -> Object {
-        let name = exp.value();
-        match self.scope.get(name) {
+        match self.lookup(exp) {
             Some(v) => {
                 return v.as_ref().clone();
             }
@@ -179,8 +290,11 @@ impl Evaluator {
 
     fn eval_if_exp(&self, if_exp: &IfExp) -> Object {
         let condition = self.eval_exp(if_exp.condition());
+        if condition.is_thrown() {
+            return condition;
+        }
 
-        if self.is_truthy(&condition) {
+        if condition.is_truthy() {
             // if condition is true
             return self.eval_block_stmt(if_exp.consequence());
         } else if if_exp.else_exp().is_some() {
@@ -201,58 +315,107 @@ impl Evaluator {
     }
 
     fn eval_call_exp(&self, call_exp: &CallExp) -> Object {
-        match self.scope.get(call_exp.name().value()) {
-            Some(v) => {
-                let func = v.as_ref().clone();
-                match func {
-                    Object::Func(f) => {
-                        // Get the arguments of the function
-                        let arguments: Vec<Object> = call_exp
-                            .arguments()
-                            .iter()
-                            .map(|a| self.eval_exp(a))
-                            .collect();
-                        match f.add_arguments(arguments) {
-                            Ok(_) => {
-                                // Evaluate the body of the function
-                                return f.eval();
-                            }
-                            Err(_) => return Object::Null,
+        match self.prepare_call(call_exp) {
+            Object::TailCall(call) => self.call_func(*call),
+            result => result,
+        }
+    }
+
+    // Evaluate the arguments of the call, the builtin function is called at once,
+    // and the user function is returned as a tail call with its arguments to be called later.
+    // The returned values are unwrapped, the functions take the values only.
+    fn prepare_call(&self, call_exp: &CallExp) -> Object {
+        let func = match self.lookup(call_exp.name()) {
+            Some(v) => v.as_ref().clone(),
+            None => return Object::Null,
+        };
+        if !matches!(func, Object::Func(_) | Object::Builtin(_)) {
+            return Object::Null;
+        }
+
+        let arguments: Vec<Object> = call_exp
+            .arguments()
+            .iter()
+            .map(|a| match self.eval_exp(a) {
+                Object::Return(v) => *v,
+                v => v,
+            })
+            .collect();
+        if let Some(err) = arguments.iter().find(|a| a.is_thrown()) {
+            return err.clone();
+        }
+
+        match func {
+            Object::Func(f) => {
+                if f.add_arguments(arguments).is_err() {
+                    return Object::Null;
+                }
+                let frame = CallFrame::new(f.name(), call_exp.name().position());
+                Object::TailCall(Box::new((f, frame)))
+            }
+            Object::Builtin(b) => b.call(&arguments),
+            _ => Object::Null,
+        }
+    }
+
+    // Evaluate the body of the function in a new frame of the call stack. The function called in
+    // tail position is returned by the body, and it's called by the loop in place of the frame,
+    // so a deep tail recursion doesn't nest the Rust calls or grow the call stack.
+    // The error thrown out of the function records the call in its trace.
+    fn call_func(&self, (mut func, frame): (Function, CallFrame)) -> Object {
+        if let Err(err) = self.calls.push(frame) {
+            return Object::Throw(err);
+        }
+
+        loop {
+            match func.eval() {
+                Object::TailCall(call) => {
+                    let (next, frame) = *call;
+                    self.calls.replace(frame);
+                    func = next;
+                }
+                result => {
+                    let frame = self.calls.pop().unwrap();
+                    return match result {
+                        Object::Throw(mut err) => {
+                            err.push_trace(frame);
+                            Object::Throw(err)
                         }
-                    }
-                    _ => return Object::Null,
+                        Object::Return(v) => *v,
+                        v => v,
+                    };
                 }
             }
-            _ => return Object::Null,
+        }
+    }
+
+    // Call the function of the tail call that can't leave the current block, e.g. in a `try` block,
+    // the result is returned the same as `return f(...)`.
+    fn finish_tail_call(&self, result: Object) -> Object {
+        match result {
+            Object::TailCall(call) => match self.call_func(*call) {
+                Object::Throw(err) => Object::Throw(err),
+                v => Object::Return(Box::new(v)),
+            },
+            result => result,
         }
     }
 
     fn eval_prefix_exp(&self, pre_exp: &PrefixExp) -> Object {
         match pre_exp.operator() {
-            TokenType::Not => {
-                let right = self.eval_exp(pre_exp.right());
-                match right {
-                    Object::Base(BaseValue::Boolean(v)) => {
-                        Object::Base(BaseValue::Boolean(Value::new(!v.value())))
-                    }
-                    Object::Base(BaseValue::Integer(v)) => {
-                        Object::Base(BaseValue::Boolean(Value::new(v.is_zero())))
-                    }
-                    Object::Base(BaseValue::Float(v)) => {
-                        Object::Base(BaseValue::Boolean(Value::new(v.is_zero())))
-                    }
-                    _ => Object::Null,
-                }
-            }
+            // The negation of any value is the opposite of its truthiness, the same as the conditions.
+            TokenType::Not => match self.eval_exp(pre_exp.right()) {
+                Object::Throw(err) => Object::Throw(err),
+                right => Object::Base(BaseValue::Boolean(Value::new(!right.is_truthy()))),
+            },
             TokenType::Minus => {
                 let right = self.eval_exp(pre_exp.right());
                 match right {
-                    Object::Base(BaseValue::Integer(v)) => {
-                        Object::Base(BaseValue::Integer(Value::new(-v.value())))
-                    }
+                    Object::Base(BaseValue::Integer(v)) => Self::integer(v.value().checked_neg()),
                     Object::Base(BaseValue::Float(v)) => {
                         Object::Base(BaseValue::Float(Value::new(-v.value())))
                     }
+                    Object::Throw(err) => Object::Throw(err),
                     _ => Object::Null,
                 }
             }
@@ -263,223 +426,53 @@ impl Evaluator {
     fn eval_infix_exp(&self, infix_exp: &InfixExp) -> Object {
         let left = match self.eval_exp(infix_exp.left()) {
             Object::Return(v) => *v,
+            Object::Throw(err) => return Object::Throw(err),
             v => v,
         };
+
+        // The right operand is only evaluated if the left one doesn't decide the result,
+        // and the result is the operand that decides it.
+        let decided = match infix_exp.operator() {
+            TokenType::And => !left.is_truthy(),
+            TokenType::Or => left.is_truthy(),
+            _ => false,
+        };
+        if decided {
+            return left;
+        }
+
         let right = match self.eval_exp(infix_exp.right()) {
             Object::Return(v) => *v,
+            Object::Throw(err) => return Object::Throw(err),
             v => v,
         };
 
         match infix_exp.operator() {
-            TokenType::And
-            | TokenType::Or
-            | TokenType::EqualTo
+            // All the values are compared by the equality and ordering of `Object`.
+            TokenType::EqualTo
             | TokenType::NotEqualTo
             | TokenType::Less
             | TokenType::LessThanOrEqualTo
             | TokenType::Greater
-            | TokenType::GreaterThanOrEqualTo => match (left, right) {
-                // Boolean compare with Boolean
-                (Object::Base(BaseValue::Boolean(l)), Object::Base(BaseValue::Boolean(r))) => {
-                    match infix_exp.operator() {
-                        TokenType::And => {
-                            Object::Base(BaseValue::Boolean(Value::new(*l.value() && *r.value())))
-                        }
-                        TokenType::Or => {
-                            Object::Base(BaseValue::Boolean(Value::new(*l.value() || *r.value())))
-                        }
-                        TokenType::EqualTo => {
-                            Object::Base(BaseValue::Boolean(Value::new(l.value() == r.value())))
-                        }
-                        TokenType::NotEqualTo => {
-                            Object::Base(BaseValue::Boolean(Value::new(l.value() != r.value())))
-                        }
-                        _ => Object::Base(BaseValue::Boolean(Value::new(false))),
-                    }
-                }
-
-                // Boolean compare with Integer
-                (Object::Base(BaseValue::Boolean(l)), Object::Base(BaseValue::Integer(r))) => {
-                    match infix_exp.operator() {
-                        TokenType::And => {
-                            Object::Base(BaseValue::Boolean(Value::new(*l.value() && !r.is_zero())))
-                        }
-                        TokenType::Or => {
-                            Object::Base(BaseValue::Boolean(Value::new(*l.value() || !r.is_zero())))
-                        }
-                        _ => Object::Base(BaseValue::Boolean(Value::new(false))),
-                    }
-                }
+            | TokenType::GreaterThanOrEqualTo => self.compare(infix_exp.operator(), left, right),
 
-                // Integer compare with Boolean
-                (Object::Base(BaseValue::Integer(l)), Object::Base(BaseValue::Boolean(r))) => {
-                    match infix_exp.operator() {
-                        TokenType::And => {
-                            Object::Base(BaseValue::Boolean(Value::new(!l.is_zero() && *r.value())))
-                        }
-                        TokenType::Or => {
-                            Object::Base(BaseValue::Boolean(Value::new(!l.is_zero() || *r.value())))
-                        }
-                        _ => Object::Base(BaseValue::Boolean(Value::new(false))),
-                    }
-                }
-
-                // Boolean compare with Float
-                (Object::Base(BaseValue::Boolean(l)), Object::Base(BaseValue::Float(r))) => {
-                    match infix_exp.operator() {
-                        TokenType::And => {
-                            Object::Base(BaseValue::Boolean(Value::new(*l.value() && !r.is_zero())))
-                        }
-                        TokenType::Or => {
-                            Object::Base(BaseValue::Boolean(Value::new(*l.value() || !r.is_zero())))
-                        }
-                        _ => Object::Base(BaseValue::Boolean(Value::new(false))),
-                    }
-                }
-
-                // Float compare with Boolean
-                (Object::Base(BaseValue::Float(l)), Object::Base(BaseValue::Boolean(r))) => {
-                    match infix_exp.operator() {
-                        TokenType::And => {
-                            Object::Base(BaseValue::Boolean(Value::new(!l.is_zero() && *r.value())))
-                        }
-                        TokenType::Or => {
-                            Object::Base(BaseValue::Boolean(Value::new(!l.is_zero() || *r.value())))
-                        }
-                        _ => Object::Base(BaseValue::Boolean(Value::new(false))),
-                    }
-                }
-
-                // Integer compare with Integer
-                (Object::Base(BaseValue::Integer(l)), Object::Base(BaseValue::Integer(r))) => {
-                    match infix_exp.operator() {
-                        TokenType::And => Object::Base(BaseValue::Boolean(Value::new(
-                            !l.is_zero() && !r.is_zero(),
-                        ))),
-                        TokenType::Or => Object::Base(BaseValue::Boolean(Value::new(
-                            !l.is_zero() || !r.is_zero(),
-                        ))),
-                        TokenType::EqualTo => {
-                            Object::Base(BaseValue::Boolean(Value::new(l.value() == r.value())))
-                        }
-                        TokenType::NotEqualTo => {
-                            Object::Base(BaseValue::Boolean(Value::new(l.value() != r.value())))
-                        }
-                        TokenType::Less => {
-                            Object::Base(BaseValue::Boolean(Value::new(l.value() < r.value())))
-                        }
-                        TokenType::LessThanOrEqualTo => {
-                            Object::Base(BaseValue::Boolean(Value::new(l.value() <= r.value())))
-                        }
-                        TokenType::Greater => {
-                            Object::Base(BaseValue::Boolean(Value::new(l.value() > r.value())))
-                        }
-                        TokenType::GreaterThanOrEqualTo => {
-                            Object::Base(BaseValue::Boolean(Value::new(l.value() >= r.value())))
-                        }
-                        _ => Object::Base(BaseValue::Boolean(Value::new(false))),
-                    }
-                }
-
-                // Float compare with Float
-                (Object::Base(BaseValue::Float(l)), Object::Base(BaseValue::Float(r))) => {
-                    match infix_exp.operator() {
-                        TokenType::And => Object::Base(BaseValue::Boolean(Value::new(
-                            !l.is_zero() && !r.is_zero(),
-                        ))),
-                        TokenType::Or => Object::Base(BaseValue::Boolean(Value::new(
-                            !l.is_zero() || !r.is_zero(),
-                        ))),
-                        TokenType::EqualTo => {
-                            Object::Base(BaseValue::Boolean(Value::new(l.value() == r.value())))
-                        }
-                        TokenType::NotEqualTo => {
-                            Object::Base(BaseValue::Boolean(Value::new(l.value() != r.value())))
-                        }
-                        TokenType::Less => {
-                            Object::Base(BaseValue::Boolean(Value::new(l.value() < r.value())))
-                        }
-                        TokenType::LessThanOrEqualTo => {
-                            Object::Base(BaseValue::Boolean(Value::new(l.value() <= r.value())))
-                        }
-                        TokenType::Greater => {
-                            Object::Base(BaseValue::Boolean(Value::new(l.value() > r.value())))
-                        }
-                        TokenType::GreaterThanOrEqualTo => {
-                            Object::Base(BaseValue::Boolean(Value::new(l.value() >= r.value())))
-                        }
-                        _ => Object::Base(BaseValue::Boolean(Value::new(false))),
-                    }
-                }
-
-                // Float compare with Integer
-                (Object::Base(BaseValue::Float(l)), Object::Base(BaseValue::Integer(r))) => {
-                    match infix_exp.operator() {
-                        TokenType::EqualTo => Object::Base(BaseValue::Boolean(Value::new(
-                            *l.value() == *r.value() as f64,
-                        ))),
-                        TokenType::NotEqualTo => Object::Base(BaseValue::Boolean(Value::new(
-                            *l.value() != *r.value() as f64,
-                        ))),
-                        TokenType::Less => Object::Base(BaseValue::Boolean(Value::new(
-                            *l.value() < *r.value() as f64,
-                        ))),
-                        TokenType::LessThanOrEqualTo => Object::Base(BaseValue::Boolean(
-                            Value::new(*l.value() <= *r.value() as f64),
-                        )),
-                        TokenType::Greater => Object::Base(BaseValue::Boolean(Value::new(
-                            *l.value() > *r.value() as f64,
-                        ))),
-                        TokenType::GreaterThanOrEqualTo => Object::Base(BaseValue::Boolean(
-                            Value::new(*l.value() >= *r.value() as f64),
-                        )),
-                        _ => Object::Base(BaseValue::Boolean(Value::new(false))),
-                    }
-                }
-
-                // Integer compare with Float
-                (Object::Base(BaseValue::Integer(l)), Object::Base(BaseValue::Float(r))) => {
-                    match infix_exp.operator() {
-                        TokenType::EqualTo => Object::Base(BaseValue::Boolean(Value::new(
-                            *l.value() as f64 == *r.value(),
-                        ))),
-                        TokenType::NotEqualTo => Object::Base(BaseValue::Boolean(Value::new(
-                            *l.value() as f64 != *r.value(),
-                        ))),
-                        TokenType::Less => Object::Base(BaseValue::Boolean(Value::new(
-                            (*l.value() as f64) < *r.value(),
-                        ))),
-                        TokenType::LessThanOrEqualTo => Object::Base(BaseValue::Boolean(
-                            Value::new(*l.value() as f64 <= *r.value()),
-                        )),
-                        TokenType::Greater => Object::Base(BaseValue::Boolean(Value::new(
-                            *l.value() as f64 > *r.value(),
-                        ))),
-                        TokenType::GreaterThanOrEqualTo => Object::Base(BaseValue::Boolean(
-                            Value::new(*l.value() as f64 >= *r.value()),
-                        )),
-                        _ => Object::Base(BaseValue::Boolean(Value::new(false))),
-                    }
-                }
-                _ => Object::Base(BaseValue::Boolean(Value::new(false))),
-            },
+            // The left operand doesn't decide the result, so the right one decides it.
+            TokenType::And | TokenType::Or => right,
 
             TokenType::Plus | TokenType::Minus | TokenType::Asterisk | TokenType::Slash => {
                 match (left, right) {
                     (Object::Base(BaseValue::Integer(l)), Object::Base(BaseValue::Integer(r))) => {
                         match infix_exp.operator() {
-                            TokenType::Plus => {
-                                Object::Base(BaseValue::Integer(Value::new(l.value() + r.value())))
-                            }
-                            TokenType::Minus => {
-                                Object::Base(BaseValue::Integer(Value::new(l.value() - r.value())))
-                            }
+                            TokenType::Plus => Self::integer(l.value().checked_add(*r.value())),
+                            TokenType::Minus => Self::integer(l.value().checked_sub(*r.value())),
                             TokenType::Asterisk => {
-                                Object::Base(BaseValue::Integer(Value::new(l.value() * r.value())))
-                            }
-                            TokenType::Slash => {
-                                Object::Base(BaseValue::Integer(Value::new(l.value() / r.value())))
+                                Self::integer(l.value().checked_mul(*r.value()))
                             }
+                            TokenType::Slash if r.is_zero() => Object::Throw(Error::new(
+                                "RuntimeError",
+                                "division by zero".to_string(),
+                            )),
+                            TokenType::Slash => Self::integer(l.value().checked_div(*r.value())),
                             _ => Object::Null,
                         }
                     }
@@ -543,12 +536,31 @@ impl Evaluator {
 
     // =================== Helper Functions ===================
 
-    fn is_truthy(&self, obj: &Object) -> bool {
-        match obj {
-            Object::Base(BaseValue::Boolean(v)) => *v.value(),
-            Object::Base(BaseValue::Integer(v)) => !v.is_zero(),
-            Object::Base(BaseValue::Float(v)) => !v.is_zero(),
-            _ => false,
+    // Get the value of the variable by the slot that is resolved by the resolver.
+    fn lookup(&self, ident: &IdentifierExp) -> Option<Rc<Object>> {
+        let (depth, slot) = ident.slot()?;
+        self.scope.get(depth, slot)
+    }
+
+    // The integer result, or a runtime error if the arithmetic overflows, the same as the virtual machine.
+    fn integer(value: Option<i64>) -> Object {
+        match value {
+            Some(v) => Object::Base(BaseValue::Integer(Value::new(v))),
+            None => Object::Throw(Error::new("RuntimeError", "integer overflow".to_string())),
         }
     }
+
+    // Compare the values by the comparison operator.
+    fn compare<T: PartialOrd>(&self, operator: &TokenType, l: T, r: T) -> Object {
+        let value = match operator {
+            TokenType::EqualTo => l == r,
+            TokenType::NotEqualTo => l != r,
+            TokenType::Less => l < r,
+            TokenType::LessThanOrEqualTo => l <= r,
+            TokenType::Greater => l > r,
+            TokenType::GreaterThanOrEqualTo => l >= r,
+            _ => false,
+        };
+        Object::Base(BaseValue::Boolean(Value::new(value)))
+    }
 }
@@ -1,2 +1,3 @@
+pub mod call_stack;
 pub mod evaluator;
 pub mod scope;
\ No newline at end of file
@@ -1,41 +1,51 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{cell::RefCell, rc::Rc};
 
 use crate::object::object::Object;
 
 /// Scope is a struct that stores the variables and functions in the scope and the parent scope.
+/// The variables are stored in an array and accessed by the slot index that is assigned by the resolver.
 /// The parent scope is used to find some variables or functions that are not in the current scope until the global scope.
 #[derive(Clone)]
 pub struct Scope {
-    // Store the variables and functions in the scope
-    store: RefCell<HashMap<String, Rc<Object>>>,
-    // Store the parent scope
-    parent_scope: Option<Box<Scope>>,
+    // Store the variables and functions in the scope by their slot indices
+    store: RefCell<Vec<Rc<Object>>>,
+    // Store the parent scope, it's never changed after it's captured by a function
+    parent_scope: Option<Rc<Scope>>,
 }
 
 impl Scope {
     /// Create a new scope with the parent scope
-    pub fn new(parent_scope: Option<Box<Scope>>) -> Self {
+    pub fn new(parent_scope: Option<Rc<Scope>>) -> Self {
         Self {
-            store: RefCell::new(HashMap::new()),
+            store: RefCell::new(Vec::new()),
             parent_scope,
         }
     }
 
-    /// Get the value of the variable or function in the scope
-    pub fn get(&self, name: &str) -> Option<Rc<Object>> {
-        match self.store.borrow().get(name) {
-            Some(object) => Some(Rc::clone(object)),
-            None => match &self.parent_scope {
-                Some(parent_scope) => parent_scope.get(name),
-                None => None,
-            },
+    /// Get the value of the variable or function in the scope,
+    /// the depth is the number of the parent scopes to go up.
+    pub fn get(&self, depth: usize, slot: usize) -> Option<Rc<Object>> {
+        if depth == 0 {
+            return self.store.borrow().get(slot).cloned();
+        }
+
+        match &self.parent_scope {
+            Some(parent_scope) => parent_scope.get(depth - 1, slot),
+            None => None,
         }
     }
 
     /// Set the value of the variable or function in the scope
-    pub fn set(&self, name: String, object: Object) {
-        self.store
-            .borrow_mut()
-            .insert(name, Rc::new(object));
+    pub fn set(&self, slot: usize, object: Object) {
+        let mut store = self.store.borrow_mut();
+        if slot >= store.len() {
+            store.resize(slot + 1, Rc::new(Object::Null));
+        }
+        store[slot] = Rc::new(object);
+    }
+
+    /// Take a snapshot of the scope, the variables defined later are not visible in the snapshot.
+    pub fn snapshot(&self) -> Rc<Scope> {
+        Rc::new(self.clone())
     }
 }
@@ -0,0 +1,507 @@
+use std::mem;
+use std::rc::Rc;
+
+use crate::lexer::lexer::{block_comment_len, Lexer};
+use crate::parser_v2::parser::Parser;
+use crate::token::token::{Token, Trivia};
+use crate::token::types::TokenType;
+
+/// The style options of the formatter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatConfig {
+    indent_width: usize,
+    max_width: usize,
+}
+
+impl FormatConfig {
+    /// Create the options, a line longer than `max_width` chars is split at the commas
+    /// of its argument list or array.
+    pub fn new(indent_width: usize, max_width: usize) -> Self {
+        Self {
+            indent_width,
+            max_width,
+        }
+    }
+
+    pub fn indent_width(&self) -> usize {
+        self.indent_width
+    }
+
+    pub fn max_width(&self) -> usize {
+        self.max_width
+    }
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        Self::new(4, 100)
+    }
+}
+
+/// Format the source code in the canonical style:
+/// - One statement per line, the blocks are indented by `indent_width` spaces and `}` is on its own line.
+/// - The binary operators are surrounded by spaces, and there are no spaces inside the brackets.
+/// - The comments are kept where they are, and at most one blank line is kept between two lines.
+/// - A line longer than `max_width` is split into one argument or element per line.
+///
+/// Formatting the result again gives the same result. The code with syntax errors is not formatted,
+/// and the errors are returned instead.
+pub fn format_source(source: &str, config: &FormatConfig) -> Result<String, String> {
+    let errors = Parser::from_source(source).errors();
+    if !errors.is_empty() {
+        return Err(errors.join("\n"));
+    }
+
+    let lexer = Lexer::from_source(source).with_trivia();
+    let bom = match lexer.woc_file().has_bom() {
+        true => "\u{FEFF}",
+        false => "",
+    };
+    let newline = match source.contains("\r\n") {
+        true => "\r\n",
+        false => "\n",
+    };
+    let tokens = collect_tokens(lexer);
+
+    let mut printer = Printer::new(config, newline);
+    printer.print(&tokens);
+    let output = format!("{}{}", bom, printer.finish());
+
+    // Only the layout can be changed, so the tokens of the result must be the same as the source's.
+    if summary(&tokens) != summary(&collect_tokens(Lexer::from_source(&output))) {
+        return Err(
+            "failed to format the code: the tokens are changed by the formatter".to_string(),
+        );
+    }
+
+    Ok(output)
+}
+
+fn collect_tokens(lexer: Lexer) -> Vec<Rc<Token>> {
+    let iter = lexer.tokens_iter();
+    let mut tokens = Vec::new();
+    while let Some(token) = iter.next() {
+        let eof = token.is_eof();
+        tokens.push(token);
+        if eof {
+            break;
+        }
+    }
+    tokens
+}
+
+fn summary(tokens: &[Rc<Token>]) -> Vec<(TokenType, &str)> {
+    tokens
+        .iter()
+        .map(|token| (token.token_type().clone(), token.literal()))
+        .collect()
+}
+
+// The tokens are created by a lossless lexer, so all of them have trivia.
+fn trivia(token: &Token) -> &Trivia {
+    token
+        .trivia()
+        .expect("the formatter needs the lossless tokens")
+}
+
+// The tokens after which `(` and `[` are a call or an index instead of a group or an array.
+fn is_operand_end(token_type: &TokenType) -> bool {
+    matches!(
+        token_type,
+        TokenType::Ident
+            | TokenType::IntegerNum
+            | TokenType::FloatNum
+            | TokenType::String
+            | TokenType::Char
+            | TokenType::Byte
+            | TokenType::True
+            | TokenType::False
+            | TokenType::None
+            | TokenType::RightParen
+            | TokenType::RightBracket
+    )
+}
+
+// The kind of an item decides where a long line can be split.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ItemKind {
+    Open,
+    Close,
+    Comma,
+    Comment,
+    Other,
+}
+
+// A token or a comment on a line, `space` is whether it's separated from the previous item by a space.
+#[derive(Debug, Clone)]
+struct Item {
+    text: String,
+    space: bool,
+    kind: ItemKind,
+}
+
+struct Printer<'a> {
+    config: &'a FormatConfig,
+    newline: &'static str,
+    output: String,
+    // The items of the current line and its indent level.
+    line: Vec<Item>,
+    indent: usize,
+    // The open brackets, a `;` inside `(` or `[` doesn't end the line.
+    brackets: Vec<TokenType>,
+    // The indent levels of the lines where the open blocks start, the lines in a block are indented
+    // once more than it, and its `}` is put back at it, even if the block is in an expression.
+    blocks: Vec<usize>,
+    // The last printed token, and whether it's a unary operator.
+    prev: Option<TokenType>,
+    prev_unary: bool,
+    // The last item is a comment, the next token is separated from it by a space.
+    after_comment: bool,
+    // The last token ends a statement or opens a block, so the next line is not a continuation.
+    at_boundary: bool,
+    // The line ends before the next token.
+    need_break: bool,
+    // There are blank lines before the next item in the source code.
+    blank: bool,
+}
+
+impl<'a> Printer<'a> {
+    fn new(config: &'a FormatConfig, newline: &'static str) -> Self {
+        Self {
+            config,
+            newline,
+            output: String::new(),
+            line: Vec::new(),
+            indent: 0,
+            brackets: Vec::new(),
+            blocks: Vec::new(),
+            prev: None,
+            prev_unary: false,
+            after_comment: false,
+            at_boundary: true,
+            need_break: false,
+            blank: false,
+        }
+    }
+
+    fn print(&mut self, tokens: &[Rc<Token>]) {
+        let mut index = 0;
+        while index < tokens.len() {
+            let token = &tokens[index];
+            let mut gap = trivia(token).leading().to_string();
+            if index > 0 {
+                gap.insert_str(0, trivia(&tokens[index - 1]).trailing());
+            }
+            self.print_gap(&gap);
+
+            match token.token_type() {
+                TokenType::Eof => break,
+                TokenType::StringHead => {
+                    // The interpolated string is printed as it is, with its embedded expressions.
+                    let end = interpolation_end(tokens, index);
+                    let mut text = trivia(token).text().to_string();
+                    for i in index + 1..=end {
+                        text.push_str(trivia(&tokens[i - 1]).trailing());
+                        text.push_str(trivia(&tokens[i]).leading());
+                        text.push_str(trivia(&tokens[i]).text());
+                    }
+                    self.print_token(&TokenType::String, &text);
+                    index = end;
+                }
+                token_type => self.print_token(token_type, trivia(token).text()),
+            }
+            index += 1;
+        }
+    }
+
+    // Print the comments between two tokens, and remember whether there are blank lines before the next token.
+    fn print_gap(&mut self, gap: &str) {
+        let mut newlines = 0;
+        let mut rest = gap;
+        while let Some(c) = rest.chars().next() {
+            let len = if rest.starts_with("//") {
+                let len = rest.find(['\r', '\n']).unwrap_or(rest.len());
+                self.print_comment(rest[..len].trim_end(), newlines);
+                self.need_break = true;
+                newlines = 0;
+                len
+            } else if rest.starts_with("/*") {
+                let len = block_comment_len(rest);
+                self.print_comment(&rest[..len], newlines);
+                newlines = 0;
+                len
+            } else {
+                if c == '\n' {
+                    newlines += 1;
+                }
+                c.len_utf8()
+            };
+            rest = &rest[len..];
+        }
+        self.blank = newlines > 1;
+    }
+
+    // A comment after the code on the same line stays there, the others are on their own lines.
+    fn print_comment(&mut self, text: &str, newlines: usize) {
+        if newlines == 0 && !self.line.is_empty() {
+            self.push(text, true, ItemKind::Comment);
+        } else {
+            self.break_line();
+            self.blank = newlines > 1;
+            self.push(text, false, ItemKind::Comment);
+            self.need_break = true;
+        }
+        self.after_comment = true;
+    }
+
+    fn print_token(&mut self, token_type: &TokenType, text: &str) {
+        let empty_block = *token_type == TokenType::RightBrace
+            && self.line.last().is_some_and(|item| item.text == "{");
+
+        if self.need_break {
+            // `else`, `catch`, `finally` and the punctuations after a block stay on the line of its `}`.
+            let after_block = self.prev == Some(TokenType::RightBrace)
+                && self
+                    .line
+                    .last()
+                    .is_some_and(|item| item.kind != ItemKind::Comment)
+                && matches!(
+                    token_type,
+                    TokenType::Else
+                        | TokenType::Catch
+                        | TokenType::Finally
+                        | TokenType::RightParen
+                        | TokenType::RightBracket
+                        | TokenType::Comma
+                        | TokenType::Semicolon
+                        | TokenType::Dot
+                );
+            if !after_block && !empty_block {
+                self.break_line();
+            }
+            self.need_break = false;
+        }
+
+        let space = self.space_before(token_type);
+        match token_type {
+            TokenType::DocComment => {
+                self.break_line();
+                self.push(text.trim_end(), false, ItemKind::Other);
+                self.need_break = true;
+                self.at_boundary = true;
+            }
+            TokenType::LeftBrace => {
+                self.push(text, true, ItemKind::Other);
+                self.brackets.push(token_type.clone());
+                self.blocks.push(self.indent);
+                self.need_break = true;
+                self.at_boundary = true;
+            }
+            TokenType::RightBrace => {
+                self.brackets.pop();
+                let indent = self.blocks.pop().unwrap_or_default();
+                if !empty_block {
+                    self.break_line();
+                    self.blank = false;
+                }
+                self.push(text, false, ItemKind::Other);
+                self.indent = indent;
+                self.need_break = true;
+                self.at_boundary = true;
+            }
+            TokenType::Semicolon => {
+                self.push(text, false, ItemKind::Other);
+                let in_brackets = matches!(
+                    self.brackets.last(),
+                    Some(TokenType::LeftParen | TokenType::LeftBracket)
+                );
+                if !in_brackets {
+                    self.need_break = true;
+                    self.at_boundary = true;
+                }
+            }
+            _ => {
+                let kind = match token_type {
+                    TokenType::LeftParen | TokenType::LeftBracket => {
+                        self.brackets.push(token_type.clone());
+                        ItemKind::Open
+                    }
+                    TokenType::RightParen | TokenType::RightBracket => {
+                        self.brackets.pop();
+                        ItemKind::Close
+                    }
+                    TokenType::Comma => ItemKind::Comma,
+                    _ => ItemKind::Other,
+                };
+                self.push(text, space, kind);
+                self.at_boundary = false;
+            }
+        }
+
+        self.prev_unary = match token_type {
+            TokenType::Not | TokenType::BitNot => true,
+            TokenType::Minus => !self.prev.as_ref().is_some_and(is_operand_end),
+            _ => false,
+        };
+        self.prev = Some(token_type.clone());
+        self.after_comment = false;
+    }
+
+    fn space_before(&self, token_type: &TokenType) -> bool {
+        if self.after_comment {
+            return true;
+        }
+
+        if matches!(
+            token_type,
+            TokenType::RightParen
+                | TokenType::RightBracket
+                | TokenType::Comma
+                | TokenType::Semicolon
+                | TokenType::Dot
+                | TokenType::Colon
+        ) {
+            return false;
+        }
+
+        match &self.prev {
+            Some(TokenType::LeftParen | TokenType::LeftBracket | TokenType::Dot) => false,
+            Some(_) if self.prev_unary => false,
+            Some(TokenType::Func) if *token_type == TokenType::LeftParen => false,
+            Some(prev) if matches!(token_type, TokenType::LeftParen | TokenType::LeftBracket) => {
+                !is_operand_end(prev)
+            }
+            _ => true,
+        }
+    }
+
+    // Add an item to the current line, a new line starts one level deeper than the line of its block,
+    // and the line that continues a statement is indented once more.
+    fn push(&mut self, text: &str, space: bool, kind: ItemKind) {
+        if self.line.is_empty() {
+            let after_open = self.output.trim_end().ends_with('{');
+            if self.blank && !self.output.is_empty() && !after_open {
+                self.output.push_str(self.newline);
+            }
+
+            let depth = self.blocks.last().map_or(0, |indent| indent + 1);
+            self.indent = depth + usize::from(!self.at_boundary);
+        }
+
+        self.line.push(Item {
+            text: text.to_string(),
+            space: space && !self.line.is_empty(),
+            kind,
+        });
+        self.blank = false;
+    }
+
+    fn break_line(&mut self) {
+        if self.line.is_empty() {
+            return;
+        }
+
+        let items = mem::take(&mut self.line);
+        for (indent, items) in self.wrap(items, self.indent) {
+            self.output
+                .push_str(&" ".repeat(indent * self.config.indent_width));
+            for item in items {
+                if item.space {
+                    self.output.push(' ');
+                }
+                self.output.push_str(&item.text);
+            }
+            self.output.push_str(self.newline);
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.break_line();
+        self.output
+    }
+
+    // Split a long line at the commas of its widest bracket group: the items of the group are moved to
+    // their own lines with one more indent, and the closing bracket starts the last line.
+    // The comments are not counted, so a long trailing comment doesn't split the code.
+    fn wrap(&self, items: Vec<Item>, indent: usize) -> Vec<(usize, Vec<Item>)> {
+        let width: usize = indent * self.config.indent_width
+            + items
+                .iter()
+                .filter(|item| item.kind != ItemKind::Comment)
+                .map(|item| item.text.chars().count() + usize::from(item.space))
+                .sum::<usize>();
+        if width <= self.config.max_width {
+            return vec![(indent, items)];
+        }
+
+        let Some((open, commas, close)) = widest_group(&items) else {
+            return vec![(indent, items)];
+        };
+
+        let mut lines = self.wrap(items[..=open].to_vec(), indent);
+        let mut start = open + 1;
+        for end in commas.iter().map(|comma| comma + 1).chain([close]) {
+            if start < end {
+                let mut element = items[start..end].to_vec();
+                element[0].space = false;
+                lines.extend(self.wrap(element, indent + 1));
+            }
+            start = end;
+        }
+
+        let mut tail = items[close..].to_vec();
+        tail[0].space = false;
+        lines.extend(self.wrap(tail, indent));
+
+        lines
+    }
+}
+
+// Find the bracket group with commas that spans the most items on the line,
+// the result is the index of the open bracket, the indexes of its commas and the index of the close bracket.
+fn widest_group(items: &[Item]) -> Option<(usize, Vec<usize>, usize)> {
+    let mut widest: Option<(usize, Vec<usize>, usize)> = None;
+    for (open, _) in items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| item.kind == ItemKind::Open)
+    {
+        let mut depth = 0;
+        let mut commas = Vec::new();
+        for (index, item) in items.iter().enumerate().skip(open + 1) {
+            match item.kind {
+                ItemKind::Open => depth += 1,
+                ItemKind::Comma if depth == 0 => commas.push(index),
+                ItemKind::Close if depth == 0 => {
+                    let wider = widest.as_ref().is_none_or(|(o, _, c)| index - open > c - o);
+                    if !commas.is_empty() && wider {
+                        widest = Some((open, commas, index));
+                    }
+                    break;
+                }
+                ItemKind::Close => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+    widest
+}
+
+// Get the index of the last token of the interpolated string that starts at the index.
+fn interpolation_end(tokens: &[Rc<Token>], start: usize) -> usize {
+    let mut depth = 0;
+    for (index, token) in tokens.iter().enumerate().skip(start) {
+        match token.token_type() {
+            TokenType::StringHead => depth += 1,
+            TokenType::StringTail => {
+                depth -= 1;
+                if depth == 0 {
+                    return index;
+                }
+            }
+            TokenType::Eof => return index - 1,
+            _ => {}
+        }
+    }
+    tokens.len() - 1
+}
@@ -0,0 +1,4 @@
+// The formatter prints the source code in the canonical style, it works on the lossless tokens,
+// so the comments and the blank lines between statements are kept.
+// - formatter: It decides the spaces, line breaks and indents between the tokens, and wraps the long lines.
+pub mod formatter;
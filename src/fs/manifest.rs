@@ -0,0 +1,174 @@
+use std::fs;
+use std::path::Path;
+
+// The file name of the project manifest.
+pub const MANIFEST_NAME: &str = "woc.toml";
+
+// The entry point that will be used if the manifest does not specify one.
+pub const DEFAULT_ENTRY: &str = "main.woc";
+
+/// The project manifest, it is read from the `woc.toml` file in the root of the project.
+/// Only a small subset of TOML is supported, which is enough for the manifest:
+///
+/// ```toml
+/// # Comments start with '#'
+/// [project]
+/// name = "demo"
+/// entry = "src/main.woc"
+/// search_paths = ["src", "lib"]
+///
+/// # The rules of `woc lint` that are turned on or off
+/// [lint]
+/// unused_variable = false
+/// ```
+///
+/// The keys can be put in the root table or in the `[project]` table.
+pub struct Manifest {
+    name: Option<String>,
+    entry: String,
+    search_paths: Vec<String>,
+    // The lint rules in the `[lint]` table and whether they're enabled, in the order of the file.
+    lint_rules: Vec<(String, bool)>,
+}
+
+impl Default for Manifest {
+    fn default() -> Self {
+        Self {
+            name: None,
+            entry: DEFAULT_ENTRY.to_string(),
+            search_paths: Vec::new(),
+            lint_rules: Vec::new(),
+        }
+    }
+}
+
+impl Manifest {
+    /// Read the manifest from the file.
+    pub fn load(path: &Path) -> Result<Manifest, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("failed to read manifest '{}': {}", path.display(), e))?;
+
+        Self::parse(&content).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Parse the manifest from the content of `woc.toml`.
+    pub fn parse(content: &str) -> Result<Manifest, String> {
+        let mut manifest = Manifest::default();
+        let mut table = String::new();
+
+        for (index, line) in content.lines().enumerate() {
+            let line_num = index + 1;
+            let line = Self::strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            // Table header, for example: [project]
+            if line.starts_with('[') {
+                if !line.ends_with(']') {
+                    return Err(format!("line {}: unclosed table header", line_num));
+                }
+                table = line[1..line.len() - 1].trim().to_string();
+                continue;
+            }
+
+            let (key, value) = match line.split_once('=') {
+                Some((k, v)) => (k.trim(), v.trim()),
+                None => return Err(format!("line {}: expected `key = value`", line_num)),
+            };
+
+            // The rule names are checked by the linter.
+            if table == "lint" {
+                let enabled = Self::parse_bool(value, line_num)?;
+                manifest.lint_rules.push((key.to_string(), enabled));
+                continue;
+            }
+
+            // The keys of other tables are ignored, they may be used by other tools.
+            if !table.is_empty() && table != "project" {
+                continue;
+            }
+
+            match key {
+                "name" => manifest.name = Some(Self::parse_string(value, line_num)?),
+                "entry" => manifest.entry = Self::parse_string(value, line_num)?,
+                "search_paths" => {
+                    manifest.search_paths = Self::parse_string_array(value, line_num)?
+                }
+                _ => return Err(format!("line {}: unknown key `{}`", line_num, key)),
+            }
+        }
+
+        Ok(manifest)
+    }
+
+    pub fn name(&self) -> Option<&String> {
+        self.name.as_ref()
+    }
+
+    /// Get the entry file, it is relative to the root of the project.
+    pub fn entry(&self) -> &str {
+        &self.entry
+    }
+
+    /// Get the directories used to resolve modules, they are relative to the root of the project.
+    pub fn search_paths(&self) -> &Vec<String> {
+        &self.search_paths
+    }
+
+    /// Get the lint rules that are turned on or off in the `[lint]` table.
+    pub fn lint_rules(&self) -> &[(String, bool)] {
+        &self.lint_rules
+    }
+
+    // Remove the comment of the line, the '#' in a string is not a comment.
+    fn strip_comment(line: &str) -> &str {
+        let mut in_string = false;
+        for (i, c) in line.char_indices() {
+            match c {
+                '"' => in_string = !in_string,
+                '#' if !in_string => return &line[..i],
+                _ => {}
+            }
+        }
+        line
+    }
+
+    fn parse_string(value: &str, line_num: usize) -> Result<String, String> {
+        if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+            Ok(value[1..value.len() - 1].to_string())
+        } else {
+            Err(format!(
+                "line {}: expected a string, got `{}`",
+                line_num, value
+            ))
+        }
+    }
+
+    fn parse_bool(value: &str, line_num: usize) -> Result<bool, String> {
+        match value {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            _ => Err(format!(
+                "line {}: expected `true` or `false`, got `{}`",
+                line_num, value
+            )),
+        }
+    }
+
+    fn parse_string_array(value: &str, line_num: usize) -> Result<Vec<String>, String> {
+        if !value.starts_with('[') || !value.ends_with(']') {
+            return Err(format!(
+                "line {}: expected an array, got `{}`",
+                line_num, value
+            ));
+        }
+
+        value[1..value.len() - 1]
+            .split(',')
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .map(|v| Self::parse_string(v, line_num))
+            .collect()
+    }
+}
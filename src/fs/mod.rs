@@ -3,4 +3,9 @@
 // - file: It represents a file, and it will be used in Lexer.
 // - tree: It represents the file system as a tree.
 //      - node: It represents a node in the tree.
+// - manifest: It represents the `woc.toml` file of a project.
+// - workspace: It represents a project, and it's used to find the entry file and resolve modules.
+pub mod manifest;
+pub mod tree;
 pub mod woc_file;
+pub mod workspace;
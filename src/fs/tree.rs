@@ -0,0 +1,141 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// The extension of the source files that will be collected into the tree.
+const WOC_EXTENSION: &str = "woc";
+
+/// A node of the file system tree.
+/// A directory node holds its children, and a file node holds the path of the source file,
+/// the file is read when it's used.
+pub enum Node {
+    Dir(DirNode),
+    File(PathBuf),
+}
+
+impl Node {
+    /// Get the name of the node, it is the last component of the path.
+    pub fn name(&self) -> &str {
+        match self {
+            Node::Dir(dir) => dir.name(),
+            Node::File(path) => path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        matches!(self, Node::Dir(_))
+    }
+}
+
+/// A directory in the tree, its children are sorted by name,
+/// so that the scan result does not depend on the order returned by the OS.
+pub struct DirNode {
+    name: String,
+    path: PathBuf,
+    children: Vec<Node>,
+}
+
+impl DirNode {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn children(&self) -> &Vec<Node> {
+        &self.children
+    }
+}
+
+/// The file system of a project represented as a tree.
+/// Only `.woc` files and the directories that (transitively) contain them are kept,
+/// hidden files and directories are skipped.
+pub struct Tree {
+    root: DirNode,
+}
+
+impl Tree {
+    /// Scan the directory recursively and build the tree.
+    pub fn scan(root: &Path) -> Result<Tree, String> {
+        if !root.is_dir() {
+            return Err(format!("'{}' is not a directory", root.display()));
+        }
+
+        let root = Self::scan_dir(root)?;
+        Ok(Tree { root })
+    }
+
+    pub fn root(&self) -> &DirNode {
+        &self.root
+    }
+
+    /// Collect all the source files in the tree with depth-first order.
+    pub fn files(&self) -> Vec<&Path> {
+        let mut files = Vec::new();
+        Self::collect_files(&self.root, &mut files);
+        files
+    }
+
+    /// Find the source file by its path, the path can be absolute or relative to the root.
+    pub fn find(&self, path: &Path) -> Option<&Path> {
+        let path = if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.root.path.join(path)
+        };
+
+        self.files().into_iter().find(|file| *file == path)
+    }
+
+    fn scan_dir(path: &Path) -> Result<DirNode, String> {
+        let entries = fs::read_dir(path)
+            .map_err(|e| format!("failed to read directory '{}': {}", path.display(), e))?;
+
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| !Self::is_hidden(p))
+            .collect();
+        paths.sort();
+
+        let mut children = Vec::new();
+        for p in paths {
+            if p.is_dir() {
+                let dir = Self::scan_dir(&p)?;
+                // There is no need to keep the directory without any source file.
+                if !dir.children.is_empty() {
+                    children.push(Node::Dir(dir));
+                }
+            } else if p.extension().is_some_and(|ext| ext == WOC_EXTENSION) {
+                children.push(Node::File(p));
+            }
+        }
+
+        Ok(DirNode {
+            name: path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            path: path.to_path_buf(),
+            children,
+        })
+    }
+
+    fn collect_files<'a>(dir: &'a DirNode, files: &mut Vec<&'a Path>) {
+        for child in dir.children.iter() {
+            match child {
+                Node::Dir(d) => Self::collect_files(d, files),
+                Node::File(f) => files.push(f),
+            }
+        }
+    }
+
+    fn is_hidden(path: &Path) -> bool {
+        path.file_name()
+            .map(|n| n.to_string_lossy().starts_with('.'))
+            .unwrap_or(false)
+    }
+}
@@ -1,89 +1,92 @@
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fs;
 use std::path::Path;
 
+// The byte order mark that some editors write at the start of a UTF-8 file.
+const BOM: char = '\u{FEFF}';
+
 pub struct WocFile {
     path: String,
     name: Option<String>,
-    // The mapping between file line numbers and code lines
-    line_num_and_code_map: HashMap<usize, String>,
-    current_line: usize,
+    // The whole source code without the BOM, the line breaks are kept as they are.
+    source: String,
+    // Whether the file starts with a BOM, it's kept for the tools that write the file back.
+    bom: bool,
+    // The byte offsets of the starts of the lines, the first line starts at 0.
+    line_starts: Vec<usize>,
 }
 
 impl WocFile {
-    // Create a new WocFile object by only recording the path
-    pub fn new(path: String) -> Self {
-        let mut wf = Self {
-            path,
-            name: None,
-            line_num_and_code_map: HashMap::new(),
-            current_line: 1,
-        };
+    // Create a new WocFile object by reading the whole file,
+    // it fails if the file can't be read or it's not valid UTF-8.
+    pub fn new(path: String) -> Result<Self, String> {
+        let file_path = Path::new(&path);
+        let name = file_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string());
+        let source = fs::read_to_string(file_path)
+            .map_err(|e| format!("failed to read '{}': {}", path, e))?;
+
+        let mut wf = Self::from_source(&path, &source);
+        wf.name = name;
+        Ok(wf)
+    }
 
-        // Get file object
-        let path = Path::new(&wf.path);
-        wf.name = Some(path.file_name().unwrap().to_string_lossy().to_string());
-        let file = match File::open(path) {
-            Ok(f) => f,
-            Err(_) => panic!("Failed to open file"),
+    // Create a WocFile object from the source code in memory, the path is only used to mark the tokens.
+    pub fn from_source(path: &str, source: &str) -> Self {
+        let (source, bom) = match source.strip_prefix(BOM) {
+            Some(s) => (s, true),
+            None => (source, false),
         };
 
-        // Get file reader
-        let mut reader = BufReader::new(file);
-
-        // Read file line by line and store the line number and code line
-        let mut line = String::new();
-        let mut line_number = 0;
-        loop {
-            line.clear();
-            match reader.read_line(&mut line) {
-                Ok(0) => {
-                    break;
-                }
-                Ok(_) => {
-                    line_number += 1;
-                    wf.line_num_and_code_map
-                        .insert(line_number, line.trim().to_string());
-                }
-                Err(_) => {
-                    break;
-                }
-            }
+        // A line starts after every '\n', so the "\r\n" line breaks are handled as well.
+        let mut line_starts = vec![0];
+        line_starts.extend(source.match_indices('\n').map(|(offset, _)| offset + 1));
+
+        Self {
+            path: path.to_string(),
+            name: None,
+            source: source.to_string(),
+            bom,
+            line_starts,
         }
+    }
 
-        wf
+    // Get the whole source code
+    pub fn source(&self) -> &str {
+        &self.source
     }
 
-    // Read a new line from the file and return the current line number and line content
-    pub fn read_line(&mut self) -> Option<(usize, String)> {
-        // It's means the file is empty
-        if self.line_num_and_code_map.len() <= 0
-            || self.current_line > self.line_num_and_code_map.len()
-        {
-            return None;
-        }
+    // Check whether the file starts with a BOM, the BOM is not a part of the source code
+    pub fn has_bom(&self) -> bool {
+        self.bom
+    }
 
-        // Get the current line content
-        let line = self
-            .line_num_and_code_map
-            .get(&self.current_line)
-            .unwrap()
-            .clone();
+    // Get the number of lines, a line break at the end of the file starts an empty line
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
 
-        // Move the current line number to the next line
-        let line_num = self.current_line;
-        self.current_line += 1;
+    // Get code line by line number, the line break is not included
+    pub fn get_line(&self, line_num: usize) -> Option<String> {
+        let start = *self.line_starts.get(line_num.checked_sub(1)?)?;
+        let end = match self.line_starts.get(line_num) {
+            Some(next) => next - 1,
+            None => self.source.len(),
+        };
 
-        Some((line_num, line))
+        let line = &self.source[start..end];
+        Some(line.strip_suffix('\r').unwrap_or(line).to_string())
     }
 
-    // Get code line by line number
-    pub fn get_line(&self, line_num: usize) -> Option<String> {
-        match self.line_num_and_code_map.get(&line_num) {
-            Some(line) => Some(line.clone()),
-            None => None,
-        }
+    // Get the line and the column of the byte offset, both of them start from 1.
+    // The column is counted in chars, so a multi-byte char takes one column.
+    pub fn position(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.source.len());
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        let start = self.line_starts[line - 1];
+        let column = self.source[start..offset].chars().count() + 1;
+
+        (line, column)
     }
 
     // Get the file path
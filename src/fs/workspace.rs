@@ -0,0 +1,71 @@
+use std::path::{Path, PathBuf};
+
+use crate::fs::manifest::{Manifest, MANIFEST_NAME};
+use crate::fs::tree::Tree;
+
+/// The workspace is a project directory that contains `.woc` files and an optional `woc.toml` manifest.
+/// It is used by the CLI to find the entry point and the source files, and by the module resolver
+/// to find the imported files.
+pub struct Workspace {
+    root: PathBuf,
+    manifest: Manifest,
+    tree: Tree,
+}
+
+impl Workspace {
+    /// Load the workspace from the project directory.
+    /// If there is no manifest in the directory, the default manifest will be used.
+    pub fn load(root: &Path) -> Result<Workspace, String> {
+        let manifest_path = root.join(MANIFEST_NAME);
+        let manifest = if manifest_path.is_file() {
+            Manifest::load(&manifest_path)?
+        } else {
+            Manifest::default()
+        };
+
+        let tree = Tree::scan(root)?;
+
+        Ok(Workspace {
+            root: root.to_path_buf(),
+            manifest,
+            tree,
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn manifest(&self) -> &Manifest {
+        &self.manifest
+    }
+
+    pub fn tree(&self) -> &Tree {
+        &self.tree
+    }
+
+    /// Get the entry file of the project.
+    pub fn entry(&self) -> Result<&Path, String> {
+        let entry = Path::new(self.manifest.entry());
+        self.tree.find(entry).ok_or(format!(
+            "entry file '{}' is not found in '{}'",
+            entry.display(),
+            self.root.display()
+        ))
+    }
+
+    /// Resolve a module name to the source file.
+    /// The module name uses '.' as the separator, for example: `utils.math` -> `utils/math.woc`.
+    /// The search paths in the manifest are searched in order, and the root of the project is searched last.
+    pub fn resolve(&self, module: &str) -> Option<&Path> {
+        let mut relative = module.split('.').collect::<PathBuf>();
+        relative.set_extension("woc");
+
+        self.manifest
+            .search_paths()
+            .iter()
+            .map(|dir| Path::new(dir).join(&relative))
+            .chain(std::iter::once(relative.clone()))
+            .find_map(|path| self.tree.find(&path))
+    }
+}
@@ -0,0 +1,43 @@
+use std::fmt::{self, Display, Formatter};
+
+/// An error found by the lexer, such as an illegal character or an unterminated string.
+/// The lexer keeps going after an error, the code with the error becomes an illegal token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexError {
+    message: String,
+    // The position where the error is found, both of them start from 1.
+    line: usize,
+    column: usize,
+}
+
+impl LexError {
+    pub fn new(message: &str, line: usize, column: usize) -> Self {
+        Self {
+            message: message.to_string(),
+            line,
+            column,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl Display for LexError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.line, self.column
+        )
+    }
+}
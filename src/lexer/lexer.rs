@@ -2,28 +2,216 @@ use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
 use crate::fs::woc_file::WocFile;
+use crate::lexer::error::LexError;
 use crate::lexer::state::State;
-use crate::token::token::Token;
+use crate::token::keywords;
+use crate::token::token::{Token, Trivia};
 use crate::token::types::TokenType;
 
+// The string or char literal that is being scanned, only the string may span multiple lines.
+struct StringLiteral {
+    // The position of the opening quote, the unterminated string is reported at it.
+    line: usize,
+    column: usize,
+    // The char index of the literal including its prefix, and the char index of the segment
+    // that is being scanned, a segment starts after the embedded expression before it.
+    start: usize,
+    segment: usize,
+    // The quote char, it's `'` for a char literal: 'a' or a byte literal: b'a'.
+    quote: char,
+    byte: bool,
+    // A hex escape sequence is decoded, the char is the byte value: b'\xFF' is 'ÿ'.
+    hex: bool,
+    // The escape sequences are not decoded in a raw string: r"C:\path".
+    raw: bool,
+    // A triple-quoted string can contain the quotes: """a "quoted" word""".
+    triple: bool,
+    // The number of the opening quote chars that are not scanned yet.
+    skip: usize,
+    // The number of the quotes that may close the triple-quoted string.
+    quotes: usize,
+    // The line, column and chars after the backslash of an unfinished escape sequence.
+    escape: Option<(usize, usize, String)>,
+    // The number of the segments before the embedded expressions that have been stored.
+    segments: usize,
+    // The embedded expression that is being collected.
+    interpolation: Option<Interpolation>,
+    value: String,
+    error: Option<LexError>,
+}
+
+// The embedded expression `${...}` in a string, it's collected until the matching brace,
+// and then it's lexed as the normal code.
+struct Interpolation {
+    line: usize,
+    column: usize,
+    // The char index of the first char of the expression.
+    start: usize,
+    source: String,
+    // The nesting depth of the braces in the expression.
+    depth: usize,
+    // The braces in the strings and chars of the expression are not counted.
+    quoted: Option<char>,
+    escaped: bool,
+}
+
+impl Interpolation {
+    // Collect a char of the expression, return true if it's the closing brace.
+    fn push(&mut self, c: char) -> bool {
+        if let Some(quote) = self.quoted {
+            if self.escaped {
+                self.escaped = false;
+            } else if c.eq(&'\\') {
+                self.escaped = true;
+            } else if c.eq(&quote) {
+                self.quoted = None;
+            }
+        } else {
+            match c {
+                '"' | '\'' => self.quoted = Some(c),
+                '{' => self.depth += 1,
+                '}' if self.depth == 0 => return true,
+                '}' => self.depth -= 1,
+                _ => {}
+            }
+        }
+
+        self.source.push(c);
+        false
+    }
+}
+
+// What the scanned char does to the string.
+enum StringStep {
+    Continue,
+    LineBreak,
+    Close,
+    StartInterpolation,
+    EndInterpolation,
+}
+
+impl StringLiteral {
+    // The quotes that don't close the triple-quoted string are a part of the value.
+    fn flush_quotes(&mut self) {
+        for _ in 0..self.quotes {
+            self.value.push('"');
+        }
+        self.quotes = 0;
+    }
+
+    // Decode the escape sequence with the next char, the unknown escape sequence is an error.
+    fn push_escape(&mut self, c: char) {
+        let (line, column, mut seq) = self.escape.take().unwrap();
+        seq.push(c);
+
+        let decoded = match seq.as_str() {
+            "n" => '\n',
+            "t" => '\t',
+            "r" => '\r',
+            "0" => '\0',
+            "\\" => '\\',
+            "\"" => '"',
+            "'" => '\'',
+            "$" => '$',
+            "u" | "x" => {
+                self.escape = Some((line, column, seq));
+                return;
+            }
+            // The hex escape sequence `\xNN` is an ASCII char in a string or a char literal,
+            // and it's any byte in a byte literal: b'\xFF'.
+            _ if seq.starts_with('x') && c.is_ascii_hexdigit() => {
+                if seq.len() < 3 {
+                    self.escape = Some((line, column, seq));
+                    return;
+                }
+                let value = u8::from_str_radix(&seq[1..], 16).unwrap();
+                if !value.is_ascii() && !self.byte {
+                    let message = format!("hex escape out of range `\\{}`", seq);
+                    return self.escape_error(&message, line, column);
+                }
+                self.hex = true;
+                value as char
+            }
+            _ if seq.starts_with("u{") && seq.ends_with('}') => {
+                let hex = &seq[2..seq.len() - 1];
+                let code = match hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                    true => u32::from_str_radix(hex, 16).ok(),
+                    false => None,
+                };
+                match code.and_then(char::from_u32) {
+                    Some(c) => c,
+                    None => return self.invalid_escape(&seq, line, column),
+                }
+            }
+            // The longest unicode escape sequence is `\u{10FFFF}`.
+            _ if seq.starts_with("u{") && seq.len() < 9 => {
+                self.escape = Some((line, column, seq));
+                return;
+            }
+            _ => return self.invalid_escape(&seq, line, column),
+        };
+
+        self.value.push(decoded);
+    }
+
+    // The char literal must contain exactly one char, and the byte literal must be an ASCII char
+    // or a hex escape sequence.
+    fn char_token(self) -> Result<(TokenType, String), LexError> {
+        let mut chars = self.value.chars();
+        let message = match (chars.next(), chars.next()) {
+            _ if self.error.is_some() => return Err(self.error.unwrap()),
+            (None, _) => Some("empty char literal"),
+            (Some(_), Some(_)) => Some("char literal must contain exactly one character"),
+            (Some(c), None) if self.byte && !self.hex && !c.is_ascii() => {
+                Some("byte literal must be an ASCII character")
+            }
+            _ => None,
+        };
+
+        match (message, self.byte) {
+            (Some(message), _) => Err(LexError::new(message, self.line, self.column)),
+            (None, true) => Ok((TokenType::Byte, self.value)),
+            (None, false) => Ok((TokenType::Char, self.value)),
+        }
+    }
+
+    // The escape sequence can't be decoded, or it's unfinished at the end of the line.
+    fn invalid_escape(&mut self, seq: &str, line: usize, column: usize) {
+        let message = match seq.chars().next() {
+            Some('u') => format!("invalid unicode escape `\\{}`", seq),
+            Some('x') => format!("invalid hex escape `\\{}`", seq),
+            _ => format!("unknown escape sequence `\\{}`", seq),
+        };
+        self.escape_error(&message, line, column);
+    }
+
+    // Only the first error in the string is reported.
+    fn escape_error(&mut self, message: &str, line: usize, column: usize) {
+        if self.error.is_none() {
+            self.error = Some(LexError::new(message, line, column));
+        }
+    }
+}
+
 pub struct Lexer {
     // The file that lexer is parsing.
-    woc_file: WocFile,
+    woc_file: Rc<WocFile>,
 
-    // Start index of token in command.
+    // Start index of token in the chars of the source code.
     start_index: Cell<usize>,
 
-    // Current index of token vector.
+    // Current index of the chars of the source code.
     cur_index: Cell<usize>,
 
-    // The number of lines of code currently parsed
-    cur_line: Cell<usize>,
+    // The whole source code is scanned once, so the tokens can span lines.
+    // The chars and the offsets are shared with the lexers of the embedded expressions in the strings.
+    code_chars: Rc<[char]>,
 
-    // The number of columns of code currently parsed
-    cur_tok_num: Cell<usize>,
+    // The byte offset of every char, and the length of the source code at the end.
+    offsets: Rc<[usize]>,
 
-    // The code that is currently being parsed.
-    cur_code_chars: Vec<char>,
+    // The chars before the end index are scanned, an embedded expression ends before the source code.
+    end_index: usize,
 
     // Store the tokens that are parsed.
     tokens: RefCell<Vec<Rc<Token>>>,
@@ -31,12 +219,28 @@ pub struct Lexer {
     // This is a key field to show the state about lexer at now.
     // It's used to define the type of the token currently.
     cur_state: Cell<State>,
+
+    // The nesting depth of the block comment.
+    comment_depth: Cell<usize>,
+
+    // The previous char in the block comment that may start a `/*` or `*/`.
+    comment_prev: Cell<Option<char>>,
+
+    // The char index of the outermost block comment, the unterminated block comment is reported at it.
+    comment_start: Cell<usize>,
+
+    // The string literal that is not closed yet.
+    string: RefCell<Option<StringLiteral>>,
+
+    // The errors found in the source code, every error is stored as an illegal token as well.
+    errors: RefCell<Vec<LexError>>,
 }
 
 /// This is a struct that is used to iterate the tokens.
 pub struct TokensIter {
-    woc_file: WocFile,
+    woc_file: Rc<WocFile>,
     tokens: Vec<Rc<Token>>,
+    errors: Vec<LexError>,
     position: Cell<usize>,
 }
 
@@ -52,50 +256,132 @@ impl TokensIter {
 
         Some(token)
     }
+
+    /// Get the source file of the tokens.
+    pub fn woc_file(&self) -> &WocFile {
+        &self.woc_file
+    }
+
+    /// Get the errors found by the lexer in the order of the source code.
+    pub fn errors(&self) -> &[LexError] {
+        &self.errors
+    }
 }
 
 impl Lexer {
-    /// Creates a new [`Lexer`].
-    pub fn new(file_path: &str) -> Lexer {
-        let mut l = Lexer {
-            woc_file: WocFile::new(file_path.to_string()),
-            start_index: Cell::new(0),
-            cur_index: Cell::new(0),
-            cur_line: Cell::new(0),
-            cur_tok_num: Cell::new(1),
-            cur_code_chars: Vec::new(),
+    /// Creates a new [`Lexer`] that analyzes the source file, it fails if the file can't be read.
+    pub fn new(file_path: &str) -> Result<Lexer, String> {
+        Ok(Self::from_file(WocFile::new(file_path.to_string())?))
+    }
+
+    /// Creates a new [`Lexer`] that analyzes the source code in memory.
+    pub fn from_source(source: &str) -> Lexer {
+        Self::from_file(WocFile::from_source("", source))
+    }
+
+    fn from_file(woc_file: WocFile) -> Lexer {
+        let source = woc_file.source();
+        let code_chars: Rc<[char]> = source.chars().collect();
+        let mut offsets: Vec<usize> = source.char_indices().map(|(offset, _)| offset).collect();
+        offsets.push(source.len());
+
+        let end = code_chars.len();
+        Self::scan(Rc::new(woc_file), code_chars, offsets.into(), 0, end)
+    }
+
+    // Analyze the chars in `[start, end)` of the source code, the tokens end with an EOF token at the end.
+    fn scan(
+        woc_file: Rc<WocFile>,
+        code_chars: Rc<[char]>,
+        offsets: Rc<[usize]>,
+        start: usize,
+        end: usize,
+    ) -> Lexer {
+        let l = Lexer {
+            woc_file,
+            start_index: Cell::new(start),
+            cur_index: Cell::new(start),
+            code_chars,
+            offsets,
+            end_index: end,
             tokens: RefCell::new(Vec::new()),
             cur_state: Cell::new(State::StartState),
+            comment_depth: Cell::new(0),
+            comment_prev: Cell::new(None),
+            comment_start: Cell::new(start),
+            string: RefCell::new(None),
+            errors: RefCell::new(Vec::new()),
         };
 
-        loop {
-            let (line, code) = match l.woc_file.read_line() {
-                Some((line, code)) => (line, code),
-                None => break,
-            };
+        l.analyze_source(start);
 
-            l.cur_line.set(line);
-            l.cur_code_chars = code.chars().collect();
-            l.analyze_command();
-            l.initialize();
+        // The string or the block comment is not closed until the end of the file.
+        // The error of the string is reported at the opening quote, but its token starts after
+        // the segments and the embedded expressions that are stored, so the tokens don't overlap.
+        if let Some(string) = l.string.take() {
+            let start = match string.interpolation {
+                Some(interpolation) => interpolation.start,
+                None => string.segment,
+            };
+            l.push_error(
+                LexError::new("unterminated string starting", string.line, string.column),
+                start,
+                end,
+            );
+        } else if l.cur_state_is(State::BlockCommentState) {
+            let start = l.comment_start.get();
+            let (line, column) = l.position(start);
+            l.push_error(
+                LexError::new("unterminated block comment starting", line, column),
+                start,
+                end,
+            );
         }
 
-        l.tokens.borrow_mut().push(Rc::new(Token::new(
-            TokenType::Eof,
-            "",
-            l.woc_file.get_path(),
-            l.cur_line.get() + 1,
-            l.cur_tok_num.get(),
-        )));
+        l.push_token(TokenType::Eof, "", end, end);
 
         l
     }
 
-    fn initialize(&self) {
-        self.start_index.set(0);
-        self.cur_index.set(0);
-        self.cur_state.set(State::StartState);
-        self.cur_tok_num.set(1);
+    /// Attach the trivia to every token, so the concatenated text of the tokens and their trivia
+    /// reproduces the source file byte for byte. It's used by the tools that rewrite the source code.
+    pub fn with_trivia(self) -> Lexer {
+        let source = self.woc_file.source();
+        let mut tokens = self.tokens.borrow_mut();
+
+        // The BOM is not a part of the source code, it's kept in the leading trivia of the first token.
+        let mut leading = match self.woc_file.has_bom() {
+            true => "\u{FEFF}".to_string(),
+            false => String::new(),
+        };
+        let mut pos = 0;
+        for i in 0..tokens.len() {
+            // The spans of the tokens don't overlap, but the text is never taken twice if they do.
+            let offset = tokens[i].offset().max(pos);
+            let end = tokens[i].end().max(offset);
+            let next = tokens.get(i + 1).map_or(source.len(), |t| t.offset()).max(end);
+            let trailing_end = end + trailing_trivia_len(&source[end..next]);
+
+            leading.push_str(&source[pos..offset]);
+            let trivia = Trivia::new(&leading, &source[offset..end], &source[end..trailing_end]);
+            tokens[i] = Rc::new(tokens[i].as_ref().clone().with_trivia(trivia));
+
+            leading.clear();
+            pos = trailing_end;
+        }
+        drop(tokens);
+
+        self
+    }
+
+    /// Get the source file that the lexer is analyzing.
+    pub fn woc_file(&self) -> &WocFile {
+        &self.woc_file
+    }
+
+    /// Get the errors found by the lexer in the order of the source code.
+    pub fn errors(&self) -> Vec<LexError> {
+        self.errors.borrow().clone()
     }
 
     /// Creates a new [`LexerIter`].
@@ -104,6 +390,7 @@ impl Lexer {
         TokensIter {
             woc_file: self.woc_file,
             tokens: self.tokens.into_inner(),
+            errors: self.errors.into_inner(),
             position: Cell::new(0),
         }
     }
@@ -113,20 +400,36 @@ impl Lexer {
         self.tokens.borrow_mut().clear();
     }
 
-    // Analyze the command and generate tokens.
-    fn analyze_command(&self) {
-        // Iterate the command char by char.
-        for (index, c) in self.cur_code_chars.iter().enumerate() {
+    // Analyze the whole source code and generate tokens.
+    fn analyze_source(&self, start: usize) {
+        // Iterate the source code char by char.
+        for (index, c) in self.code_chars[..self.end_index].iter().enumerate().skip(start) {
             // Update self.cur_index.
             self.cur_index.set(index);
 
+            // The chars in the line comment are skipped until the line break.
+            if self.cur_state_is(State::LineCommentState) {
+                if c.eq(&'\n') {
+                    self.set_state(State::StartState);
+                }
+                continue;
+            }
+
+            // The chars in the block comment are skipped.
+            if self.cur_state_is(State::BlockCommentState) {
+                self.scan_block_comment(index, *c);
+                continue;
+            }
+
+            // The chars in the string are scanned until the closing quote.
+            if self.cur_state_is(State::StringState) {
+                self.scan_string(index, *c);
+                continue;
+            }
+
             // If the char is blank, we need to store the token and transform the state.
             if c.is_whitespace() {
-                if self.cur_state_is(State::StringState) {
-                    self.set_state(State::StringState);
-                } else {
-                    self.store_token_and_trans_state();
-                }
+                self.store_token_and_trans_state();
                 continue;
             }
 
@@ -134,454 +437,64 @@ impl Lexer {
             match state {
                 State::StartState => self.trans_state(&c),
 
-                // =============== keywords ===============
-                // ============ while ============
-                State::WhileState1 => {
-                    if c.eq(&'h') {
-                        self.set_state(State::WhileState2);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::WhileState2 => {
-                    if c.eq(&'i') {
-                        self.set_state(State::WhileState3);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::WhileState3 => {
-                    if c.eq(&'l') {
-                        self.set_state(State::WhileState4);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::WhileState4 => {
-                    if c.eq(&'e') {
-                        self.set_state(State::WhileState);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                // ============ for ============
-                State::ForState1 => {
-                    if c.eq(&'o') {
-                        self.set_state(State::ForState2)
-                    } else if c.eq(&'u') {
-                        self.set_state(State::FuncState2);
-                    } else if c.eq(&'a') {
-                        self.set_state(State::FalseState2);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::ForState2 => {
-                    if c.eq(&'r') {
-                        self.set_state(State::ForState);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                // ============ if ============
-                State::IfState1 => {
-                    if c.eq(&'f') {
-                        self.set_state(State::IfState);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                // ============ else ============
-                State::ElseState1 => {
-                    if c.eq(&'l') {
-                        self.set_state(State::ElseState2);
-                    } else if c.eq(&'n') {
-                        self.set_state(State::EnumState2);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::ElseState2 => {
-                    if c.eq(&'s') {
-                        self.set_state(State::ElseState3);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::ElseState3 => {
-                    if c.eq(&'e') {
-                        self.set_state(State::ElseState);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                // ============ break ============
-                State::BreakState1 => {
-                    if c.eq(&'r') {
-                        self.set_state(State::BreakState2);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::BreakState2 => {
-                    if c.eq(&'e') {
-                        self.set_state(State::BreakState3);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::BreakState3 => {
-                    if c.eq(&'a') {
-                        self.set_state(State::BreakState4);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::BreakState4 => {
-                    if c.eq(&'k') {
-                        self.set_state(State::BreakState);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                // ============ continue ============
-                State::ContinueState1 => {
-                    if c.eq(&'o') {
-                        self.set_state(State::ContinueState2)
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::ContinueState2 => {
-                    if c.eq(&'n') {
-                        self.set_state(State::ContinueState3);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::ContinueState3 => {
-                    if c.eq(&'t') {
-                        self.set_state(State::ContinueState4);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::ContinueState4 => {
-                    if c.eq(&'i') {
-                        self.set_state(State::ContinueState5);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::ContinueState5 => {
-                    if c.eq(&'n') {
-                        self.set_state(State::ContinueState6);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::ContinueState6 => {
-                    if c.eq(&'u') {
-                        self.set_state(State::ContinueState7);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::ContinueState7 => {
-                    if c.eq(&'e') {
-                        self.set_state(State::ContinueState);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                // ============ let ============
-                State::LetState1 => {
-                    if c.eq(&'e') {
-                        self.set_state(State::LetState2);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::LetState2 => {
-                    if c.eq(&'t') {
-                        self.set_state(State::LetState);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                // ============ function ============
-                State::FuncState2 => {
-                    if c.eq(&'n') {
-                        self.set_state(State::FuncState3);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::FuncState3 => {
-                    if c.eq(&'c') {
-                        self.set_state(State::FuncState);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                // ============ return ============
-                State::ReturnState1 => {
-                    if c.eq(&'e') {
-                        self.set_state(State::ReturnState2);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::ReturnState2 => {
-                    if c.eq(&'t') {
-                        self.set_state(State::ReturnState3);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::ReturnState3 => {
-                    if c.eq(&'u') {
-                        self.set_state(State::ReturnState4);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::ReturnState4 => {
-                    if c.eq(&'r') {
-                        self.set_state(State::ReturnState5);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::ReturnState5 => {
-                    if c.eq(&'n') {
-                        self.set_state(State::ReturnState);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                // ============ struct ============
-                State::StructState1 => {
-                    if c.eq(&'t') {
-                        self.set_state(State::StructState2);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::StructState2 => {
-                    if c.eq(&'r') {
-                        self.set_state(State::StructState3);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::StructState3 => {
-                    if c.eq(&'u') {
-                        self.set_state(State::StructState4);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::StructState4 => {
-                    if c.eq(&'c') {
-                        self.set_state(State::StructState5);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::StructState5 => {
-                    if c.eq(&'t') {
-                        self.set_state(State::StructState);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                // ============ enum ============
-                State::EnumState2 => {
-                    if c.eq(&'u') {
-                        self.set_state(State::EnumState3);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::EnumState3 => {
-                    if c.eq(&'m') {
-                        self.set_state(State::EnumState);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                // =============== none ===============
-                State::NoneState1 => {
-                    if c.eq(&'o') {
-                        self.set_state(State::NoneState2);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::NoneState2 => {
-                    if c.eq(&'n') {
-                        self.set_state(State::NoneState3);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::NoneState3 => {
-                    if c.eq(&'e') {
-                        self.set_state(State::NoneState);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                // =============== true ===============
-                State::TrueState1 => {
-                    if c.eq(&'r') {
-                        self.set_state(State::TrueState2);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::TrueState2 => {
-                    if c.eq(&'u') {
-                        self.set_state(State::TrueState3);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::TrueState3 => {
-                    if c.eq(&'e') {
-                        self.set_state(State::TrueState);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                // =============== false ===============
-                State::FalseState2 => {
-                    if c.eq(&'l') {
-                        self.set_state(State::FalseState3);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::FalseState3 => {
-                    if c.eq(&'s') {
-                        self.set_state(State::FalseState4);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::FalseState4 => {
-                    if c.eq(&'e') {
-                        self.set_state(State::FalseState);
-                    } else {
-                        self.trans_2_ident_or_store_token(c);
-                    }
-                }
-
-                State::WhileState
-                | State::ForState
-                | State::IfState
-                | State::ElseState
-                | State::BreakState
-                | State::ContinueState
-                | State::LetState
-                | State::FuncState
-                | State::ReturnState
-                | State::StructState
-                | State::EnumState
-                | State::NoneState
-                | State::TrueState
-                | State::FalseState => self.store_token_and_trans_state(),
-
                 // =============== number ===============
+                // The invalid digits and suffixes are kept in the literal of the number,
+                // and they are reported by the parser.
                 State::IntegerNumState => {
-                    if c.is_numeric() || (state == State::IntegerNumState && c.eq(&'_')) {
-                        self.set_state(State::IntegerNumState)
-                    } else if c.eq(&'.') {
+                    if c.eq(&'.') {
+                        self.set_state(State::FloatNumState)
+                    } else if (c.eq(&'e') || c.eq(&'E')) && !self.is_hex_number(index) {
+                        // The exponent of a float number: 1e9
                         self.set_state(State::FloatNumState)
-                    } else if c.is_alphabetic() {
-                        self.set_state(State::IdentState)
+                    } else if c.is_alphanumeric() || c.eq(&'_') {
+                        self.set_state(State::IntegerNumState)
                     } else {
                         self.store_token_and_trans_state();
                     }
                 }
 
                 State::FloatNumState => {
-                    if c.is_numeric() {
+                    if c.is_alphanumeric() || c.eq(&'_') {
+                        self.set_state(State::FloatNumState)
+                    } else if (c.eq(&'+') || c.eq(&'-'))
+                        && matches!(self.code_chars[index - 1], 'e' | 'E')
+                    {
+                        // The sign of the exponent: 1e-9
                         self.set_state(State::FloatNumState)
-                    } else if c.is_alphabetic() {
-                        self.set_state(State::IdentState)
                     } else {
                         self.store_token_and_trans_state();
                     }
                 }
 
                 // =============== Literal ===============
+                // The keywords are scanned as identifiers, and they are looked up when the token is stored.
                 State::IdentState => {
-                    if !(c.is_alphanumeric() || c.eq(&'_')) {
+                    if c.eq(&'"') && self.cur_ident_is("r") {
+                        // The raw string: r"..."
+                        self.set_state(State::StringState);
+                    } else if c.eq(&'\'') && self.cur_ident_is("b") {
+                        // The byte literal: b'a'
+                        self.set_state(State::StringState);
+                    } else if !(c.is_alphanumeric() || c.eq(&'_')) {
                         self.store_token_and_trans_state();
                     }
                 }
 
                 // =============== string ===============
-                State::StringState => {
-                    if c.eq(&'"') {
-                        self.store_token_and_trans_state()
-                    } else if c.eq(&'\\') {
-                        self.set_state(State::StringState);
-                    } else if c.is_alphanumeric() || c.is_whitespace() {
-                        self.set_state(State::StringState)
+                // The string has been scanned before matching the state.
+                State::StringState => {}
+
+                // =============== single symbols ===============
+                State::DotState => {
+                    // A float number can start with a dot: .5
+                    if c.is_numeric() {
+                        self.set_state(State::FloatNumState)
+                    } else {
+                        self.store_token_and_trans_state();
                     }
                 }
 
-                // =============== single symbols ===============
                 State::CommaState
-                | State::DotState
                 | State::SemiColonState
                 | State::ColonState
                 | State::LeftParenState
@@ -655,6 +568,8 @@ impl Lexer {
                 State::MinusState => {
                     if c.eq(&'=') {
                         self.set_state(State::MinusAssignState);
+                    } else if c.eq(&'>') {
+                        self.set_state(State::ArrowState);
                     } else if c.is_numeric() {
                         self.set_state(State::IntegerNumState);
                     } else {
@@ -673,6 +588,14 @@ impl Lexer {
                 State::SlashState => {
                     if c.eq(&'=') {
                         self.set_state(State::SlashAssignState);
+                    } else if c.eq(&'/') {
+                        // The rest of the line is a comment, and the first '/' is at the previous index.
+                        self.skip_line_comment(index - 1);
+                    } else if c.eq(&'*') {
+                        self.comment_start.set(index - 1);
+                        self.comment_depth.set(1);
+                        self.comment_prev.set(None);
+                        self.set_state(State::BlockCommentState);
                     } else {
                         self.store_token_and_trans_state();
                     }
@@ -687,46 +610,29 @@ impl Lexer {
                 | State::PlusAssignState
                 | State::MinusAssignState
                 | State::StarAssignState
-                | State::SlashAssignState => {
+                | State::SlashAssignState
+                | State::ArrowState => {
                     self.store_token_and_trans_state();
                 }
 
-                // ============ Underline ============
-                State::UnderscoreState => {
-                    if c.is_alphabetic() || c.is_numeric() {
-                        self.set_state(State::IdentState);
-                    } else {
-                        self.store_token_and_trans_state();
-                    }
-                }
+                // ============ Illegal ============
+                // The illegal char is a token by itself.
+                State::IllegalState => self.store_token_and_trans_state(),
 
-                // =============== end ===============
-                State::EndState => break,
+                // =============== comments ===============
+                // The comments have been scanned before matching the state.
+                State::LineCommentState | State::BlockCommentState => {}
             }
         }
 
-        // If the state of lexer is not end, we need to store the last token.
-        if !self.cur_state_is(State::EndState) {
-            // store the last state
-            let last_state = self.cur_state.get();
-            // Transform the state by current char.
-            self.trans_state(&self.cur_code_chars[self.cur_index.get()]);
-
-            // If the state is changed, we need to store the current state and recover the last state.
-            if !self.cur_state_is(last_state) {
-                let cur_state = self.cur_state.get();
-                self.set_state(last_state);
-                self.store_token_and_trans_state();
-                self.set_state(cur_state);
-            }
-
-            // Store the last token.
-            self.cur_index.set(self.cur_code_chars.len());
-            self.store_token_and_trans_state();
+        // Store the last token at the end of the source code, the comment is skipped,
+        // and the string that is not closed is reported by the caller.
+        self.cur_index.set(self.end_index);
+        match self.cur_state.get() {
+            State::StringState if self.string.borrow().is_none() => self.open_string(),
+            State::StringState | State::LineCommentState | State::BlockCommentState => {}
+            _ => self.store_token_and_trans_state(),
         }
-
-        // Add a EOF token to the end for the parser to determine the end of the command.
-        self.cur_state.set(State::EndState);
     }
 
     // Store token and transform state.
@@ -737,68 +643,327 @@ impl Lexer {
             return;
         }
 
-        // Match the state to get the token type.
-        let token_type = self.trans_to_token_type();
-
         // Get the literal of token from char vector.
-        let literal = match token_type {
-            TokenType::String => {
-                let literal: String = self.cur_code_chars
-                    [self.start_index.get() + 1..self.cur_index.get()]
-                    .iter()
-                    .collect();
-
-                // The reason we need to add 1 here is that the cur_index is at the second '"' character.
-                // It should move to the next character to ensure the parsing of the next token.
-                self.start_index.set(self.cur_index.get() + 1);
-
-                literal
+        let (start, end) = (self.start_index.get(), self.cur_index.get());
+        let literal: String = self.code_chars[start..end].iter().collect();
+
+        // Match the state to get the token type, the identifier may be a keyword.
+        let token_type = match self.trans_to_token_type() {
+            TokenType::Ident => keywords::lookup(&literal).unwrap_or(TokenType::Ident),
+            token_type => token_type,
+        };
+        if token_type == TokenType::Illegal {
+            let message = format!("illegal character `{}`", literal.escape_debug());
+            let (line, column) = self.position(start);
+            self.push_error(LexError::new(&message, line, column), start, end);
+        } else {
+            self.push_token(token_type, &literal, start, end);
+        }
+        self.start_index.set(end);
+
+        // Reset the state of lexer.
+        self.set_state(State::StartState);
+
+        // Judge whether the state should be reset or be ended.
+        if self.start_index.get() < self.end_index {
+            // Reset lexer state
+            self.trans_state(&self.code_chars[self.start_index.get()]);
+        }
+    }
+
+    // Skip the line comment that starts at the index, the doc comment is stored as a token,
+    // so the parser can attach it to the following declaration.
+    fn skip_line_comment(&self, start: usize) {
+        let mut end = start;
+        while end < self.end_index && !matches!(self.code_chars[end], '\r' | '\n') {
+            end += 1;
+        }
+        let comment: String = self.code_chars[start..end].iter().collect();
+
+        // The comment starts with `////` is a normal comment.
+        if comment.starts_with("///") && !comment.starts_with("////") {
+            self.push_token(TokenType::DocComment, comment[3..].trim(), start, end);
+        }
+
+        self.start_index.set(end);
+        self.set_state(State::LineCommentState);
+    }
+
+    // Scan a char in the block comment, the block comments can be nested.
+    fn scan_block_comment(&self, index: usize, c: char) {
+        match (self.comment_prev.get(), c) {
+            (Some('/'), '*') => {
+                self.comment_depth.set(self.comment_depth.get() + 1);
+                self.comment_prev.set(None);
             }
-            _ => {
-                let literal: String = self.cur_code_chars
-                    [self.start_index.get()..self.cur_index.get()]
-                    .iter()
-                    .collect();
+            (Some('*'), '/') => {
+                self.comment_depth.set(self.comment_depth.get() - 1);
+                self.comment_prev.set(None);
 
-                self.start_index.set(self.cur_index.get());
+                // The outermost block comment is closed, the next token starts after it.
+                if self.comment_depth.get() == 0 {
+                    self.start_index.set(index + 1);
+                    self.set_state(State::StartState);
+                }
+            }
+            _ => self.comment_prev.set(Some(c)),
+        }
+    }
+
+    // Start the string at the start index, the quote may follow the `r` of a raw string
+    // or the `b` of a byte literal.
+    fn open_string(&self) {
+        self.move_start_index_to_next_non_blank_char();
+        let start = self.start_index.get();
+        let prefix = self.code_chars[start];
+        let quote = start + usize::from(prefix == 'r' || prefix == 'b');
+        let quote_char = self.code_chars[quote];
+        let triple = quote_char == '"'
+            && self.char_at(quote + 1) == Some('"')
+            && self.char_at(quote + 2) == Some('"');
+        let (line, column) = self.position(quote);
+
+        *self.string.borrow_mut() = Some(StringLiteral {
+            line,
+            column,
+            start,
+            segment: start,
+            quote: quote_char,
+            byte: prefix == 'b',
+            hex: false,
+            raw: prefix == 'r',
+            triple,
+            skip: if triple { 2 } else { 0 },
+            quotes: 0,
+            escape: None,
+            segments: 0,
+            interpolation: None,
+            value: String::new(),
+            error: None,
+        });
+    }
 
-                literal
+    // Scan a char in the string, the escape sequences are decoded except in a raw string.
+    fn scan_string(&self, index: usize, c: char) {
+        if self.string.borrow().is_none() {
+            self.open_string();
+        }
+
+        let step = {
+            let mut string = self.string.borrow_mut();
+            let string = string.as_mut().unwrap();
+
+            if string.skip > 0 {
+                string.skip -= 1;
+                StringStep::Continue
+            } else if let Some(interpolation) = string.interpolation.as_mut() {
+                match interpolation.push(c) {
+                    true => StringStep::EndInterpolation,
+                    false => StringStep::Continue,
+                }
+            } else if c.eq(&'\n') {
+                StringStep::LineBreak
+            } else if c.eq(&'\r') && self.char_at(index + 1) == Some('\n') {
+                // The "\r\n" line break is a '\n' in the string.
+                StringStep::Continue
+            } else if string.escape.is_some() {
+                string.push_escape(c);
+                StringStep::Continue
+            } else if c.eq(&'"') && string.triple {
+                string.quotes += 1;
+                match string.quotes {
+                    3 => StringStep::Close,
+                    _ => StringStep::Continue,
+                }
+            } else if c.eq(&string.quote) {
+                StringStep::Close
+            } else {
+                string.flush_quotes();
+                if string.raw {
+                    string.value.push(c);
+                    StringStep::Continue
+                } else if c.eq(&'$')
+                    && string.quote == '"'
+                    && self.char_at(index + 1) == Some('{')
+                {
+                    StringStep::StartInterpolation
+                } else if c.eq(&'\\') {
+                    let (line, column) = self.position(index);
+                    string.escape = Some((line, column, String::new()));
+                    StringStep::Continue
+                } else {
+                    string.value.push(c);
+                    StringStep::Continue
+                }
             }
         };
 
-        self.tokens.borrow_mut().push(Rc::new(Token::new(
-            token_type,
-            &literal,
-            self.woc_file.get_path(),
-            self.cur_line.get(),
-            self.cur_tok_num.get(),
-        )));
+        match step {
+            StringStep::Continue => {}
+            StringStep::LineBreak => self.end_string_line(index),
+            StringStep::Close => self.close_string(index),
+            StringStep::StartInterpolation => self.start_interpolation(index),
+            StringStep::EndInterpolation => self.end_interpolation(index),
+        }
+    }
 
-        // Update the column number.
-        self.cur_tok_num.set(self.cur_tok_num.get() + 1);
+    // Store the string token, or the illegal token with the error found in the string.
+    fn close_string(&self, index: usize) {
+        let string = self.string.take().unwrap();
+        let segment = string.segment;
+        let token = match string.error {
+            _ if string.quote == '\'' => string.char_token(),
+            Some(error) => Err(error),
+            None if string.segments > 0 => Ok((TokenType::StringTail, string.value)),
+            None => Ok((TokenType::String, string.value)),
+        };
+        match token {
+            Ok((token_type, literal)) => self.push_token(token_type, &literal, segment, index + 1),
+            Err(error) => self.push_error(error, segment, index + 1),
+        }
 
-        // Reset the state of lexer.
+        // The next token starts after the closing quote.
+        self.start_index.set(index + 1);
         self.set_state(State::StartState);
+    }
 
-        // Judge whether the state should be reset or be ended.
-        if self.start_index.get() < self.cur_code_chars.len() {
-            // Reset lexer state
-            self.trans_state(&self.cur_code_chars[self.start_index.get()]);
+    // Store the segment before the `${` at the index, and start collecting the embedded expression.
+    fn start_interpolation(&self, index: usize) {
+        let mut string = self.string.borrow_mut();
+        let string = string.as_mut().unwrap();
+
+        let token_type = match string.segments {
+            0 => TokenType::StringHead,
+            _ => TokenType::StringMiddle,
+        };
+        self.push_token(
+            token_type,
+            &std::mem::take(&mut string.value),
+            string.segment,
+            index + 2,
+        );
+
+        string.segments += 1;
+        // The `{` after the `$` is skipped.
+        string.skip = 1;
+        let (line, column) = self.position(index);
+        string.interpolation = Some(Interpolation {
+            line,
+            column,
+            start: index + 2,
+            source: String::new(),
+            depth: 0,
+            quoted: None,
+            escaped: false,
+        });
+    }
+
+    // Lex the embedded expression that ends before the `}` at the index,
+    // its tokens are stored between the segments of the string.
+    fn end_interpolation(&self, index: usize) {
+        let mut string = self.string.borrow_mut();
+        let string = string.as_mut().unwrap();
+        let interpolation = string.interpolation.take().unwrap();
+        string.segment = index;
+
+        if interpolation.source.trim().is_empty() {
+            if string.error.is_none() {
+                string.error = Some(LexError::new(
+                    "empty interpolation `${}`",
+                    interpolation.line,
+                    interpolation.column,
+                ));
+            }
+            return;
         }
+
+        // The expression is lexed in place, so its tokens and errors have the positions in the file.
+        let lexer = Lexer::scan(
+            self.woc_file.clone(),
+            self.code_chars.clone(),
+            self.offsets.clone(),
+            interpolation.start,
+            index,
+        );
+        let mut tokens = lexer.tokens.into_inner();
+        tokens.pop();
+        self.tokens.borrow_mut().extend(tokens);
+        self.errors.borrow_mut().extend(lexer.errors.into_inner());
+    }
+
+    // The line ends at the index in the string, the line break is a part of the string.
+    fn end_string_line(&self, index: usize) {
+        // The char literal can't span multiple lines.
+        if self.string.borrow().as_ref().unwrap().quote == '\'' {
+            let string = self.string.take().unwrap();
+            self.push_error(
+                LexError::new(
+                    "unterminated char literal starting",
+                    string.line,
+                    string.column,
+                ),
+                string.start,
+                index,
+            );
+            self.start_index.set(index);
+            self.set_state(State::StartState);
+            return;
+        }
+
+        let mut string = self.string.borrow_mut();
+        let string = string.as_mut().unwrap();
+
+        string.flush_quotes();
+        match string.escape.take() {
+            Some((_, _, seq)) if seq.is_empty() => string.value.push('\\'),
+            Some((line, column, seq)) => string.invalid_escape(&seq, line, column),
+            None => {}
+        }
+
+        // The line break right after the opening quotes of a triple-quoted string is skipped.
+        let (line, _) = self.position(index);
+        if !(string.triple && string.line == line && string.value.is_empty()) {
+            string.value.push('\n');
+        }
+    }
+
+    // Store a token whose source text is the chars in `[start, end)`.
+    fn push_token(&self, token_type: TokenType, literal: &str, start: usize, end: usize) {
+        let (line, column) = self.position(start);
+        self.tokens.borrow_mut().push(Rc::new(
+            Token::new(token_type, literal, self.woc_file.get_path(), line, column)
+                .with_span(self.offsets[start], self.offsets[end]),
+        ));
+    }
+
+    // Store the illegal token of the error whose source text is the chars in `[start, end)`.
+    fn push_error(&self, error: LexError, start: usize, end: usize) {
+        self.push_token(TokenType::Illegal, &error.to_string(), start, end);
+        self.errors.borrow_mut().push(error);
+    }
+
+    // Get the char at the index if it's scanned by the lexer.
+    fn char_at(&self, index: usize) -> Option<char> {
+        self.code_chars[..self.end_index].get(index).copied()
+    }
+
+    // Get the line and the column of the char at the index.
+    fn position(&self, index: usize) -> (usize, usize) {
+        self.woc_file.position(self.offsets[index])
     }
 
     fn move_start_index_to_next_non_blank_char(&self) {
         let mut index = self.start_index.get();
 
         // Move index to next non-blank char.
-        while index < self.cur_code_chars.len() && self.cur_code_chars[index].is_whitespace() {
+        while index < self.end_index && self.code_chars[index].is_whitespace() {
             index += 1;
         }
 
         // If index is out of range, we need to set it to the end of command.
         // It means from cur_index to the end of command are all blank chars.
-        if index >= self.cur_code_chars.len() {
-            index = self.cur_code_chars.len();
+        if index >= self.end_index {
+            index = self.end_index;
         }
 
         self.start_index.set(index);
@@ -806,102 +971,12 @@ impl Lexer {
 
     // Transform lexer state by the current char.
     fn trans_state(&self, c: &char) {
-        if self.cur_state_is(State::EndState) {
-            return;
-        }
-
         match c {
-            // =============== keywords ===============
-            'w' => {
-                if self.cur_state_is(State::IdentState) {
-                    self.set_state(State::IdentState)
-                } else {
-                    self.set_state(State::WhileState1)
-                }
-            }
-            'f' => {
-                if self.cur_state_is(State::IdentState) {
-                    self.set_state(State::IdentState)
-                } else {
-                    self.set_state(State::ForState1)
-                }
-            }
-            'i' => {
-                if self.cur_state_is(State::IdentState) {
-                    self.set_state(State::IdentState)
-                } else {
-                    self.set_state(State::IfState1)
-                }
-            }
-            'e' => {
-                if self.cur_state_is(State::IdentState) {
-                    self.set_state(State::IdentState)
-                } else {
-                    self.set_state(State::ElseState1)
-                }
-            }
-            'b' => {
-                if self.cur_state_is(State::IdentState) {
-                    self.set_state(State::IdentState)
-                } else {
-                    self.set_state(State::BreakState1)
-                }
-            }
-            'c' => {
-                if self.cur_state_is(State::IdentState) {
-                    self.set_state(State::IdentState)
-                } else {
-                    self.set_state(State::ContinueState1)
-                }
-            }
-            'l' => {
-                if self.cur_state_is(State::IdentState) {
-                    self.set_state(State::IdentState)
-                } else {
-                    self.set_state(State::LetState1)
-                }
-            }
-            'r' => {
-                if self.cur_state_is(State::IdentState) {
-                    self.set_state(State::IdentState)
-                } else {
-                    self.set_state(State::ReturnState1)
-                }
-            }
-            's' => {
-                if self.cur_state_is(State::IdentState) {
-                    self.set_state(State::IdentState)
-                } else {
-                    self.set_state(State::StructState1)
-                }
-            }
-            'n' => {
-                if self.cur_state_is(State::IdentState) {
-                    self.set_state(State::IdentState)
-                } else {
-                    self.set_state(State::NoneState1)
-                }
-            }
-            't' => {
-                if self.cur_state_is(State::IdentState) {
-                    self.set_state(State::IdentState)
-                } else {
-                    self.set_state(State::TrueState1)
-                }
-            }
+            // =============== identifier and keywords ===============
+            c if c.is_alphabetic() || c.eq(&'_') => self.set_state(State::IdentState),
 
             // =============== number ===============
-            '0'..='9' => {
-                if self.cur_state_is(State::IdentState) {
-                    self.set_state(State::IdentState)
-                } else if self.cur_state_is(State::IntegerNumState) {
-                    self.set_state(State::IntegerNumState)
-                } else if self.cur_state_is(State::FloatNumState) {
-                    self.set_state(State::FloatNumState)
-                } else {
-                    self.set_state(State::IntegerNumState)
-                }
-            }
+            '0'..='9' => self.set_state(State::IntegerNumState),
 
             // =============== symbols ===============
             ',' => self.set_state(State::CommaState),
@@ -926,20 +1001,30 @@ impl Lexer {
             '~' => self.set_state(State::BitNotState),
 
             // =============== Others ===============
-            '"' => self.set_state(State::StringState),
+            '"' | '\'' => self.set_state(State::StringState),
             '(' => self.set_state(State::LeftParenState),
             ')' => self.set_state(State::RightParenState),
             '{' => self.set_state(State::LeftBraceState),
             '}' => self.set_state(State::RightBraceState),
             '[' => self.set_state(State::LeftBracketState),
             ']' => self.set_state(State::RightBracketState),
-            '_' => self.set_state(State::UnderscoreState),
-            ' ' | '\n' => self.set_state(State::StartState),
+            c if c.is_whitespace() => self.set_state(State::StartState),
 
-            _ => self.set_state(State::IdentState),
+            _ => self.set_state(State::IllegalState),
         }
     }
 
+    // Check whether the number before the index is a hexadecimal number, the 'e' in it is a digit.
+    fn is_hex_number(&self, index: usize) -> bool {
+        let literal: String = self.code_chars[self.start_index.get()..index]
+            .iter()
+            .collect();
+        let literal = literal.trim_start();
+        let literal = literal.strip_prefix('-').unwrap_or(literal);
+
+        literal.starts_with("0x") || literal.starts_with("0X")
+    }
+
     fn cur_state_is(&self, state: State) -> bool {
         self.cur_state.get() == state
     }
@@ -948,12 +1033,11 @@ impl Lexer {
         self.cur_state.set(state);
     }
 
-    fn trans_2_ident_or_store_token(&self, c: &char) {
-        if c.is_alphanumeric() || c.eq(&'_') {
-            self.set_state(State::IdentState)
-        } else {
-            self.store_token_and_trans_state();
-        }
+    // Check whether the identifier that is being scanned is the text, the blanks before it are skipped.
+    fn cur_ident_is(&self, text: &str) -> bool {
+        let chars = &self.code_chars[self.start_index.get()..self.cur_index.get()];
+        let ident = chars.iter().skip_while(|c| c.is_whitespace());
+        ident.copied().eq(text.chars())
     }
 
     fn trans_to_token_type(&self) -> TokenType {
@@ -997,30 +1081,59 @@ impl Lexer {
             State::MinusAssignState => TokenType::MinusAssign,
             State::StarAssignState => TokenType::AsteriskAssign,
             State::SlashAssignState => TokenType::SlashAssign,
+            State::ArrowState => TokenType::Arrow,
 
             // =============== data ===============
             State::IdentState => TokenType::Ident,
             State::IntegerNumState => TokenType::IntegerNum,
             State::FloatNumState => TokenType::FloatNum,
             State::StringState => TokenType::String,
-
-            // =============== keywords ===============
-            State::WhileState => TokenType::While,
-            State::ForState => TokenType::For,
-            State::IfState => TokenType::If,
-            State::ElseState => TokenType::Else,
-            State::BreakState => TokenType::Break,
-            State::ContinueState => TokenType::Continue,
-            State::LetState => TokenType::Let,
-            State::FuncState => TokenType::Func,
-            State::ReturnState => TokenType::Return,
-            State::StructState => TokenType::Struct,
-            State::EnumState => TokenType::Enum,
-            State::NoneState => TokenType::None,
-            State::TrueState => TokenType::True,
-            State::FalseState => TokenType::False,
+            State::IllegalState => TokenType::Illegal,
 
             _ => TokenType::Ident,
         }
     }
 }
+
+// Get the length of the trivia after a token on the same line, it includes the line break.
+// The gap between two tokens only contains whitespace and comments.
+fn trailing_trivia_len(gap: &str) -> usize {
+    let mut index = 0;
+    while index < gap.len() {
+        let rest = &gap[index..];
+        if rest.starts_with('\n') {
+            return index + 1;
+        } else if rest.starts_with("//") {
+            index += rest.find(['\r', '\n']).unwrap_or(rest.len());
+        } else if rest.starts_with("/*") {
+            index += block_comment_len(rest);
+        } else {
+            index += rest.chars().next().unwrap().len_utf8();
+        }
+    }
+    gap.len()
+}
+
+// Get the length of the nested block comment at the start of the text, it's scanned the same way
+// as the lexer does, and an unterminated block comment takes the rest of the text.
+pub(crate) fn block_comment_len(text: &str) -> usize {
+    let mut depth = 0;
+    let mut prev = None;
+    for (index, c) in text.char_indices() {
+        match (prev, c) {
+            (Some('/'), '*') => {
+                depth += 1;
+                prev = None;
+            }
+            (Some('*'), '/') => {
+                depth -= 1;
+                prev = None;
+                if depth == 0 {
+                    return index + 1;
+                }
+            }
+            _ => prev = Some(c),
+        }
+    }
+    text.len()
+}
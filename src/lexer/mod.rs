@@ -1,2 +1,3 @@
 mod state;
+pub mod error;
 pub mod lexer;
@@ -1,7 +1,16 @@
 pub mod ast_v2;
+pub mod builtins;
+pub mod checker;
+pub mod compiler;
 pub mod evaluator_v2;
+pub mod formatter;
 pub mod fs;
 pub mod lexer;
+pub mod linter;
+pub mod lsp;
 pub mod object;
+pub mod optimizer;
 pub mod parser_v2;
+pub mod resolver;
 pub mod token;
+pub mod vm;
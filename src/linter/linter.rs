@@ -0,0 +1,672 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+use crate::ast_v2::expressions::{ElseExp, IdentifierExp, IfExp};
+use crate::ast_v2::statements::{BlockStatement, FuncStatement, TryStatement};
+use crate::ast_v2::{Expression, Node, Statement};
+use crate::fs::woc_file::WocFile;
+use crate::lexer::lexer::{block_comment_len, Lexer};
+use crate::parser_v2::parser::Parser;
+use crate::token::types::TokenType;
+
+/// All the lint rules and what they find, all of them are enabled by default.
+pub const RULES: &[(&str, &str)] = &[
+    ("unused_variable", "a `let` binding that is never used"),
+    (
+        "unused_parameter",
+        "a function parameter that is never used",
+    ),
+    (
+        "shadowing",
+        "a name in a function that hides the same name of an enclosing scope",
+    ),
+    (
+        "unreachable_code",
+        "the statements after a `return` or `throw` in a block",
+    ),
+    ("constant_condition", "an `if` condition that never changes"),
+    (
+        "self_comparison",
+        "a value compared with itself, e.g. `x == x`",
+    ),
+    (
+        "missing_return",
+        "a function that returns a value on some paths but not on all of them",
+    ),
+    (
+        "unknown_directive",
+        "a `// lint:` comment with an unknown command or rule",
+    ),
+];
+
+// The prefix of the comments that turn the rules on or off.
+const DIRECTIVE: &str = "lint:";
+
+/// The rules that are turned off, it's read from the `[lint]` table of `woc.toml` and the command line.
+#[derive(Debug, Clone, Default)]
+pub struct LintConfig {
+    disabled: Vec<&'static str>,
+}
+
+impl LintConfig {
+    /// Turn the rule on or off, an unknown rule is an error.
+    pub fn set(&mut self, rule: &str, enabled: bool) -> Result<(), String> {
+        let rule = find_rule(rule).ok_or(format!("unknown lint rule `{}`", rule))?;
+        self.disabled.retain(|r| *r != rule);
+        if !enabled {
+            self.disabled.push(rule);
+        }
+        Ok(())
+    }
+
+    pub fn is_enabled(&self, rule: &str) -> bool {
+        !self.disabled.contains(&rule)
+    }
+}
+
+/// A problem found by a lint rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    rule: &'static str,
+    message: String,
+    line: usize,
+    column: usize,
+}
+
+impl Lint {
+    pub fn rule(&self) -> &'static str {
+        self.rule
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Get the (line, column) of the problem, both of them start from 1.
+    pub fn position(&self) -> (usize, usize) {
+        (self.line, self.column)
+    }
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {} [{}]",
+            self.line, self.column, self.message, self.rule
+        )
+    }
+}
+
+/// Check the source code with the enabled rules, the problems are sorted by their positions.
+/// The code with syntax errors is not checked, and the errors are returned instead.
+///
+/// The comments in the code can change the rules:
+/// - `// lint: allow rule_a, rule_b` ignores the rules on the next line, or on the same line if it's after the code.
+/// - `// lint: disable rule_a` and `// lint: enable rule_a` turn the rules off or on for the whole file.
+///
+/// An unknown rule or command in the comments is reported as an `unknown_directive` problem on the comment,
+/// and the rest of the file is still checked.
+pub fn lint_source(source: &str, config: &LintConfig) -> Result<Vec<Lint>, String> {
+    let parser = Parser::from_source(source);
+    let errors = parser.errors();
+    if !errors.is_empty() {
+        return Err(errors.join("\n"));
+    }
+
+    let mut linter = Linter::new();
+    linter.walk_nodes(parser.programs().iter());
+    linter.end_scope();
+
+    let mut config = config.clone();
+    let mut allowed: Vec<(usize, &'static str)> = Vec::new();
+    for (line, position, directive) in directives(source) {
+        let (command, rules) = directive
+            .split_once(' ')
+            .unwrap_or((directive.as_str(), ""));
+        if !matches!(command, "allow" | "disable" | "enable") {
+            linter.report(
+                "unknown_directive",
+                position,
+                format!("unknown lint command `{}`", command),
+            );
+            continue;
+        }
+
+        let mut known = Vec::new();
+        for rule in rules.split(',').map(|r| r.trim()).filter(|r| !r.is_empty()) {
+            match find_rule(rule) {
+                Some(rule) => known.push(rule),
+                None => linter.report(
+                    "unknown_directive",
+                    position,
+                    format!("unknown lint rule `{}`", rule),
+                ),
+            }
+        }
+        if command == "allow" {
+            allowed.extend(known.into_iter().map(|r| (line, r)));
+        } else {
+            for rule in known {
+                config.set(rule, command == "enable")?;
+            }
+        }
+    }
+
+    let mut lints: Vec<Lint> = linter
+        .lints
+        .into_iter()
+        .filter(|lint| config.is_enabled(lint.rule))
+        .filter(|lint| !allowed.contains(&(lint.line, lint.rule)))
+        .collect();
+    lints.sort_by_key(|lint| (lint.line, lint.column));
+    Ok(lints)
+}
+
+fn find_rule(name: &str) -> Option<&'static str> {
+    RULES
+        .iter()
+        .map(|(rule, _)| *rule)
+        .find(|rule| *rule == name)
+}
+
+// Find the `// lint: ...` comments, the result is the line that every comment applies to, the position of the comment
+// and the text after `lint:`. A comment before a token applies to the line of the token, and a comment after
+// a token applies to its own line.
+fn directives(source: &str) -> Vec<(usize, (usize, usize), String)> {
+    let lexer = Lexer::from_source(source).with_trivia();
+    let file = WocFile::from_source("", source);
+    let tokens = lexer.tokens_iter();
+
+    let mut result = Vec::new();
+    while let Some(token) = tokens.next() {
+        let Some(trivia) = token.trivia() else {
+            break;
+        };
+        // The BOM is in the leading trivia of the first token, but it's not a part of the source code.
+        let leading = trivia.leading().trim_start_matches('\u{FEFF}');
+        let (token_line, _) = file.position(token.offset());
+        let (trailing_line, _) = file.position(token.end());
+
+        // The offsets where the gaps start, the leading one ends at the token.
+        let leading_start = token.offset() - leading.len();
+        for (gap, start, line) in [
+            (leading, leading_start, token_line),
+            (trivia.trailing(), token.end(), trailing_line),
+        ] {
+            let mut rest = gap;
+            while !rest.is_empty() {
+                if let Some(comment) = rest.strip_prefix("//") {
+                    let len = comment.find(['\r', '\n']).unwrap_or(comment.len());
+                    if let Some(directive) = comment[..len].trim().strip_prefix(DIRECTIVE) {
+                        let position = file.position(start + gap.len() - rest.len());
+                        result.push((line, position, directive.trim().to_string()));
+                    }
+                    rest = &comment[len..];
+                } else if rest.starts_with("/*") {
+                    rest = &rest[block_comment_len(rest)..];
+                } else {
+                    rest = &rest[rest.chars().next().unwrap().len_utf8()..];
+                }
+            }
+        }
+
+        if token.is_eof() {
+            break;
+        }
+    }
+    result
+}
+
+// The kinds of the names that can be unused or shadowed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BindingKind {
+    Variable,
+    Parameter,
+    Function,
+}
+
+struct Binding {
+    name: String,
+    kind: BindingKind,
+    position: (usize, usize),
+    used: bool,
+}
+
+// The names of a function or the top-level code, a block does not create a new scope.
+#[derive(Default)]
+struct Scope {
+    bindings: Vec<Binding>,
+    // The index of the latest binding of every name.
+    names: HashMap<String, usize>,
+}
+
+// It walks the AST with the same scope rules as the resolver and collects the problems of all the rules,
+// the disabled rules are filtered out at the end.
+struct Linter {
+    scopes: Vec<Scope>,
+    lints: Vec<Lint>,
+}
+
+impl Linter {
+    fn new() -> Self {
+        Self {
+            scopes: vec![Scope::default()],
+            lints: Vec::new(),
+        }
+    }
+
+    fn report(&mut self, rule: &'static str, (line, column): (usize, usize), message: String) {
+        self.lints.push(Lint {
+            rule,
+            message,
+            line,
+            column,
+        });
+    }
+
+    // =================== Walk the AST ===================
+
+    // Walk the statements of a block or the top-level code, the ones after a `return` or `throw` are
+    // never executed, and only the first of them is reported.
+    fn walk_nodes<'a>(&mut self, nodes: impl Iterator<Item = &'a Node>) {
+        let mut returned = None;
+        let mut reported = false;
+        for node in nodes {
+            if let (Some((position, keyword)), false) = (returned, reported) {
+                reported = true;
+                self.report(
+                    "unreachable_code",
+                    node_position(node).unwrap_or(position),
+                    format!("the code after `{}` is never executed", keyword),
+                );
+            }
+
+            self.walk_node(node);
+            let exit = match node {
+                Node::Stmt(Statement::Return(ret_stmt)) => ret_stmt.position().zip(Some("return")),
+                Node::Stmt(Statement::Throw(throw_stmt)) => {
+                    throw_stmt.position().zip(Some("throw"))
+                }
+                _ => None,
+            };
+            returned = returned.or(exit);
+        }
+    }
+
+    fn walk_block(&mut self, block: &BlockStatement) {
+        if let Some(stmts) = block.statements() {
+            self.walk_nodes(stmts.iter().map(|stmt| stmt.as_ref()));
+        }
+    }
+
+    fn walk_node(&mut self, node: &Node) {
+        match node {
+            Node::Exp(exp) => self.walk_exp(exp),
+            Node::Stmt(stmt) => self.walk_stmt(stmt),
+        }
+    }
+
+    fn walk_stmt(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Let(let_stmt) => {
+                // The value is walked first, so `let x = x;` uses the `x` defined before.
+                if let Some(value) = let_stmt.value() {
+                    self.walk_exp(value);
+                }
+                self.define(let_stmt.ident(), BindingKind::Variable);
+            }
+            Statement::Return(ret_stmt) => {
+                if let Some(value) = ret_stmt.value() {
+                    self.walk_exp(value);
+                }
+            }
+            Statement::Block(block) => self.walk_block(block),
+            Statement::Func(func_stmt) => self.walk_func(func_stmt),
+            Statement::Throw(throw_stmt) => self.walk_exp(throw_stmt.value()),
+            Statement::Try(try_stmt) => {
+                self.walk_block(try_stmt.body());
+                if let Some((ident, block)) = try_stmt.catch() {
+                    // The caught error is only visible in the catch block.
+                    let name = ident.value();
+                    let outer = self.scopes.last().unwrap().names.get(name).copied();
+                    self.define(ident, BindingKind::Variable);
+                    self.walk_block(block);
+
+                    let names = &mut self.scopes.last_mut().unwrap().names;
+                    match outer {
+                        Some(index) => names.insert(name.to_string(), index),
+                        None => names.remove(name),
+                    };
+                }
+                if let Some(block) = try_stmt.finally() {
+                    self.walk_block(block);
+                }
+            }
+            Statement::Error(_) => {}
+        }
+    }
+
+    fn walk_func(&mut self, func_stmt: &FuncStatement) {
+        if let Some(position) = func_stmt.ident().position() {
+            let body = func_stmt.body();
+            if has_return_value(body) && !body.always_returns() {
+                self.report(
+                    "missing_return",
+                    position,
+                    format!(
+                        "not all paths of function `{}` return a value",
+                        func_stmt.name()
+                    ),
+                );
+            }
+        }
+
+        // The parameters and the function itself are defined in the scope of its body.
+        self.scopes.push(Scope::default());
+        for param in func_stmt.params().into_iter().flatten() {
+            self.define(param, BindingKind::Parameter);
+        }
+        self.define(func_stmt.ident(), BindingKind::Function);
+        self.walk_block(func_stmt.body());
+        self.end_scope();
+
+        self.define(func_stmt.ident(), BindingKind::Function);
+    }
+
+    fn walk_exp(&mut self, exp: &Expression) {
+        match exp {
+            Expression::Identifier(ident) => self.use_name(ident),
+            Expression::Num(_)
+            | Expression::Boolean(_)
+            | Expression::Str(_)
+            | Expression::Char(_)
+            | Expression::Byte(_) => {}
+            Expression::Interpolated(interp_exp) => {
+                for part in interp_exp.parts() {
+                    self.walk_exp(part);
+                }
+            }
+            Expression::Arr(arr_exp) => {
+                for element in arr_exp.elements() {
+                    self.walk_exp(element);
+                }
+            }
+            Expression::ArrIndex(arr_index) => {
+                self.use_name(arr_index.name());
+                self.walk_exp(arr_index.index());
+            }
+            Expression::If(if_exp) => self.walk_if(if_exp),
+            Expression::Call(call_exp) => {
+                self.use_name(call_exp.name());
+                for arg in call_exp.arguments() {
+                    self.walk_exp(arg);
+                }
+            }
+            Expression::Prefix(pre_exp) => self.walk_exp(pre_exp.right()),
+            Expression::Infix(infix_exp) => {
+                self.check_self_comparison(
+                    infix_exp.left(),
+                    infix_exp.operator(),
+                    infix_exp.right(),
+                );
+                self.walk_exp(infix_exp.left());
+                self.walk_exp(infix_exp.right());
+            }
+        }
+    }
+
+    fn walk_if(&mut self, if_exp: &IfExp) {
+        if let (true, Some(position)) = (is_constant(if_exp.condition()), if_exp.position()) {
+            self.report(
+                "constant_condition",
+                position,
+                format!("the condition `{}` is a constant", if_exp.condition()),
+            );
+        }
+
+        self.walk_exp(if_exp.condition());
+        self.walk_block(if_exp.consequence());
+        if let Some(else_exp) = if_exp.else_exp() {
+            self.walk_else(else_exp);
+        }
+    }
+
+    fn walk_else(&mut self, else_exp: &ElseExp) {
+        if let Some(if_exp) = else_exp.if_exp() {
+            self.walk_if(if_exp);
+        }
+        if let Some(block) = else_exp.consequence() {
+            self.walk_block(block);
+        }
+    }
+
+    // =================== Rules ===================
+
+    // The comparisons of a value with itself, a call is not checked because it may return different values.
+    fn check_self_comparison(
+        &mut self,
+        left: &Expression,
+        operator: &TokenType,
+        right: &Expression,
+    ) {
+        let result = match operator {
+            TokenType::EqualTo | TokenType::LessThanOrEqualTo | TokenType::GreaterThanOrEqualTo => {
+                "true"
+            }
+            TokenType::NotEqualTo | TokenType::Less | TokenType::Greater => "false",
+            _ => return,
+        };
+
+        if left.to_string() != right.to_string() || has_call(left) {
+            return;
+        }
+        if let Some(position) = exp_position(left) {
+            self.report(
+                "self_comparison",
+                position,
+                format!(
+                    "`{}` is compared with itself, the result is always {}",
+                    left, result
+                ),
+            );
+        }
+    }
+
+    // Define the name in the current scope, it shadows the same name of an enclosing function or the top-level code.
+    fn define(&mut self, ident: &IdentifierExp, kind: BindingKind) {
+        let Some(position) = ident.position() else {
+            return;
+        };
+        let name = ident.value();
+
+        let scope = self.scopes.last().unwrap();
+        let redefined = scope.names.get(name).map(|i| &scope.bindings[*i]);
+        // A function is defined again in the enclosing scope after its body, it's the same function.
+        let same_function =
+            redefined.is_some_and(|b| b.kind == BindingKind::Function && b.position == position);
+        if same_function {
+            return;
+        }
+
+        // The function itself is defined in the scope of its body, it doesn't shadow its own name.
+        let enclosing = self.scopes[..self.scopes.len() - 1]
+            .iter()
+            .rev()
+            .find_map(|s| s.names.get(name).map(|i| &s.bindings[*i]));
+        if let Some(outer) = enclosing {
+            if outer.position != position {
+                let (line, column) = outer.position;
+                self.report(
+                    "shadowing",
+                    position,
+                    format!(
+                        "`{}` shadows the name defined at line {}, column {}",
+                        name, line, column
+                    ),
+                );
+            }
+        }
+
+        let scope = self.scopes.last_mut().unwrap();
+        scope.names.insert(name.to_string(), scope.bindings.len());
+        scope.bindings.push(Binding {
+            name: name.to_string(),
+            kind,
+            position,
+            used: false,
+        });
+    }
+
+    fn use_name(&mut self, ident: &IdentifierExp) {
+        let name = ident.value();
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(index) = scope.names.get(name) {
+                scope.bindings[*index].used = true;
+                return;
+            }
+        }
+    }
+
+    // Report the variables and parameters of the scope that are never used,
+    // the names that start with `_` are unused on purpose.
+    fn end_scope(&mut self) {
+        let scope = self.scopes.pop().unwrap();
+        for binding in scope.bindings {
+            if binding.used || binding.name.starts_with('_') {
+                continue;
+            }
+            match binding.kind {
+                BindingKind::Variable => self.report(
+                    "unused_variable",
+                    binding.position,
+                    format!("variable `{}` is never used", binding.name),
+                ),
+                BindingKind::Parameter => self.report(
+                    "unused_parameter",
+                    binding.position,
+                    format!("parameter `{}` is never used", binding.name),
+                ),
+                BindingKind::Function => {}
+            }
+        }
+    }
+}
+
+// =================== Helper Functions ===================
+
+// Check whether the value of the expression never changes, it only has literals and operators.
+fn is_constant(exp: &Expression) -> bool {
+    match exp {
+        Expression::Num(_)
+        | Expression::Boolean(_)
+        | Expression::Str(_)
+        | Expression::Char(_)
+        | Expression::Byte(_) => true,
+        Expression::Interpolated(interp_exp) => interp_exp.parts().iter().all(is_constant),
+        Expression::Arr(arr_exp) => arr_exp.elements().iter().all(is_constant),
+        Expression::Prefix(pre_exp) => is_constant(pre_exp.right()),
+        Expression::Infix(infix_exp) => {
+            is_constant(infix_exp.left()) && is_constant(infix_exp.right())
+        }
+        Expression::Identifier(_)
+        | Expression::ArrIndex(_)
+        | Expression::If(_)
+        | Expression::Call(_) => false,
+    }
+}
+
+fn has_call(exp: &Expression) -> bool {
+    match exp {
+        Expression::Call(_) | Expression::If(_) => true,
+        Expression::Interpolated(interp_exp) => interp_exp.parts().iter().any(has_call),
+        Expression::Arr(arr_exp) => arr_exp.elements().iter().any(has_call),
+        Expression::ArrIndex(arr_index) => has_call(arr_index.index()),
+        Expression::Prefix(pre_exp) => has_call(pre_exp.right()),
+        Expression::Infix(infix_exp) => has_call(infix_exp.left()) || has_call(infix_exp.right()),
+        _ => false,
+    }
+}
+
+// Check whether the block returns a value somewhere, the nested functions are not included.
+fn has_return_value(block: &BlockStatement) -> bool {
+    block
+        .statements()
+        .into_iter()
+        .flatten()
+        .any(|node| match node.as_ref() {
+            Node::Stmt(Statement::Return(ret_stmt)) => ret_stmt.value().is_some(),
+            Node::Stmt(Statement::Block(block)) => has_return_value(block),
+            Node::Stmt(Statement::Try(try_stmt)) => try_blocks(try_stmt).any(has_return_value),
+            Node::Exp(Expression::If(if_exp)) => {
+                let else_block = if_exp.else_exp().map(else_blocks).unwrap_or_default();
+                has_return_value(if_exp.consequence())
+                    || else_block.iter().any(|b| has_return_value(b))
+            }
+            _ => false,
+        })
+}
+
+// Get the body, catch and finally blocks of the try statement.
+fn try_blocks(try_stmt: &TryStatement) -> impl Iterator<Item = &BlockStatement> {
+    let catch = try_stmt.catch().map(|(_, block)| block);
+    std::iter::once(try_stmt.body())
+        .chain(catch)
+        .chain(try_stmt.finally())
+}
+
+// Get all the blocks of the else branches, including the ones of `else if`.
+fn else_blocks(else_exp: &ElseExp) -> Vec<&BlockStatement> {
+    let mut blocks = Vec::new();
+    if let Some(if_exp) = else_exp.if_exp() {
+        blocks.push(if_exp.consequence());
+        if let Some(else_exp) = if_exp.else_exp() {
+            blocks.extend(else_blocks(else_exp));
+        }
+    }
+    if let Some(block) = else_exp.consequence() {
+        blocks.push(block);
+    }
+    blocks
+}
+
+// Get the position of the first name or keyword of the node, the literals have no position.
+fn node_position(node: &Node) -> Option<(usize, usize)> {
+    match node {
+        Node::Stmt(Statement::Let(let_stmt)) => let_stmt.ident().position(),
+        Node::Stmt(Statement::Return(ret_stmt)) => ret_stmt.position(),
+        Node::Stmt(Statement::Func(func_stmt)) => func_stmt.ident().position(),
+        Node::Stmt(Statement::Throw(throw_stmt)) => throw_stmt.position(),
+        Node::Stmt(Statement::Block(block)) => block_position(block),
+        Node::Stmt(Statement::Try(try_stmt)) => try_blocks(try_stmt).find_map(block_position),
+        Node::Stmt(Statement::Error(error_stmt)) => Some(error_stmt.position()),
+        Node::Exp(exp) => exp_position(exp),
+    }
+}
+
+// Get the position of the first statement of the block that has one.
+fn block_position(block: &BlockStatement) -> Option<(usize, usize)> {
+    block
+        .statements()
+        .and_then(|stmts| stmts.iter().find_map(|stmt| node_position(stmt)))
+}
+
+fn exp_position(exp: &Expression) -> Option<(usize, usize)> {
+    match exp {
+        Expression::Identifier(ident) => ident.position(),
+        Expression::If(if_exp) => if_exp.position(),
+        Expression::Call(call_exp) => call_exp.name().position(),
+        Expression::ArrIndex(arr_index) => arr_index.name().position(),
+        Expression::Prefix(pre_exp) => exp_position(pre_exp.right()),
+        Expression::Infix(infix_exp) => {
+            exp_position(infix_exp.left()).or_else(|| exp_position(infix_exp.right()))
+        }
+        Expression::Arr(arr_exp) => arr_exp.elements().iter().find_map(exp_position),
+        Expression::Interpolated(interp_exp) => interp_exp.parts().iter().find_map(exp_position),
+        Expression::Num(_)
+        | Expression::Boolean(_)
+        | Expression::Str(_)
+        | Expression::Char(_)
+        | Expression::Byte(_) => None,
+    }
+}
@@ -0,0 +1,3 @@
+// The linter finds the code that is valid but likely a mistake, it walks the AST with the scope rules of the resolver.
+// - linter: The rules, the config that turns them on or off and the `// lint:` comments in the code.
+pub mod linter;
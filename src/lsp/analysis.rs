@@ -0,0 +1,550 @@
+use std::collections::HashMap;
+
+use crate::ast_v2::expressions::{ElseExp, IdentifierExp, IfExp};
+use crate::ast_v2::statements::{BlockStatement, FuncStatement};
+use crate::ast_v2::{Expression, Node, Statement};
+use crate::builtins::builtins::BUILTINS;
+use crate::checker::checker::TypeChecker;
+use crate::fs::woc_file::WocFile;
+use crate::parser_v2::parser::Parser;
+use crate::token::types::TokenType;
+
+/// The kind of a name defined in the code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Variable,
+    Function,
+    Parameter,
+    Builtin,
+}
+
+/// A name defined by `let`, `func`, a parameter or a builtin function.
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    name: String,
+    kind: SymbolKind,
+    // The (line, column) of the name, the builtin functions have no position.
+    position: Option<(usize, usize)>,
+    // The inferred type of a variable, or the parameters of a function.
+    detail: String,
+    doc: Option<String>,
+}
+
+impl Symbol {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> SymbolKind {
+        self.kind
+    }
+
+    /// Get the (line, column) where the name is defined, both of them start from 1.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.position
+    }
+
+    /// Get the inferred type of a variable, e.g. `integer`, or the parameters of a function, e.g. `x, y`.
+    pub fn detail(&self) -> &str {
+        &self.detail
+    }
+
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+
+    /// Get the declaration of the symbol as it's shown to the user, e.g. `let a: integer` or `func add(x, y)`.
+    pub fn signature(&self) -> String {
+        match self.kind {
+            SymbolKind::Variable => format!("let {}: {}", self.name, self.detail),
+            SymbolKind::Function => format!("func {}({})", self.name, self.detail),
+            SymbolKind::Parameter => format!("parameter {}", self.name),
+            SymbolKind::Builtin => format!("builtin {}", self.name),
+        }
+    }
+}
+
+/// A problem found in the code, the lexer errors, the statements that fail to parse, the undefined names and the type errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    message: String,
+    line: usize,
+    column: usize,
+    // The number of chars marked on the line.
+    len: usize,
+}
+
+impl Diagnostic {
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Get the (line, column) where the problem starts, both of them start from 1.
+    pub fn position(&self) -> (usize, usize) {
+        (self.line, self.column)
+    }
+
+    /// Get the (line, column) right after the marked text, the text doesn't span lines.
+    pub fn end(&self) -> (usize, usize) {
+        (self.line, self.column + self.len)
+    }
+}
+
+/// A function and the functions defined in its body.
+#[derive(Debug, Clone)]
+pub struct Outline {
+    symbol: Symbol,
+    children: Vec<Outline>,
+}
+
+impl Outline {
+    pub fn symbol(&self) -> &Symbol {
+        &self.symbol
+    }
+
+    pub fn children(&self) -> &[Outline] {
+        &self.children
+    }
+}
+
+// An identifier in the code and the index of the symbol it refers to.
+struct Reference {
+    line: usize,
+    column: usize,
+    len: usize,
+    symbol: usize,
+}
+
+/// The analysis of a source file for the editors, it's built on the diagnostics and the AST of the parser.
+/// The names are resolved with the same scope rules as the [`crate::resolver::resolver::Resolver`]:
+/// the top-level code and every function have their own scope, and a name can only be used after its definition.
+pub struct Analysis {
+    file: WocFile,
+    symbols: Vec<Symbol>,
+    references: Vec<Reference>,
+    outline: Vec<Outline>,
+    diagnostics: Vec<Diagnostic>,
+    // The names defined in every scope so far, the first scope is the builtin functions.
+    scopes: Vec<HashMap<String, usize>>,
+    // The names that are not defined when they're used, with their positions.
+    unresolved: Vec<(String, usize, usize)>,
+    // The functions whose bodies are being analyzed.
+    funcs: Vec<Outline>,
+    // The errors from the lexer, the statements that fail because of them are not reported again.
+    lex_errors: Vec<String>,
+}
+
+impl Analysis {
+    /// Analyze the source code, the statements that fail to parse are reported and skipped.
+    pub fn new(source: &str) -> Self {
+        let parser = Parser::from_source(source);
+        let mut analysis = Self {
+            file: WocFile::from_source("", source),
+            symbols: Vec::new(),
+            references: Vec::new(),
+            outline: Vec::new(),
+            diagnostics: Vec::new(),
+            scopes: vec![HashMap::new(), HashMap::new()],
+            unresolved: Vec::new(),
+            funcs: Vec::new(),
+            lex_errors: Vec::new(),
+        };
+
+        for error in parser.lex_errors() {
+            analysis.report(error.message(), error.line(), error.column(), 1);
+            analysis.lex_errors.push(error.to_string());
+        }
+
+        for builtin in BUILTINS {
+            analysis.scopes[0].insert(builtin.name().to_string(), analysis.symbols.len());
+            analysis.symbols.push(Symbol {
+                name: builtin.name().to_string(),
+                kind: SymbolKind::Builtin,
+                position: None,
+                detail: String::new(),
+                doc: None,
+            });
+        }
+
+        let programs = parser.programs();
+        for node in programs.iter() {
+            analysis.walk_node(node);
+        }
+        if let Err(errors) = TypeChecker::new().check(&programs) {
+            for error in errors {
+                analysis.report(error.message(), error.line(), error.column(), 1);
+            }
+        }
+
+        // A name defined later is used before its definition, the others are not defined at all.
+        for (name, line, column) in std::mem::take(&mut analysis.unresolved) {
+            let message = match analysis.symbols.iter().any(|s| s.name == name) {
+                true => format!("variable `{}` is used before its definition", name),
+                false => format!("undefined variable `{}`", name),
+            };
+            analysis.report(&message, line, column, name.chars().count());
+        }
+        analysis.diagnostics.sort_by_key(|d| (d.line, d.column));
+
+        analysis
+    }
+
+    /// Get the analyzed source file, it's used to convert the positions.
+    pub fn file(&self) -> &WocFile {
+        &self.file
+    }
+
+    /// Get the problems found in the code in the order of their positions.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Get all the symbols, the builtin functions come first and the others are in the order of the code.
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    /// Get the functions of the code, a nested function is a child of the function it's defined in.
+    pub fn outline(&self) -> &[Outline] {
+        &self.outline
+    }
+
+    /// Find the symbol of the name at the (line, column), the name can be a definition or a use.
+    /// The position right after the name is also accepted, it's where the cursor is after typing the name.
+    pub fn symbol_at(&self, line: usize, column: usize) -> Option<&Symbol> {
+        let on_line = || self.references.iter().filter(|r| r.line == line);
+        on_line()
+            .find(|r| r.column <= column && column < r.column + r.len)
+            .or_else(|| on_line().find(|r| r.column + r.len == column))
+            .map(|r| &self.symbols[r.symbol])
+    }
+
+    // =================== Walk the AST ===================
+
+    fn walk_node(&mut self, node: &Node) {
+        match node {
+            Node::Exp(exp) => self.walk_exp(exp),
+            Node::Stmt(stmt) => self.walk_stmt(stmt),
+        }
+    }
+
+    fn walk_stmt(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Let(let_stmt) => {
+                // The value is walked first, so `let x = x;` uses the `x` defined before.
+                let detail = match let_stmt.value() {
+                    Some(value) => {
+                        self.walk_exp(value);
+                        self.infer(value)
+                    }
+                    None => "unknown",
+                };
+                let index = self.add_symbol(
+                    let_stmt.ident(),
+                    SymbolKind::Variable,
+                    detail.to_string(),
+                    let_stmt.doc(),
+                );
+                self.define(let_stmt.name(), index);
+            }
+            Statement::Return(ret_stmt) => {
+                if let Some(value) = ret_stmt.value() {
+                    self.walk_exp(value);
+                }
+            }
+            Statement::Block(block) => self.walk_block(block),
+            Statement::Func(func_stmt) => self.walk_func(func_stmt),
+            Statement::Throw(throw_stmt) => self.walk_exp(throw_stmt.value()),
+            Statement::Try(try_stmt) => {
+                self.walk_block(try_stmt.body());
+                if let Some((ident, block)) = try_stmt.catch() {
+                    // The caught error is only visible in the catch block.
+                    let name = ident.value();
+                    let outer = self.scopes.last().unwrap().get(name).copied();
+                    let index =
+                        self.add_symbol(ident, SymbolKind::Variable, "error".to_string(), None);
+                    self.define(name, index);
+                    self.walk_block(block);
+
+                    let scope = self.scopes.last_mut().unwrap();
+                    match outer {
+                        Some(index) => scope.insert(name.to_string(), index),
+                        None => scope.remove(name),
+                    };
+                }
+                if let Some(block) = try_stmt.finally() {
+                    self.walk_block(block);
+                }
+            }
+            Statement::Error(error_stmt) => {
+                // The errors from the lexer are reported already with their own positions.
+                let message = error_stmt.message();
+                if !message.is_empty() && !self.lex_errors.iter().any(|e| e == message) {
+                    let (line, column) = error_stmt.position();
+                    let first_line = error_stmt.text().lines().next().unwrap_or_default();
+                    let len = first_line.chars().count().max(1);
+                    self.report(error_stmt.message(), line, column, len);
+                }
+            }
+        }
+    }
+
+    fn walk_block(&mut self, block: &BlockStatement) {
+        if let Some(stmts) = block.statements() {
+            for stmt in stmts {
+                self.walk_node(stmt);
+            }
+        }
+    }
+
+    fn walk_func(&mut self, func_stmt: &FuncStatement) {
+        let params: Vec<&str> = func_stmt
+            .params()
+            .map(|params| params.iter().map(|p| p.value()).collect())
+            .unwrap_or_default();
+        let index = self.add_symbol(
+            func_stmt.ident(),
+            SymbolKind::Function,
+            params.join(", "),
+            func_stmt.doc(),
+        );
+
+        // The parameters and the function itself are defined in the scope of its body.
+        self.scopes.push(HashMap::new());
+        for param in func_stmt.params().into_iter().flatten() {
+            let param_index = self.add_symbol(param, SymbolKind::Parameter, String::new(), None);
+            self.define(param.value(), param_index);
+        }
+        self.define(func_stmt.name(), index);
+
+        self.funcs.push(Outline {
+            symbol: self.symbols[index].clone(),
+            children: Vec::new(),
+        });
+        self.walk_block(func_stmt.body());
+        let outline = self.funcs.pop().unwrap();
+        match self.funcs.last_mut() {
+            Some(parent) => parent.children.push(outline),
+            None => self.outline.push(outline),
+        }
+        self.scopes.pop();
+
+        // The function can be used by the code after it.
+        self.define(func_stmt.name(), index);
+    }
+
+    fn walk_exp(&mut self, exp: &Expression) {
+        match exp {
+            Expression::Identifier(ident) => self.use_name(ident),
+            Expression::Num(_)
+            | Expression::Boolean(_)
+            | Expression::Str(_)
+            | Expression::Char(_)
+            | Expression::Byte(_) => {}
+            Expression::Interpolated(interp_exp) => {
+                for part in interp_exp.parts() {
+                    self.walk_exp(part);
+                }
+            }
+            Expression::Arr(arr_exp) => {
+                for element in arr_exp.elements() {
+                    self.walk_exp(element);
+                }
+            }
+            Expression::ArrIndex(arr_index) => {
+                self.use_name(arr_index.name());
+                self.walk_exp(arr_index.index());
+            }
+            Expression::If(if_exp) => self.walk_if(if_exp),
+            Expression::Call(call_exp) => {
+                self.use_name(call_exp.name());
+                for arg in call_exp.arguments() {
+                    self.walk_exp(arg);
+                }
+            }
+            Expression::Prefix(pre_exp) => self.walk_exp(pre_exp.right()),
+            Expression::Infix(infix_exp) => {
+                self.walk_exp(infix_exp.left());
+                self.walk_exp(infix_exp.right());
+            }
+        }
+    }
+
+    fn walk_if(&mut self, if_exp: &IfExp) {
+        self.walk_exp(if_exp.condition());
+        self.walk_block(if_exp.consequence());
+        if let Some(else_exp) = if_exp.else_exp() {
+            self.walk_else(else_exp);
+        }
+    }
+
+    fn walk_else(&mut self, else_exp: &ElseExp) {
+        if let Some(if_exp) = else_exp.if_exp() {
+            self.walk_if(if_exp);
+        }
+        if let Some(block) = else_exp.consequence() {
+            self.walk_block(block);
+        }
+    }
+
+    // =================== Helper Functions ===================
+
+    // Add the symbol of the definition, the name of the definition refers to the symbol itself.
+    fn add_symbol(
+        &mut self,
+        ident: &IdentifierExp,
+        kind: SymbolKind,
+        detail: String,
+        doc: Option<&str>,
+    ) -> usize {
+        let index = self.symbols.len();
+        self.symbols.push(Symbol {
+            name: ident.value().to_string(),
+            kind,
+            position: ident.position(),
+            detail,
+            doc: doc.map(|d| d.to_string()),
+        });
+        self.add_reference(ident, index);
+        index
+    }
+
+    fn add_reference(&mut self, ident: &IdentifierExp, symbol: usize) {
+        if let Some((line, column)) = ident.position() {
+            self.references.push(Reference {
+                line,
+                column,
+                len: ident.value().chars().count(),
+                symbol,
+            });
+        }
+    }
+
+    fn define(&mut self, name: &str, symbol: usize) {
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.to_string(), symbol);
+    }
+
+    // Find the symbol of the name from the current scope to the builtin functions.
+    fn lookup(&self, name: &str) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+
+    fn use_name(&mut self, ident: &IdentifierExp) {
+        match self.lookup(ident.value()) {
+            Some(symbol) => self.add_reference(ident, symbol),
+            None => {
+                if let Some((line, column)) = ident.position() {
+                    self.unresolved
+                        .push((ident.value().to_string(), line, column));
+                }
+            }
+        }
+    }
+
+    fn report(&mut self, message: &str, line: usize, column: usize, len: usize) {
+        self.diagnostics.push(Diagnostic {
+            message: message.to_string(),
+            line,
+            column,
+            len,
+        });
+    }
+
+    // Infer the type of the value from the literals and operators, `unknown` if it's only known at runtime.
+    fn infer(&self, exp: &Expression) -> &'static str {
+        match exp {
+            Expression::Identifier(ident) => match self.lookup(ident.value()) {
+                Some(index) => {
+                    let symbol = &self.symbols[index];
+                    match symbol.kind {
+                        SymbolKind::Variable => type_name(&symbol.detail),
+                        SymbolKind::Function | SymbolKind::Builtin => "function",
+                        SymbolKind::Parameter => "unknown",
+                    }
+                }
+                None => "unknown",
+            },
+            Expression::Num(num) if num.integer_value().is_some() => "integer",
+            Expression::Num(_) => "float",
+            Expression::Boolean(_) => "boolean",
+            Expression::Str(_) | Expression::Interpolated(_) => "string",
+            Expression::Char(_) => "char",
+            Expression::Byte(_) => "byte",
+            Expression::Arr(_) => "array",
+            Expression::Call(call_exp) => {
+                let builtin = self
+                    .lookup(call_exp.name().value())
+                    .filter(|index| self.symbols[*index].kind == SymbolKind::Builtin);
+                match builtin.map(|index| self.symbols[index].name.as_str()) {
+                    Some("int") => "integer",
+                    Some("float") => "float",
+                    Some("bool") => "boolean",
+                    Some("char") => "char",
+                    Some("byte") => "byte",
+                    Some("str" | "type" | "error_kind" | "error_message") => "string",
+                    Some("error_trace") => "array",
+                    Some("error") => "error",
+                    Some(name) if name.starts_with("is_") => "boolean",
+                    _ => "unknown",
+                }
+            }
+            Expression::Prefix(pre_exp) => {
+                match (pre_exp.operator(), self.infer(pre_exp.right())) {
+                    (TokenType::Not, _) => "boolean",
+                    (TokenType::Minus, right @ ("integer" | "float")) => right,
+                    (TokenType::BitNot, "integer") => "integer",
+                    _ => "unknown",
+                }
+            }
+            Expression::Infix(infix_exp) => {
+                let left = self.infer(infix_exp.left());
+                let right = self.infer(infix_exp.right());
+                match infix_exp.operator() {
+                    TokenType::EqualTo
+                    | TokenType::NotEqualTo
+                    | TokenType::Greater
+                    | TokenType::Less
+                    | TokenType::GreaterThanOrEqualTo
+                    | TokenType::LessThanOrEqualTo => "boolean",
+                    // The result is one of the operands.
+                    TokenType::And | TokenType::Or if left == right => left,
+                    TokenType::Plus if left == "string" && right == "string" => "string",
+                    TokenType::Plus
+                    | TokenType::Minus
+                    | TokenType::Asterisk
+                    | TokenType::Slash
+                    | TokenType::Percent => match (left, right) {
+                        ("integer", "integer") => "integer",
+                        ("integer" | "float", "integer" | "float") => "float",
+                        _ => "unknown",
+                    },
+                    TokenType::BitAnd | TokenType::BitOr
+                        if left == "integer" && right == "integer" =>
+                    {
+                        "integer"
+                    }
+                    _ => "unknown",
+                }
+            }
+            Expression::ArrIndex(_) | Expression::If(_) => "unknown",
+        }
+    }
+}
+
+// Get the static name of an inferred type.
+fn type_name(detail: &str) -> &'static str {
+    const TYPES: &[&str] = &[
+        "integer", "float", "boolean", "string", "char", "byte", "array", "function", "error",
+    ];
+    TYPES
+        .iter()
+        .find(|t| **t == detail)
+        .copied()
+        .unwrap_or("unknown")
+}
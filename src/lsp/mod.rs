@@ -0,0 +1,7 @@
+// The language server lets the editors work with the woc code, it speaks the Language Server Protocol over stdio.
+// - json: It parses and writes the JSON messages of the protocol.
+// - analysis: It finds the diagnostics, the symbols and the references of a source file from its AST.
+// - server: It handles the requests and notifications of the editor.
+pub mod analysis;
+pub mod json;
+pub mod server;
@@ -0,0 +1,433 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+use crate::fs::woc_file::WocFile;
+use crate::token::keywords::KEYWORDS;
+
+use super::analysis::{Analysis, Outline, Symbol, SymbolKind};
+use super::json::Json;
+
+// The error codes of JSON-RPC.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+// The kinds of the protocol for the symbols and the completion items.
+const SYMBOL_FUNCTION: usize = 12;
+const COMPLETION_FUNCTION: usize = 3;
+const COMPLETION_VARIABLE: usize = 6;
+const COMPLETION_KEYWORD: usize = 14;
+
+type RequestResult = Result<Json, (i64, String)>;
+
+/// The language server of the woc language. It keeps the analysis of the open documents and answers
+/// the requests of the editor, the documents are always synchronized with their full text.
+///
+/// It supports:
+/// - `textDocument/publishDiagnostics` after a document is opened or changed.
+/// - `textDocument/definition` for the names defined by `let`, `func` and the parameters.
+/// - `textDocument/hover` with the inferred type of a variable or the signature of a function.
+/// - `textDocument/documentSymbol` for the functions.
+/// - `textDocument/completion` with the keywords, the builtin functions and the names in the document.
+#[derive(Default)]
+pub struct Server {
+    documents: HashMap<String, Analysis>,
+    shutdown: bool,
+    exited: bool,
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check whether the client has sent `exit`, the server must stop then.
+    pub fn is_exited(&self) -> bool {
+        self.exited
+    }
+
+    /// Check whether the client has sent `shutdown`.
+    pub fn is_shutdown(&self) -> bool {
+        self.shutdown
+    }
+
+    /// Handle a message from the client, the result is the messages to send back:
+    /// the response of a request, or the diagnostics of a changed document.
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let params = message.get("params").unwrap_or(&Json::Null);
+        match (
+            message.get("id"),
+            message.get("method").and_then(Json::as_str),
+        ) {
+            (Some(id), Some(method)) => vec![self.handle_request(id.clone(), method, params)],
+            (None, Some(method)) => self.handle_notification(method, params),
+            // The server never sends requests, so there are no responses to handle.
+            _ => Vec::new(),
+        }
+    }
+
+    fn handle_request(&mut self, id: Json, method: &str, params: &Json) -> Json {
+        if self.shutdown {
+            return error_response(id, INVALID_REQUEST, "the server is shut down");
+        }
+
+        let result = match method {
+            "initialize" => Ok(capabilities()),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Json::Null)
+            }
+            "textDocument/definition" => self.definition(params),
+            "textDocument/hover" => self.hover(params),
+            "textDocument/documentSymbol" => self.document_symbols(params),
+            "textDocument/completion" => self.completion(params),
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method `{}`", method))),
+        };
+
+        match result {
+            Ok(result) => Json::object(vec![
+                ("jsonrpc", "2.0".into()),
+                ("id", id),
+                ("result", result),
+            ]),
+            Err((code, msg)) => error_response(id, code, &msg),
+        }
+    }
+
+    fn handle_notification(&mut self, method: &str, params: &Json) -> Vec<Json> {
+        let document = params.get("textDocument");
+        let uri = document.and_then(|d| d.get("uri")).and_then(Json::as_str);
+
+        match (method, uri) {
+            ("exit", _) => {
+                self.exited = true;
+                Vec::new()
+            }
+            ("textDocument/didOpen", Some(uri)) => {
+                let text = document.and_then(|d| d.get("text")).and_then(Json::as_str);
+                self.update(uri, text.unwrap_or_default())
+            }
+            ("textDocument/didChange", Some(uri)) => {
+                // The full text is sent on every change, the last one is the current text.
+                let changes = params.get("contentChanges").and_then(Json::as_array);
+                let text = changes
+                    .and_then(|c| c.last())
+                    .and_then(|c| c.get("text"))
+                    .and_then(Json::as_str);
+                match text {
+                    Some(text) => self.update(uri, text),
+                    None => Vec::new(),
+                }
+            }
+            ("textDocument/didClose", Some(uri)) => {
+                // The diagnostics of a closed document are cleared.
+                self.documents.remove(uri);
+                vec![diagnostics_notification(uri, Vec::new())]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    // Analyze the new text of the document and publish its diagnostics.
+    fn update(&mut self, uri: &str, text: &str) -> Vec<Json> {
+        let analysis = Analysis::new(text);
+        let diagnostics = analysis
+            .diagnostics()
+            .iter()
+            .map(|d| {
+                Json::object(vec![
+                    ("range", lsp_range(analysis.file(), d.position(), d.end())),
+                    ("severity", 1usize.into()),
+                    ("source", "woc".into()),
+                    ("message", d.message().into()),
+                ])
+            })
+            .collect();
+
+        self.documents.insert(uri.to_string(), analysis);
+        vec![diagnostics_notification(uri, diagnostics)]
+    }
+
+    // =================== Requests ===================
+
+    fn definition(&self, params: &Json) -> RequestResult {
+        let (uri, analysis, symbol) = self.symbol_at(params)?;
+        let position = symbol.and_then(|s| s.position().map(|p| (p, s.name())));
+
+        Ok(match position {
+            Some((position, name)) => Json::object(vec![
+                ("uri", uri.into()),
+                ("range", name_range(analysis.file(), position, name)),
+            ]),
+            None => Json::Null,
+        })
+    }
+
+    fn hover(&self, params: &Json) -> RequestResult {
+        let (_, _, symbol) = self.symbol_at(params)?;
+
+        Ok(match symbol {
+            Some(symbol) => {
+                let mut value = format!("```woc\n{}\n```", symbol.signature());
+                if let Some(doc) = symbol.doc() {
+                    value.push_str("\n\n");
+                    value.push_str(doc);
+                }
+                Json::object(vec![(
+                    "contents",
+                    Json::object(vec![("kind", "markdown".into()), ("value", value.into())]),
+                )])
+            }
+            None => Json::Null,
+        })
+    }
+
+    fn document_symbols(&self, params: &Json) -> RequestResult {
+        let (_, analysis) = self.document(params)?;
+        let symbols = analysis
+            .outline()
+            .iter()
+            .map(|outline| document_symbol(analysis.file(), outline))
+            .collect();
+        Ok(Json::Array(symbols))
+    }
+
+    fn completion(&self, params: &Json) -> RequestResult {
+        let (_, analysis) = self.document(params)?;
+
+        let mut items: Vec<Json> = KEYWORDS
+            .iter()
+            .map(|(keyword, _)| completion_item(keyword, COMPLETION_KEYWORD, keyword))
+            .collect();
+
+        let mut names: Vec<&str> = Vec::new();
+        for symbol in analysis.symbols() {
+            if names.contains(&symbol.name()) {
+                continue;
+            }
+            names.push(symbol.name());
+
+            let kind = match symbol.kind() {
+                SymbolKind::Function | SymbolKind::Builtin => COMPLETION_FUNCTION,
+                SymbolKind::Variable | SymbolKind::Parameter => COMPLETION_VARIABLE,
+            };
+            items.push(completion_item(symbol.name(), kind, &symbol.signature()));
+        }
+
+        Ok(Json::Array(items))
+    }
+
+    // =================== Helper Functions ===================
+
+    // Get the URI and the analysis of the document in the request.
+    fn document<'a>(&'a self, params: &'a Json) -> Result<(&'a str, &'a Analysis), (i64, String)> {
+        let uri = params
+            .get("textDocument")
+            .and_then(|d| d.get("uri"))
+            .and_then(Json::as_str)
+            .ok_or((INVALID_PARAMS, "missing the text document".to_string()))?;
+        let analysis = self.documents.get(uri).ok_or((
+            INVALID_PARAMS,
+            format!("the document `{}` is not open", uri),
+        ))?;
+        Ok((uri, analysis))
+    }
+
+    // Get the symbol of the name at the position of the request.
+    fn symbol_at<'a>(
+        &'a self,
+        params: &'a Json,
+    ) -> Result<(&'a str, &'a Analysis, Option<&'a Symbol>), (i64, String)> {
+        let (uri, analysis) = self.document(params)?;
+        let (line, column) = params
+            .get("position")
+            .and_then(|p| from_lsp_position(analysis.file(), p))
+            .ok_or((INVALID_PARAMS, "missing the position".to_string()))?;
+        Ok((uri, analysis, analysis.symbol_at(line, column)))
+    }
+}
+
+/// Serve a client over the input and output until the client sends `exit` or closes the input.
+/// It fails if the client exits without `shutdown`, as the protocol requires an error exit code then.
+pub fn run(input: &mut impl BufRead, output: &mut impl Write) -> Result<(), String> {
+    let mut server = Server::new();
+
+    while let Some(body) = read_message(input)? {
+        let messages = match Json::parse(&body) {
+            Ok(message) => server.handle(&message),
+            Err(msg) => vec![error_response(Json::Null, PARSE_ERROR, &msg)],
+        };
+        for message in messages.iter() {
+            write_message(output, message)?;
+        }
+
+        if server.is_exited() {
+            break;
+        }
+    }
+
+    match server.is_shutdown() {
+        true => Ok(()),
+        false => Err("the client exited without shutting down the language server".to_string()),
+    }
+}
+
+/// Read the body of a message framed by the `Content-Length` header, `None` means the input is closed.
+pub fn read_message(input: &mut impl BufRead) -> Result<Option<String>, String> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        let read = input
+            .read_line(&mut line)
+            .map_err(|e| format!("failed to read the message: {}", e))?;
+        if read == 0 {
+            return Ok(None);
+        }
+
+        // The headers end with an empty line.
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let length = length.ok_or("missing the Content-Length header".to_string())?;
+    let mut body = vec![0; length];
+    input
+        .read_exact(&mut body)
+        .map_err(|e| format!("failed to read the message: {}", e))?;
+    String::from_utf8(body)
+        .map(Some)
+        .map_err(|_| "the message is not valid UTF-8".to_string())
+}
+
+/// Write the message with the `Content-Length` header.
+pub fn write_message(output: &mut impl Write, message: &Json) -> Result<(), String> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)
+        .and_then(|_| output.flush())
+        .map_err(|e| format!("failed to write the message: {}", e))
+}
+
+fn capabilities() -> Json {
+    Json::object(vec![
+        (
+            "capabilities",
+            Json::object(vec![
+                // The documents are synchronized with their full text.
+                ("textDocumentSync", 1usize.into()),
+                ("definitionProvider", true.into()),
+                ("hoverProvider", true.into()),
+                ("documentSymbolProvider", true.into()),
+                ("completionProvider", Json::object(Vec::new())),
+            ]),
+        ),
+        ("serverInfo", Json::object(vec![("name", "woc".into())])),
+    ])
+}
+
+fn error_response(id: Json, code: i64, message: &str) -> Json {
+    Json::object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("id", id),
+        (
+            "error",
+            Json::object(vec![("code", code.into()), ("message", message.into())]),
+        ),
+    ])
+}
+
+fn diagnostics_notification(uri: &str, diagnostics: Vec<Json>) -> Json {
+    Json::object(vec![
+        ("jsonrpc", "2.0".into()),
+        ("method", "textDocument/publishDiagnostics".into()),
+        (
+            "params",
+            Json::object(vec![
+                ("uri", uri.into()),
+                ("diagnostics", diagnostics.into()),
+            ]),
+        ),
+    ])
+}
+
+fn document_symbol(file: &WocFile, outline: &Outline) -> Json {
+    let symbol = outline.symbol();
+    let range = match symbol.position() {
+        Some(position) => name_range(file, position, symbol.name()),
+        None => lsp_range(file, (1, 1), (1, 1)),
+    };
+    let children = outline
+        .children()
+        .iter()
+        .map(|child| document_symbol(file, child))
+        .collect();
+
+    Json::object(vec![
+        ("name", symbol.name().into()),
+        ("detail", symbol.signature().into()),
+        ("kind", SYMBOL_FUNCTION.into()),
+        ("range", range.clone()),
+        ("selectionRange", range),
+        ("children", Json::Array(children)),
+    ])
+}
+
+fn completion_item(label: &str, kind: usize, detail: &str) -> Json {
+    Json::object(vec![
+        ("label", label.into()),
+        ("kind", kind.into()),
+        ("detail", detail.into()),
+    ])
+}
+
+// =================== Positions ===================
+// The protocol counts the lines from 0 and the columns in UTF-16 code units,
+// the lexer counts both of them from 1 and the columns in chars.
+
+fn from_lsp_position(file: &WocFile, position: &Json) -> Option<(usize, usize)> {
+    let line = position.get("line")?.as_i64()? as usize + 1;
+    let character = position.get("character")?.as_i64()? as usize;
+
+    let text = file.get_line(line).unwrap_or_default();
+    let mut units = 0;
+    let mut column = 1;
+    for c in text.chars() {
+        if units >= character {
+            break;
+        }
+        units += c.len_utf16();
+        column += 1;
+    }
+    Some((line, column))
+}
+
+fn lsp_position(file: &WocFile, (line, column): (usize, usize)) -> Json {
+    let text = file.get_line(line).unwrap_or_default();
+    let character: usize = text
+        .chars()
+        .take(column.saturating_sub(1))
+        .map(char::len_utf16)
+        .sum();
+    Json::object(vec![
+        ("line", line.saturating_sub(1).into()),
+        ("character", character.into()),
+    ])
+}
+
+fn lsp_range(file: &WocFile, start: (usize, usize), end: (usize, usize)) -> Json {
+    Json::object(vec![
+        ("start", lsp_position(file, start)),
+        ("end", lsp_position(file, end)),
+    ])
+}
+
+fn name_range(file: &WocFile, (line, column): (usize, usize), name: &str) -> Json {
+    lsp_range(file, (line, column), (line, column + name.chars().count()))
+}
@@ -1 +1,326 @@
-fn main() {}
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+
+use woc_lang::ast_v2::Node;
+use woc_lang::checker::checker::TypeChecker;
+use woc_lang::compiler::compiler::Compiler;
+use woc_lang::compiler::wocc::{self, WOCC_EXTENSION};
+use woc_lang::evaluator_v2::call_stack::{DEFAULT_MAX_DEPTH, STACK_PER_CALL};
+use woc_lang::evaluator_v2::evaluator::Evaluator;
+use woc_lang::formatter::formatter::{format_source, FormatConfig};
+use woc_lang::fs::workspace::Workspace;
+use woc_lang::linter::linter::{lint_source, LintConfig};
+use woc_lang::lsp::json::Json;
+use woc_lang::lsp::server;
+use woc_lang::object::object::Object;
+use woc_lang::optimizer::optimizer;
+use woc_lang::parser_v2::parser::Parser;
+use woc_lang::resolver::resolver::Resolver;
+use woc_lang::vm::vm::Vm;
+
+const USAGE: &str = "Usage:
+    woc run [path] [options]       Run a .woc or .wocc file, or the entry file of the project directory (default: .)
+        --max-depth <n>            The maximum depth of the nested function calls (default: 1000)
+    woc compile <path> [options]   Compile a .woc file, or the entry file of the project directory, to a .wocc file
+        -o <output>                The output file (default: the source file with the .wocc extension)
+        --strip                    Do not write the debug line table
+    woc fmt [paths...] [options]   Format the .woc files in place, a directory is formatted with all its files (default: .)
+        --check                    Do not write the files, fail if any of them is not formatted
+        --indent <n>               The number of spaces of an indent (default: 4)
+        --width <n>                The maximum line width (default: 100)
+    woc lint [paths...] [options]  Check the .woc files for likely mistakes, a directory uses the [lint] table of its woc.toml
+        --json                     Print the problems as a JSON array
+        --enable <rule>            Turn the rule on
+        --disable <rule>           Turn the rule off
+    woc lsp                        Start the language server, it speaks the Language Server Protocol over stdio";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = match args.first().map(|s| s.as_str()) {
+        Some("run") => run(&args[1..]),
+        Some("compile") => compile(&args[1..]),
+        Some("fmt") => format(&args[1..]),
+        Some("lint") => lint(&args[1..]),
+        Some("lsp") => server::run(&mut io::stdin().lock(), &mut io::stdout().lock()),
+        _ => Err(USAGE.to_string()),
+    };
+
+    if let Err(msg) = result {
+        eprintln!("{}", msg);
+        process::exit(1);
+    }
+}
+
+// Run the script, if the path is a directory, the entry file of the workspace will be run.
+// The precompiled script is executed by the virtual machine.
+fn run(args: &[String]) -> Result<(), String> {
+    let mut path = None;
+    let mut max_depth = DEFAULT_MAX_DEPTH;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--max-depth" => max_depth = number_arg(iter.next())?,
+            _ if path.is_none() => path = Some(arg.as_str()),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    let entry = entry_file(path.unwrap_or("."))?;
+
+    if entry.extension().is_some_and(|ext| ext == WOCC_EXTENSION) {
+        let bytecode = wocc::read_file(&entry)?;
+        print_result(Vm::new(bytecode).with_max_depth(max_depth).run()?);
+        return Ok(());
+    }
+
+    // Every function call of the evaluator nests the Rust calls, so it runs on a thread
+    // whose stack is large enough for the maximum depth, half of it can be taken by the calls.
+    // The AST isn't shared between threads, so the script is parsed on the thread too.
+    let stack_size = STACK_PER_CALL.saturating_mul(max_depth + 1);
+    thread::Builder::new()
+        .stack_size(stack_size)
+        .spawn(move || {
+            let programs = optimizer::optimize(&parse(&entry)?);
+            let evaluator = Evaluator::new(None)
+                .with_max_depth(max_depth)
+                .with_stack_limit(stack_size / 2);
+
+            let mut result = Object::Null;
+            for node in programs.iter() {
+                result = evaluator.eval(node);
+                // The error that is not caught stops the program, the same as the virtual machine.
+                if let Object::Throw(err) = result {
+                    return Err(err.report());
+                }
+            }
+            print_result(result);
+            Ok(())
+        })
+        .map_err(|e| format!("failed to start the evaluator: {}", e))?
+        .join()
+        .map_err(|_| "the evaluator panicked".to_string())?
+}
+
+// Print the result of the script, the value of a top-level `return` is the result too.
+fn print_result(mut result: Object) {
+    if let Object::Return(v) = result {
+        result = *v;
+    }
+
+    if !result.is_null() {
+        println!("{}", result);
+    }
+}
+
+// Compile the script to the `.wocc` file.
+fn compile(args: &[String]) -> Result<(), String> {
+    let mut source = None;
+    let mut output = None;
+    let mut debug = true;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-o" => output = Some(PathBuf::from(iter.next().ok_or(USAGE.to_string())?)),
+            "--strip" => debug = false,
+            _ if source.is_none() => source = Some(arg.as_str()),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    let source = entry_file(source.ok_or(USAGE.to_string())?)?;
+    let output = output.unwrap_or(source.with_extension(WOCC_EXTENSION));
+
+    let programs = optimizer::optimize(&parse(&source)?);
+    let bytecode = Compiler::new().compile(&programs);
+
+    wocc::write_file(&output, &bytecode, debug)
+}
+
+// Format the source files in place, or only list the files that are not formatted with `--check`.
+// The files with syntax errors are left unchanged and reported.
+fn format(args: &[String]) -> Result<(), String> {
+    let mut paths = Vec::new();
+    let mut check = false;
+    let mut config = FormatConfig::default();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--check" => check = true,
+            "--indent" => config = FormatConfig::new(number_arg(iter.next())?, config.max_width()),
+            "--width" => {
+                config = FormatConfig::new(config.indent_width(), number_arg(iter.next())?)
+            }
+            _ => paths.push(arg.as_str()),
+        }
+    }
+    if paths.is_empty() {
+        paths.push(".");
+    }
+
+    let mut errors = Vec::new();
+    let mut unformatted = Vec::new();
+    for path in source_files(&paths)? {
+        let source = fs::read_to_string(&path)
+            .map_err(|e| format!("failed to read '{}': {}", path.display(), e))?;
+        let formatted = match format_source(&source, &config) {
+            Ok(formatted) => formatted,
+            Err(msg) => {
+                errors.push(format!("{}:\n{}", path.display(), msg));
+                continue;
+            }
+        };
+
+        if formatted == source {
+            continue;
+        } else if check {
+            unformatted.push(path.display().to_string());
+        } else {
+            fs::write(&path, formatted)
+                .map_err(|e| format!("failed to write '{}': {}", path.display(), e))?;
+        }
+    }
+
+    if !unformatted.is_empty() {
+        errors.push(format!("not formatted:\n{}", unformatted.join("\n")));
+    }
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors.join("\n")),
+    }
+}
+
+// Check the source files with the lint rules, the rules of the command line override the ones of `woc.toml`.
+// It fails if any problem is found, so it can be used in CI.
+fn lint(args: &[String]) -> Result<(), String> {
+    let mut paths = Vec::new();
+    let mut json = false;
+    let mut overrides = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--enable" | "--disable" => {
+                let rule = iter.next().ok_or(USAGE.to_string())?;
+                overrides.push((rule.as_str(), arg == "--enable"));
+            }
+            _ => paths.push(arg.as_str()),
+        }
+    }
+    if paths.is_empty() {
+        paths.push(".");
+    }
+
+    let mut errors = Vec::new();
+    let mut problems = Vec::new();
+    for path in paths {
+        let mut config = LintConfig::default();
+        if Path::new(path).is_dir() {
+            for (rule, enabled) in Workspace::load(Path::new(path))?.manifest().lint_rules() {
+                config
+                    .set(rule, *enabled)
+                    .map_err(|e| format!("woc.toml: {}", e))?;
+            }
+        }
+        for (rule, enabled) in overrides.iter() {
+            config.set(rule, *enabled)?;
+        }
+
+        for file in source_files(&[path])? {
+            let source = fs::read_to_string(&file)
+                .map_err(|e| format!("failed to read '{}': {}", file.display(), e))?;
+            match lint_source(&source, &config) {
+                Ok(lints) => problems.extend(lints.into_iter().map(|lint| (file.clone(), lint))),
+                Err(msg) => errors.push(format!("{}:\n{}", file.display(), msg)),
+            }
+        }
+    }
+
+    if json {
+        let items = problems.iter().map(|(file, lint)| {
+            let (line, column) = lint.position();
+            Json::object(vec![
+                ("file", file.display().to_string().into()),
+                ("line", line.into()),
+                ("column", column.into()),
+                ("rule", lint.rule().into()),
+                ("message", lint.message().into()),
+            ])
+        });
+        println!("{}", Json::Array(items.collect()));
+    } else {
+        for (file, lint) in problems.iter() {
+            println!("{}:{}", file.display(), lint);
+        }
+    }
+
+    if !problems.is_empty() {
+        errors.push(format!("found {} lint problem(s)", problems.len()));
+    }
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors.join("\n")),
+    }
+}
+
+// Get the source files of the paths, a directory contains all the `.woc` files of its workspace.
+fn source_files(paths: &[&str]) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    for path in paths.iter().map(Path::new) {
+        if path.is_dir() {
+            let workspace = Workspace::load(path)?;
+            files.extend(workspace.tree().files().into_iter().map(Path::to_path_buf));
+        } else {
+            files.push(path.to_path_buf());
+        }
+    }
+    Ok(files)
+}
+
+// Parse the value of a numeric option.
+fn number_arg(arg: Option<&String>) -> Result<usize, String> {
+    arg.and_then(|s| s.parse().ok()).ok_or(USAGE.to_string())
+}
+
+// Parse, check and resolve the script, the errors found by the type checker and the resolver are reported together.
+fn parse(path: &Path) -> Result<Vec<Node>, String> {
+    let source = fs::read_to_string(path)
+        .map_err(|e| format!("failed to read '{}': {}", path.display(), e))?;
+    let parser = Parser::from_source(&source);
+    let errors = parser.errors();
+    if !errors.is_empty() {
+        return Err(errors.join("\n"));
+    }
+
+    let programs = parser.programs();
+    let mut errors: Vec<String> = match TypeChecker::new().check(&programs) {
+        Ok(()) => Vec::new(),
+        Err(type_errors) => type_errors.iter().map(|e| e.to_string()).collect(),
+    };
+    match Resolver::new().resolve(&programs) {
+        Ok(programs) if errors.is_empty() => Ok(programs),
+        Ok(_) => Err(errors.join("\n")),
+        Err(resolve_errors) => {
+            errors.extend(resolve_errors);
+            Err(errors.join("\n"))
+        }
+    }
+}
+
+// Get the file to run or compile, if the path is a directory, it's the entry file of the workspace.
+fn entry_file(path: &str) -> Result<PathBuf, String> {
+    let path = Path::new(path);
+    if path.is_dir() {
+        let workspace = Workspace::load(path)?;
+        Ok(workspace.entry()?.to_path_buf())
+    } else {
+        Ok(path.to_path_buf())
+    }
+}
@@ -1,8 +1,12 @@
+use std::cmp::Ordering;
 use std::fmt::{self, Debug, Display, Formatter};
+use std::rc::Rc;
 
 use crate::{
     ast_v2::{expressions::IdentifierExp, statements::BlockStatement},
-    evaluator_v2::{evaluator::Evaluator, scope::scope::Scope},
+    builtins::builtins::Builtin,
+    compiler::opcode::{Capture, OpCode},
+    evaluator_v2::evaluator::Evaluator,
 };
 
 #[derive(Clone)]
@@ -19,8 +23,22 @@ pub enum Object {
 
     // ===== Statement =====
     Return(Box<Object>),
+    // The thrown error, it unwinds the evaluation like `return` until it's caught by `try`,
+    // or stops the program and is reported with its message.
+    Throw(Error),
+    // The call in tail position, e.g. `return f(x);`, it leaves the function like `return`,
+    // and the caller calls the function in a loop instead of nesting the calls.
+    TailCall(Box<(Function, CallFrame)>),
+
+    // ===== Error =====
+    // The error value, it's what `catch` binds and what `error(kind, message)` creates.
+    Error(Error),
 
     Func(Function),
+    Builtin(Builtin),
+
+    // ===== Virtual Machine =====
+    Closure(Closure),
 }
 
 #[derive(PartialEq, Eq)]
@@ -29,14 +47,37 @@ pub enum ObjectType {
     Integer,
     Float,
     Boolean,
+    Char,
+    Byte,
 
     String,
     Array,
 
     Return,
+    Error,
     Func,
 }
 
+/// The type names are the same as the type annotations, e.g. `int` and `str`.
+impl Display for ObjectType {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let name = match self {
+            ObjectType::Null => "null",
+            ObjectType::Integer => "int",
+            ObjectType::Float => "float",
+            ObjectType::Boolean => "bool",
+            ObjectType::Char => "char",
+            ObjectType::Byte => "byte",
+            ObjectType::String => "str",
+            ObjectType::Array => "array",
+            ObjectType::Return => "return",
+            ObjectType::Error => "error",
+            ObjectType::Func => "func",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 impl Object {
     pub fn obj_type(&self) -> ObjectType {
         match self {
@@ -45,14 +86,41 @@ impl Object {
                 BaseValue::Integer(_) => ObjectType::Integer,
                 BaseValue::Float(_) => ObjectType::Float,
                 BaseValue::Boolean(_) => ObjectType::Boolean,
+                BaseValue::Char(_) => ObjectType::Char,
+                BaseValue::Byte(_) => ObjectType::Byte,
             },
             Object::Str(_) => ObjectType::String,
             Object::Array(_) => ObjectType::Array,
-            Object::Return(_) => ObjectType::Return,
-            Object::Func(_) => ObjectType::Func,
+            Object::Return(_) | Object::TailCall(_) => ObjectType::Return,
+            Object::Error(_) | Object::Throw(_) => ObjectType::Error,
+            Object::Func(_) | Object::Builtin(_) | Object::Closure(_) => ObjectType::Func,
+        }
+    }
+
+    // Get the value of the `return`, the other values are returned as they are.
+    fn unwrap_return(&self) -> &Object {
+        match self {
+            Object::Return(v) => v.unwrap_return(),
+            v => v,
         }
     }
 
+    /// Only the true booleans and the non-zero numbers are truthy, the other values are all falsy.
+    /// It's used by the conditions, `&&` and `||` of both the evaluator and the virtual machine.
+    pub fn is_truthy(&self) -> bool {
+        match self.unwrap_return() {
+            Object::Base(BaseValue::Boolean(v)) => *v.value(),
+            Object::Base(BaseValue::Integer(v)) => !v.is_zero(),
+            Object::Base(BaseValue::Float(v)) => !v.is_zero(),
+            _ => false,
+        }
+    }
+
+    /// Check whether the value is a thrown error that is still unwinding.
+    pub fn is_thrown(&self) -> bool {
+        matches!(self, Object::Throw(_))
+    }
+
     pub fn is_null(&self) -> bool {
         match self {
             Object::Null => true,
@@ -69,6 +137,8 @@ impl Display for Object {
                 BaseValue::Integer(v) => write!(f, "{}", v.value()),
                 BaseValue::Float(v) => write!(f, "{}", v.value()),
                 BaseValue::Boolean(v) => write!(f, "{}", v.value()),
+                BaseValue::Char(v) => write!(f, "{}", v.value()),
+                BaseValue::Byte(v) => write!(f, "{}", v.value()),
             },
             Object::Str(str) => write!(f, "{}", str.value()),
             Object::Array(arr) => write!(f, "{:?}", arr.elements()),
@@ -77,10 +147,17 @@ impl Display for Object {
                     BaseValue::Integer(v) => write!(f, "return {}", v.value()),
                     BaseValue::Float(v) => write!(f, "return {}", v.value()),
                     BaseValue::Boolean(v) => write!(f, "return {}", v.value()),
+                    BaseValue::Char(v) => write!(f, "return {}", v.value()),
+                    BaseValue::Byte(v) => write!(f, "return {}", v.value()),
                 },
                 _ => write!(f, "null"),
             },
+            Object::Error(err) => write!(f, "{}", err),
+            Object::Throw(err) => write!(f, "throw {}", err),
+            Object::TailCall(call) => write!(f, "return {}(...)", call.0.name()),
             Object::Func(func) => write!(f, "{}", func),
+            Object::Builtin(builtin) => write!(f, "{}", builtin),
+            Object::Closure(closure) => write!(f, "{}", closure),
         }
     }
 }
@@ -93,6 +170,8 @@ impl Debug for Object {
                 BaseValue::Integer(v) => write!(f, "{:?}", v),
                 BaseValue::Float(v) => write!(f, "{:?}", v),
                 BaseValue::Boolean(v) => write!(f, "{:?}", v),
+                BaseValue::Char(v) => write!(f, "{:?}", v),
+                BaseValue::Byte(v) => write!(f, "b{:?}", *v.value() as char),
             },
             Object::Str(str) => write!(f, "{:?}", str),
             Object::Array(arr) => write!(f, "{:?}", arr),
@@ -101,10 +180,244 @@ impl Debug for Object {
                     BaseValue::Integer(v) => write!(f, "return {:?}", v),
                     BaseValue::Float(v) => write!(f, "return {:?}", v),
                     BaseValue::Boolean(v) => write!(f, "return {:?}", v),
+                    BaseValue::Char(v) => write!(f, "return {:?}", v),
+                    BaseValue::Byte(v) => write!(f, "return b{:?}", *v.value() as char),
                 },
                 _ => write!(f, "null"),
             },
+            Object::Error(err) => write!(f, "{:?}", err),
+            Object::Throw(err) => write!(f, "throw {:?}", err),
+            Object::TailCall(call) => write!(f, "return {}(...)", call.0.name()),
             Object::Func(func) => write!(f, "{:?}", func),
+            Object::Builtin(builtin) => write!(f, "{}", builtin),
+            Object::Closure(closure) => write!(f, "{:?}", closure),
+        }
+    }
+}
+
+/// The values of different types are never equal, except that an integer is compared with a float
+/// by its exact value. The arrays are equal if all their elements are equal, the functions are equal if
+/// they are the same builtin or come from the same definition, and a returned value is compared
+/// by its value.
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.unwrap_return(), other.unwrap_return()) {
+            (Object::Null, Object::Null) => true,
+            (Object::Base(l), Object::Base(r)) => match (l, r) {
+                (BaseValue::Integer(l), BaseValue::Integer(r)) => l.value() == r.value(),
+                (BaseValue::Integer(l), BaseValue::Float(r)) => {
+                    compare_int_float(*l.value(), *r.value()) == Some(Ordering::Equal)
+                }
+                (BaseValue::Float(l), BaseValue::Integer(r)) => {
+                    compare_int_float(*r.value(), *l.value()) == Some(Ordering::Equal)
+                }
+                (BaseValue::Float(l), BaseValue::Float(r)) => l.value() == r.value(),
+                (BaseValue::Boolean(l), BaseValue::Boolean(r)) => l.value() == r.value(),
+                (BaseValue::Char(l), BaseValue::Char(r)) => l.value() == r.value(),
+                (BaseValue::Byte(l), BaseValue::Byte(r)) => l.value() == r.value(),
+                _ => false,
+            },
+            (Object::Str(l), Object::Str(r)) => l.value() == r.value(),
+            (Object::Array(l), Object::Array(r)) => l.elements() == r.elements(),
+            (Object::Error(l), Object::Error(r)) => {
+                l.kind() == r.kind() && l.message() == r.message()
+            }
+            (Object::Func(l), Object::Func(r)) => Rc::ptr_eq(&l.body, &r.body),
+            (Object::Builtin(l), Object::Builtin(r)) => l.name() == r.name(),
+            (Object::Closure(l), Object::Closure(r)) => Rc::ptr_eq(l.func(), r.func()),
+            _ => false,
+        }
+    }
+}
+
+/// The numbers, chars, bytes and strings are ordered by their values, and the arrays are ordered
+/// element by element like the strings. The values of the other types are only equal or unordered.
+impl PartialOrd for Object {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self.unwrap_return(), other.unwrap_return()) {
+            (Object::Base(l), Object::Base(r)) => match (l, r) {
+                (BaseValue::Integer(l), BaseValue::Integer(r)) => l.value().partial_cmp(r.value()),
+                (BaseValue::Integer(l), BaseValue::Float(r)) => {
+                    compare_int_float(*l.value(), *r.value())
+                }
+                (BaseValue::Float(l), BaseValue::Integer(r)) => {
+                    compare_int_float(*r.value(), *l.value()).map(Ordering::reverse)
+                }
+                (BaseValue::Float(l), BaseValue::Float(r)) => l.value().partial_cmp(r.value()),
+                (BaseValue::Char(l), BaseValue::Char(r)) => l.value().partial_cmp(r.value()),
+                (BaseValue::Byte(l), BaseValue::Byte(r)) => l.value().partial_cmp(r.value()),
+                _ => (self == other).then_some(Ordering::Equal),
+            },
+            (Object::Str(l), Object::Str(r)) => l.value().partial_cmp(r.value()),
+            (Object::Array(l), Object::Array(r)) => l.elements().partial_cmp(r.elements()),
+            _ => (self == other).then_some(Ordering::Equal),
+        }
+    }
+}
+
+// Compare an integer with a float exactly, the integer isn't converted to a float, which rounds
+// the integers above 2^53, so the order stays transitive. NaN is unordered with every integer.
+fn compare_int_float(int: i64, float: f64) -> Option<Ordering> {
+    // -2^63 and 2^63 are exact floats, the floats out of them are beyond every integer.
+    const LIMIT: f64 = 9_223_372_036_854_775_808.0;
+    if float.is_nan() {
+        return None;
+    } else if float >= LIMIT {
+        return Some(Ordering::Less);
+    } else if float < -LIMIT {
+        return Some(Ordering::Greater);
+    }
+
+    // The integral part of the float is an exact integer in the range, its fraction decides the tie.
+    let trunc = float.trunc();
+    match int.cmp(&(trunc as i64)) {
+        Ordering::Equal => trunc.partial_cmp(&float),
+        ordering => Some(ordering),
+    }
+}
+
+/// The error raised by `throw`, a builtin function or the runtime, e.g. dividing by zero.
+/// The trace records the calls of the functions it unwound through, the innermost one first.
+#[derive(Clone)]
+pub struct Error {
+    kind: String,
+    message: String,
+    trace: Vec<CallFrame>,
+}
+
+impl Error {
+    pub fn new(kind: &str, message: String) -> Self {
+        Self {
+            kind: kind.to_string(),
+            message,
+            trace: vec![],
+        }
+    }
+
+    /// Get the error raised by `throw`, an error value is thrown as it is,
+    /// and the other values become the message of an `Error`.
+    pub fn thrown(value: Object) -> Self {
+        match value {
+            Object::Error(err) => err,
+            Object::Return(v) => Self::thrown(*v),
+            v => Self::new("Error", v.to_string()),
+        }
+    }
+
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn trace(&self) -> &Vec<CallFrame> {
+        &self.trace
+    }
+
+    /// Record that the error unwound through the function call.
+    pub fn push_trace(&mut self, frame: CallFrame) {
+        self.trace.push(frame);
+    }
+
+    /// Format the error with its backtrace, one call per line and the innermost one first.
+    /// The same calls in a row, e.g. of a recursive function, are only shown once.
+    ///
+    /// ```text
+    /// RuntimeError: division by zero
+    ///     at div (line 2, column 12)
+    ///     at main (line 5, column 1)
+    /// ```
+    pub fn report(&self) -> String {
+        let mut report = self.to_string();
+        let mut i = 0;
+        while i < self.trace.len() {
+            let frame = &self.trace[i];
+            let repeated = self.trace[i..].iter().take_while(|f| *f == frame).count();
+            report.push_str(&format!("\n    {}", frame));
+            if repeated > 1 {
+                report.push_str(&format!("\n    ... repeated {} more times", repeated - 1));
+            }
+            i += repeated;
+        }
+        report
+    }
+}
+
+/// The errors of the virtual machine are all runtime errors.
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Self::new("RuntimeError", message)
+    }
+}
+
+impl Debug for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}({:?})", self.kind, self.message)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.kind, self.message)
+    }
+}
+
+/// A call of a function, the position is the (line, column) where the function is called,
+/// or `None` if it's unknown. The bytecode only keeps the lines, so the column can be unknown alone.
+#[derive(Clone, PartialEq)]
+pub struct CallFrame {
+    name: String,
+    line: Option<usize>,
+    column: Option<usize>,
+}
+
+impl CallFrame {
+    pub fn new(name: &str, position: Option<(usize, usize)>) -> Self {
+        Self {
+            name: name.to_string(),
+            line: position.map(|(line, _)| line),
+            column: position.map(|(_, column)| column),
+        }
+    }
+
+    /// Create the call whose column is unknown, e.g. in the bytecode.
+    pub fn at_line(name: &str, line: Option<usize>) -> Self {
+        Self {
+            name: name.to_string(),
+            line,
+            column: None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn line(&self) -> Option<usize> {
+        self.line
+    }
+
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.line.zip(self.column)
+    }
+}
+
+impl Debug for CallFrame {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl Display for CallFrame {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => {
+                write!(f, "at {} (line {}, column {})", self.name, line, column)
+            }
+            (Some(line), None) => write!(f, "at {} (line {})", self.name, line),
+            _ => write!(f, "at {}", self.name),
         }
     }
 }
@@ -171,38 +484,46 @@ impl Display for Array {
 pub struct Function {
     name: String,
     parameters: Option<Vec<IdentifierExp>>,
-    body: BlockStatement,
+    // The body is shared by the copies of the function, every call evaluates a copy.
+    body: Rc<BlockStatement>,
     eval: Evaluator,
 }
 
 impl Function {
+    /// Create a function whose body is evaluated by the evaluator,
+    /// it's enclosed by the scope where the function is defined.
+    /// The functions that share the body come from the same definition.
     pub fn new(
         name: String,
         parameters: Option<Vec<IdentifierExp>>,
-        body: BlockStatement,
-        parent_scope: Option<Box<Scope>>,
+        body: Rc<BlockStatement>,
+        eval: Evaluator,
     ) -> Self {
         Self {
             name,
             parameters,
             body,
-            eval: Evaluator::new(parent_scope),
+            eval,
         }
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn add_arguments(&self, args: Vec<Object>) -> Result<(), String> {
-        if args.len() != self.parameters.as_ref().unwrap().len() {
+        let parameters = self.parameters.as_deref().unwrap_or(&[]);
+        if args.len() != parameters.len() {
             return Err(format!(
                 "wrong number of arguments. got={}, want={}",
                 args.len(),
-                self.parameters.as_ref().unwrap().len()
+                parameters.len()
             ));
         }
 
-        for (i, param) in self.parameters.as_ref().unwrap().iter().enumerate() {
-            self.eval
-                .scope()
-                .set(param.value().to_string(), args[i].clone());
+        // The parameters take the first slots of the scope, and the function itself takes the next one.
+        for (i, arg) in args.into_iter().enumerate() {
+            self.eval.scope().set(i, arg);
         }
 
         self.add_self_to_scope();
@@ -211,9 +532,8 @@ impl Function {
     }
 
     fn add_self_to_scope(&self) {
-        self.eval
-            .scope()
-            .set(self.name.clone(), Object::Func(self.clone()));
+        let slot = self.parameters.as_ref().map_or(0, |params| params.len());
+        self.eval.scope().set(slot, Object::Func(self.clone()));
     }
 
     pub fn eval(&self) -> Object {
@@ -261,14 +581,129 @@ impl Display for Function {
     }
 }
 
+/// The function compiled to bytecode, it's created by the compiler and executed by the virtual machine.
+pub struct CompiledFunction {
+    name: String,
+    params: Vec<String>,
+    // The number of local variables, including the parameters.
+    num_locals: u32,
+    instructions: Vec<OpCode>,
+    captures: Vec<Capture>,
+    // The debug line table, it maps the index of an instruction to the line number in the source file.
+    // It's sorted by the index of the instruction.
+    lines: Vec<(u32, u32)>,
+}
+
+impl CompiledFunction {
+    pub fn new(
+        name: String,
+        params: Vec<String>,
+        num_locals: u32,
+        instructions: Vec<OpCode>,
+        captures: Vec<Capture>,
+        lines: Vec<(u32, u32)>,
+    ) -> Self {
+        Self {
+            name,
+            params,
+            num_locals,
+            instructions,
+            captures,
+            lines,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn params(&self) -> &Vec<String> {
+        &self.params
+    }
+
+    pub fn arity(&self) -> usize {
+        self.params.len()
+    }
+
+    pub fn num_locals(&self) -> u32 {
+        self.num_locals
+    }
+
+    pub fn instructions(&self) -> &Vec<OpCode> {
+        &self.instructions
+    }
+
+    pub fn captures(&self) -> &Vec<Capture> {
+        &self.captures
+    }
+
+    pub fn lines(&self) -> &Vec<(u32, u32)> {
+        &self.lines
+    }
+
+    /// Get the line number of the instruction, it's the nearest line recorded at or before the instruction.
+    pub fn line_of(&self, ip: usize) -> Option<u32> {
+        let pos = self.lines.partition_point(|(index, _)| *index as usize <= ip);
+        if pos == 0 {
+            return None;
+        }
+        Some(self.lines[pos - 1].1)
+    }
+}
+
+impl Debug for CompiledFunction {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "fn {}({}) {:?}", self.name, self.params.join(", "), self.instructions)
+    }
+}
+
+/// The closure is a compiled function with the values it captured when it was created.
+/// Both of them are shared, so cloning a closure is cheap.
+#[derive(Clone)]
+pub struct Closure {
+    func: Rc<CompiledFunction>,
+    captures: Rc<Vec<Object>>,
+}
+
+impl Closure {
+    pub fn new(func: Rc<CompiledFunction>, captures: Vec<Object>) -> Self {
+        Self {
+            func,
+            captures: Rc::new(captures),
+        }
+    }
+
+    pub fn func(&self) -> &Rc<CompiledFunction> {
+        &self.func
+    }
+
+    pub fn captures(&self) -> &Vec<Object> {
+        &self.captures
+    }
+}
+
+impl Debug for Closure {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.func)
+    }
+}
+
+impl Display for Closure {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "fn {}({})", self.func.name, self.func.params.join(", "))
+    }
+}
+
 #[derive(Clone)]
 pub enum BaseValue {
     Integer(Value<i64>),
     Float(Value<f64>),
     Boolean(Value<bool>),
+    Char(Value<char>),
+    Byte(Value<u8>),
 }
 
-/// This is basic value struct that holds a value of integer, float, char and boolean
+/// This is basic value struct that holds a value of integer, float, char, byte and boolean
 /// For example, Value::new(10) will create a Value struct that holds an integer value of 10
 #[derive(Clone)]
 pub struct Value<T> {
@@ -0,0 +1,3 @@
+// The optimizer rewrites the AST before it's evaluated or compiled, the result has the same behavior as the input.
+// - optimizer: It folds the constant expressions and removes the code that is never executed.
+pub mod optimizer;
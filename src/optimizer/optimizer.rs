@@ -0,0 +1,258 @@
+use crate::ast_v2::expressions::{
+    ArrayExp, ArrayIndexExp, BooleanExp, CallExp, ElseExp, IfExp, InfixExp, InterpolatedExp,
+    NumExp, PrefixExp,
+};
+use crate::ast_v2::statements::{
+    BlockStatement, FuncStatement, LetStatement, ReturnStatement, ThrowStatement, TryStatement,
+};
+use crate::ast_v2::{Expression, Node, Statement};
+use crate::evaluator_v2::evaluator::Evaluator;
+use crate::object::object::{BaseValue, Object};
+use crate::token::types::TokenType;
+
+// The result of an if expression after its condition is folded.
+enum Branch {
+    // The condition is a literal, only this block can be executed.
+    Taken(BlockStatement),
+    // The condition is only known at runtime.
+    Dynamic(IfExp),
+}
+
+/// Optimize the programs, the result can be evaluated by the `Evaluator` or compiled by the `Compiler`.
+/// It does the following things without changing the result of the programs:
+/// - Folds the prefix and infix expressions whose operands are literals, e.g. `2 * 3 + 1` -> `7`.
+///   The expressions that fail at runtime (division by zero, integer overflow) are kept as they are.
+/// - Prunes the branches of the if expression that can never be executed, e.g. `if (true) { 1; } else { 2; }` -> `{ 1; }`.
+/// - Removes the statements after a `return` statement in a block statement.
+pub fn optimize(programs: &[Node]) -> Vec<Node> {
+    programs.iter().map(optimize_node).collect()
+}
+
+fn optimize_node(node: &Node) -> Node {
+    match node {
+        // An if expression with a literal condition is replaced by the block that is executed.
+        Node::Exp(Expression::If(if_exp)) => match optimize_if(if_exp) {
+            Branch::Taken(block) => Node::Stmt(Statement::Block(block)),
+            Branch::Dynamic(if_exp) => Node::Exp(Expression::If(if_exp)),
+        },
+        Node::Exp(exp) => Node::Exp(optimize_exp(exp)),
+        Node::Stmt(stmt) => Node::Stmt(optimize_stmt(stmt)),
+    }
+}
+
+// =================== Optimize Statement ===================
+
+fn optimize_stmt(stmt: &Statement) -> Statement {
+    match stmt {
+        Statement::Let(let_stmt) => Statement::Let(
+            LetStatement::new(let_stmt.ident().clone(), let_stmt.value().map(optimize_exp))
+                .with_annotation(let_stmt.annotation().cloned())
+                .with_doc(let_stmt.doc().map(|d| d.to_string())),
+        ),
+        Statement::Return(ret_stmt) => {
+            Statement::Return(ReturnStatement::new(ret_stmt.value().map(optimize_exp)))
+        }
+        Statement::Block(block_stmt) => Statement::Block(optimize_block(block_stmt)),
+        Statement::Func(func_stmt) => Statement::Func(
+            FuncStatement::new(
+                func_stmt.ident().clone(),
+                func_stmt.params().cloned(),
+                optimize_block(func_stmt.body()),
+            )
+            .with_types(
+                func_stmt.param_types().to_vec(),
+                func_stmt.return_type().cloned(),
+            )
+            .with_doc(func_stmt.doc().map(|d| d.to_string())),
+        ),
+        Statement::Throw(throw_stmt) => {
+            Statement::Throw(ThrowStatement::new(optimize_exp(throw_stmt.value())))
+        }
+        Statement::Try(try_stmt) => Statement::Try(TryStatement::new(
+            optimize_block(try_stmt.body()),
+            try_stmt
+                .catch()
+                .map(|(ident, block)| (ident.clone(), optimize_block(block))),
+            try_stmt.finally().map(optimize_block),
+        )),
+        Statement::Error(error_stmt) => Statement::Error(error_stmt.clone()),
+    }
+}
+
+fn optimize_block(block: &BlockStatement) -> BlockStatement {
+    let mut result = BlockStatement::new();
+
+    if let Some(stmts) = block.statements() {
+        for stmt in stmts {
+            result.add(Box::new(optimize_node(stmt)));
+
+            // The statements after the return or throw statement are never executed.
+            if let Node::Stmt(Statement::Return(_) | Statement::Throw(_)) = stmt.as_ref() {
+                break;
+            }
+        }
+    }
+
+    result
+}
+
+// =================== Optimize Expression ===================
+
+fn optimize_exp(exp: &Expression) -> Expression {
+    match exp {
+        Expression::Identifier(_)
+        | Expression::Num(_)
+        | Expression::Boolean(_)
+        | Expression::Str(_)
+        | Expression::Char(_)
+        | Expression::Byte(_) => exp.clone(),
+        Expression::Interpolated(interp_exp) => Expression::Interpolated(InterpolatedExp::new(
+            interp_exp.parts().iter().map(optimize_exp).collect(),
+        )),
+        Expression::Arr(arr_exp) => Expression::Arr(ArrayExp::new(
+            arr_exp.elements().iter().map(optimize_exp).collect(),
+        )),
+        Expression::ArrIndex(arr_index) => Expression::ArrIndex(ArrayIndexExp::new(
+            arr_index.name().clone(),
+            optimize_exp(arr_index.index()),
+        )),
+        Expression::If(if_exp) => match optimize_if(if_exp) {
+            // The if expression is kept in the expression, but it has only the block that is executed.
+            Branch::Taken(block) => Expression::If(IfExp::new(
+                Expression::Boolean(BooleanExp::new(true)),
+                block,
+                None,
+            )),
+            Branch::Dynamic(if_exp) => Expression::If(if_exp),
+        },
+        Expression::Call(call_exp) => Expression::Call(CallExp::new(
+            call_exp.name().clone(),
+            call_exp.arguments().iter().map(optimize_exp).collect(),
+            call_exp.line(),
+        )),
+        Expression::Prefix(pre_exp) => {
+            let right = optimize_exp(pre_exp.right());
+            let pre_exp = PrefixExp::new(pre_exp.operator().clone(), right);
+            fold_prefix(&pre_exp).unwrap_or(Expression::Prefix(pre_exp))
+        }
+        Expression::Infix(infix_exp) => {
+            let left = optimize_exp(infix_exp.left());
+            let right = optimize_exp(infix_exp.right());
+            let infix_exp = InfixExp::new(left, infix_exp.operator().clone(), right);
+            fold_infix(&infix_exp).unwrap_or(Expression::Infix(infix_exp))
+        }
+    }
+}
+
+fn optimize_if(if_exp: &IfExp) -> Branch {
+    let condition = optimize_exp(if_exp.condition());
+
+    match literal_truthy(&condition) {
+        Some(true) => Branch::Taken(optimize_block(if_exp.consequence())),
+        Some(false) => match if_exp.else_exp() {
+            Some(else_exp) => match else_exp.if_exp() {
+                Some(else_if) => optimize_if(else_if),
+                None => Branch::Taken(optimize_block(else_exp.consequence().unwrap())),
+            },
+            // Nothing is executed, the empty block evaluates to null like the if expression.
+            None => Branch::Taken(BlockStatement::new()),
+        },
+        None => {
+            let else_exp = if_exp.else_exp().and_then(optimize_else);
+            Branch::Dynamic(IfExp::new(
+                condition,
+                optimize_block(if_exp.consequence()),
+                else_exp,
+            ))
+        }
+    }
+}
+
+// Optimize the else expression of an if expression whose condition is only known at runtime.
+fn optimize_else(else_exp: &ElseExp) -> Option<ElseExp> {
+    match else_exp.if_exp() {
+        Some(else_if) => match optimize_if(else_if) {
+            Branch::Taken(block) => Some(ElseExp::new(None, Some(block))),
+            Branch::Dynamic(if_exp) => Some(ElseExp::new(Some(Box::new(if_exp)), None)),
+        },
+        None => Some(ElseExp::new(
+            None,
+            Some(optimize_block(else_exp.consequence().unwrap())),
+        )),
+    }
+}
+
+// =================== Constant Folding ===================
+
+fn fold_prefix(pre_exp: &PrefixExp) -> Option<Expression> {
+    // The negation of the minimum integer overflows.
+    if let (TokenType::Minus, Expression::Num(num)) = (pre_exp.operator(), pre_exp.right()) {
+        if num
+            .integer_value()
+            .is_some_and(|v| v.checked_neg().is_none())
+        {
+            return None;
+        }
+    }
+
+    if !is_literal(pre_exp.right()) {
+        return None;
+    }
+
+    eval_literal(Expression::Prefix(pre_exp.clone()))
+}
+
+fn fold_infix(infix_exp: &InfixExp) -> Option<Expression> {
+    if !is_literal(infix_exp.left()) || !is_literal(infix_exp.right()) {
+        return None;
+    }
+
+    // The integer arithmetic that fails at runtime is not folded.
+    if let (Expression::Num(l), Expression::Num(r)) = (infix_exp.left(), infix_exp.right()) {
+        if let (Some(l), Some(r)) = (l.integer_value(), r.integer_value()) {
+            let checked = match infix_exp.operator() {
+                TokenType::Plus => l.checked_add(r),
+                TokenType::Minus => l.checked_sub(r),
+                TokenType::Asterisk => l.checked_mul(r),
+                TokenType::Slash => l.checked_div(r),
+                _ => Some(0),
+            };
+            checked?;
+        }
+    }
+
+    eval_literal(Expression::Infix(infix_exp.clone()))
+}
+
+// Evaluate the expression whose operands are literals by the evaluator, so the folded value is
+// exactly the same as the value at runtime. The result that can't be written as a literal is not folded.
+fn eval_literal(exp: Expression) -> Option<Expression> {
+    match Evaluator::new(None).eval(&Node::Exp(exp)) {
+        Object::Base(BaseValue::Integer(v)) => {
+            Some(Expression::Num(NumExp::new(Some(*v.value()), None)))
+        }
+        Object::Base(BaseValue::Float(v)) => {
+            Some(Expression::Num(NumExp::new(None, Some(*v.value()))))
+        }
+        Object::Base(BaseValue::Boolean(v)) => {
+            Some(Expression::Boolean(BooleanExp::new(*v.value())))
+        }
+        _ => None,
+    }
+}
+
+fn is_literal(exp: &Expression) -> bool {
+    matches!(exp, Expression::Num(_) | Expression::Boolean(_))
+}
+
+// Get the truthiness of the literal condition, it's the same as the `Object::is_truthy`.
+fn literal_truthy(exp: &Expression) -> Option<bool> {
+    match exp {
+        Expression::Boolean(b) => Some(b.value()),
+        Expression::Num(num) => match num.integer_value() {
+            Some(v) => Some(v != 0),
+            None => num.float_value().map(|v| v != 0.0),
+        },
+        _ => None,
+    }
+}
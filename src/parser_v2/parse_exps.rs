@@ -1,7 +1,7 @@
 use crate::ast_v2::{
     expressions::{
-        ArrayExp, ArrayIndexExp, BooleanExp, CallExp, ElseExp, IdentifierExp, IfExp, InfixExp,
-        NumExp, PrefixExp, StringExp,
+        ArrayExp, ArrayIndexExp, BooleanExp, ByteExp, CallExp, CharExp, ElseExp, IfExp, InfixExp,
+        InterpolatedExp, NumExp, PrefixExp, StringExp,
     },
     statements::BlockStatement,
     Expression,
@@ -24,7 +24,11 @@ impl Parser {
         self.register_prefix(TokenType::True, Parser::parse_boolean);
         self.register_prefix(TokenType::False, Parser::parse_boolean);
         self.register_prefix(TokenType::String, Parser::parse_string);
+        self.register_prefix(TokenType::Char, Parser::parse_char);
+        self.register_prefix(TokenType::Byte, Parser::parse_byte);
+        self.register_prefix(TokenType::StringHead, Parser::parse_interpolated_string);
         self.register_prefix(TokenType::If, Parser::parse_if_expression);
+        self.register_prefix(TokenType::Illegal, Parser::parse_illegal);
 
         // Register the infix parsing functions.
         self.register_infix(TokenType::Plus, Parser::parse_infix_exp);
@@ -58,18 +62,15 @@ impl Parser {
             }
         };
 
-        Some(Expression::Prefix(PrefixExp::new(
-            cur_token.token_type().clone(),
-            right,
-        )))
+        Some(Expression::Prefix(
+            PrefixExp::new(cur_token.token_type().clone(), right)
+                .with_position(cur_token.file_row_number(), cur_token.column()),
+        ))
     }
 
     // This method is used to parse the identifier expression.
     pub(super) fn parse_identifier(&self) -> Option<Expression> {
-        let literal = self.get_cur_token().literal().to_string();
-        let ident = IdentifierExp::new(literal);
-
-        Some(Expression::Identifier(ident))
+        Some(Expression::Identifier(self.cur_ident()))
     }
 
     // This method is used to parse the number expression.
@@ -78,19 +79,19 @@ impl Parser {
         let literal = cur_token.literal().to_string();
 
         // Parse the number expression by checking the token type.
-        let num_exp: NumExp = match cur_token.token_type() {
-            TokenType::IntegerNum => {
-                let i_value = literal.parse::<i64>().unwrap();
-                NumExp::new(Some(i_value), None)
-            }
-            TokenType::FloatNum => {
-                let f_value = literal.parse::<f64>().unwrap();
-                NumExp::new(None, Some(f_value))
-            }
+        let num_exp = match cur_token.token_type() {
+            TokenType::IntegerNum => parse_integer(&literal).map(|v| NumExp::new(Some(v), None)),
+            TokenType::FloatNum => parse_float(&literal).map(|v| NumExp::new(None, Some(v))),
             _ => panic!("This is not a number token."),
         };
 
-        Some(Expression::Num(num_exp))
+        match num_exp {
+            Ok(num_exp) => Some(Expression::Num(num_exp)),
+            Err(msg) => {
+                self.store_error(&format!("{} at line {}", msg, cur_token.file_row_number()));
+                None
+            }
+        }
     }
 
     // This method is used to parse the grouped expression: (5 + 5).
@@ -181,10 +182,7 @@ impl Parser {
             return None;
         }
 
-        Some(Expression::ArrIndex(ArrayIndexExp::new(
-            arr_name,
-            index.unwrap(),
-        )))
+        Some(Expression::ArrIndex(ArrayIndexExp::new(arr_name, index?)))
     }
 
     // This method is used to parse the boolean expression.
@@ -207,7 +205,63 @@ impl Parser {
         Some(Expression::Str(StringExp::new(value)))
     }
 
+    // This method is used to parse the char expression, the lexer has checked that it's a single char.
+    fn parse_char(&self) -> Option<Expression> {
+        let cur_token = self.get_cur_token();
+        let value = cur_token.literal().chars().next()?;
+
+        Some(Expression::Char(CharExp::new(value)))
+    }
+
+    // This method is used to parse the byte expression, the lexer has checked that it's an ASCII char.
+    fn parse_byte(&self) -> Option<Expression> {
+        let cur_token = self.get_cur_token();
+        let value = cur_token.literal().chars().next()?;
+
+        Some(Expression::Byte(ByteExp::new(value as u8)))
+    }
+
+    // This method is used to parse the interpolated string expression.
+    // The lexer stores the embedded expressions between the segments of the string:
+    // StringHead, expression, (StringMiddle, expression)*, StringTail
+    fn parse_interpolated_string(&self) -> Option<Expression> {
+        let mut parts = Vec::new();
+        let mut segment = self.get_cur_token();
+
+        loop {
+            // The empty segments are omitted.
+            if !segment.literal().is_empty() {
+                parts.push(Expression::Str(StringExp::new(
+                    segment.literal().to_string(),
+                )));
+            }
+            if segment.token_type() == &TokenType::StringTail {
+                break;
+            }
+
+            self.next_token();
+            parts.push(self.parse_expression(LEVEL_0)?);
+
+            if self.peek_tok_is(&TokenType::StringMiddle) {
+                self.next_token();
+            } else if !self.expect_peek(&TokenType::StringTail) {
+                return None;
+            }
+            segment = self.get_cur_token();
+        }
+
+        Some(Expression::Interpolated(InterpolatedExp::new(parts)))
+    }
+
+    // The illegal token carries the error found by the lexer, such as an unterminated string.
+    // The illegal token has been reported by the lexer.
+    fn parse_illegal(&self) -> Option<Expression> {
+        self.mark_error(self.get_cur_token().literal());
+        None
+    }
+
     fn parse_if_expression(&self) -> Option<Expression> {
+        let if_token = self.get_cur_token();
         if !self.expect_peek(&TokenType::LeftParen) {
             self.store_error("There is no left parenthesis after the if keyword.");
             return None;
@@ -279,7 +333,10 @@ impl Parser {
             None
         };
 
-        Some(Expression::If(IfExp::new(condition, consequence, else_exp)))
+        Some(Expression::If(
+            IfExp::new(condition, consequence, else_exp)
+                .with_position(if_token.file_row_number(), if_token.column()),
+        ))
     }
 
     // ==================== Infix Parsing Functions ====================
@@ -299,11 +356,10 @@ impl Parser {
             }
         };
 
-        Some(Expression::Infix(InfixExp::new(
-            left,
-            cur_token.token_type().clone(),
-            right,
-        )))
+        Some(Expression::Infix(
+            InfixExp::new(left, cur_token.token_type().clone(), right)
+                .with_position(cur_token.file_row_number(), cur_token.column()),
+        ))
     }
 
     /// This method is used to parse the call expression.
@@ -314,10 +370,13 @@ impl Parser {
     /// add(5, 5);
     /// ```
     fn parse_call_exp(&self, left: Expression) -> Option<Expression> {
+        let line = self.get_cur_token().file_row_number();
         let arguments = self.parse_call_arguments();
 
         match left {
-            Expression::Identifier(ident) => Some(Expression::Call(CallExp::new(ident, arguments))),
+            Expression::Identifier(ident) => {
+                Some(Expression::Call(CallExp::new(ident, arguments, line)))
+            }
             _ => {
                 self.store_error("The left expression is not an identifier.");
                 return None;
@@ -340,12 +399,12 @@ impl Parser {
         }
 
         self.next_token();
-        args.push(self.parse_expression(LEVEL_0).unwrap());
+        args.extend(self.parse_expression(LEVEL_0));
 
         while self.peek_tok_is(&TokenType::Comma) {
             self.next_token();
             self.next_token();
-            args.push(self.parse_expression(LEVEL_0).unwrap());
+            args.extend(self.parse_expression(LEVEL_0));
         }
 
         if !self.expect_peek(&TokenType::RightParen) {
@@ -355,3 +414,101 @@ impl Parser {
         args
     }
 }
+
+// Parse the integer literal: 1_000, 0xff, 0o17, 0b1010, -3.
+// The underscores are separators, and the prefix decides the radix of the digits.
+fn parse_integer(literal: &str) -> Result<i64, String> {
+    let (sign, unsigned) = match literal.strip_prefix('-') {
+        Some(unsigned) => ("-", unsigned),
+        None => ("", literal),
+    };
+    let (radix, digits) = match unsigned.get(..2) {
+        Some("0x") | Some("0X") => (16, &unsigned[2..]),
+        Some("0o") | Some("0O") => (8, &unsigned[2..]),
+        Some("0b") | Some("0B") => (2, &unsigned[2..]),
+        _ => (10, unsigned),
+    };
+
+    if let Some(pos) = digits.find(|c: char| c != '_' && !c.is_digit(radix)) {
+        let rest = &digits[pos..];
+        return match rest.chars().next() {
+            Some(c) if c.is_ascii_digit() => Err(format!(
+                "invalid digit `{}` for a base {} literal `{}`",
+                c, radix, literal
+            )),
+            _ => Err(format!(
+                "invalid suffix `{}` for the number literal `{}`",
+                rest, literal
+            )),
+        };
+    }
+
+    let digits: String = digits.chars().filter(|c| *c != '_').collect();
+    if digits.is_empty() {
+        return Err(format!(
+            "missing digits in the number literal `{}`",
+            literal
+        ));
+    }
+
+    i64::from_str_radix(&format!("{}{}", sign, digits), radix)
+        .map_err(|_| format!("the integer literal `{}` is out of range", literal))
+}
+
+// Parse the float literal: 1.5, .5, 1_000.0, 1e-9, 2.5E+3.
+fn parse_float(literal: &str) -> Result<f64, String> {
+    let text: String = literal.chars().filter(|c| *c != '_').collect();
+    let chars: Vec<char> = text.chars().collect();
+    let count_digits = |from: usize| {
+        chars[from..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .count()
+    };
+
+    let mut index = usize::from(chars.first() == Some(&'-'));
+
+    // The integer part and the fraction part.
+    let mut mantissa = count_digits(index);
+    index += mantissa;
+    if chars.get(index) == Some(&'.') {
+        let fraction = count_digits(index + 1);
+        mantissa += fraction;
+        index += fraction + 1;
+    }
+    if mantissa == 0 {
+        return Err(format!(
+            "missing digits in the number literal `{}`",
+            literal
+        ));
+    }
+
+    // The exponent part.
+    if matches!(chars.get(index), Some('e') | Some('E')) {
+        index += 1;
+        if matches!(chars.get(index), Some('+') | Some('-')) {
+            index += 1;
+        }
+        let exponent = count_digits(index);
+        if exponent == 0 {
+            return Err(format!(
+                "missing digits in the exponent of the float literal `{}`",
+                literal
+            ));
+        }
+        index += exponent;
+    }
+
+    if index < chars.len() {
+        let rest: String = chars[index..].iter().collect();
+        return Err(format!(
+            "invalid suffix `{}` for the number literal `{}`",
+            rest, literal
+        ));
+    }
+
+    match text.parse::<f64>() {
+        Ok(value) if value.is_finite() => Ok(value),
+        _ => Err(format!("the float literal `{}` is out of range", literal)),
+    }
+}
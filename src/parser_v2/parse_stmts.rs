@@ -1,7 +1,11 @@
 use crate::{
     ast_v2::{
         expressions::IdentifierExp,
-        statements::{BlockStatement, FuncStatement, LetStatement, ReturnStatement},
+        statements::{
+            BlockStatement, FuncStatement, LetStatement, ReturnStatement, ThrowStatement,
+            TryStatement,
+        },
+        types::Type,
     },
 };
 use crate::token::precedence::LEVEL_0;
@@ -11,14 +15,15 @@ use super::parser::Parser;
 
 impl Parser {
     pub(super) fn parse_let_stmt(&self) -> Option<LetStatement> {
+        let doc = self.cur_doc();
+
         if !self.expect_peek(&TokenType::Ident) {
             return None;
         }
 
-        // Move to Ident token
-        let name = self.get_cur_token().literal().to_string();
-        // Create IdentifierExp
-        let ident_exp = IdentifierExp::new(name);
+        // Move to Ident token and create IdentifierExp
+        let ident_exp = self.cur_ident();
+        let annotation = self.parse_annotation(&TokenType::Colon)?;
 
         // Move to Assign token
         if !self.expect_peek(&TokenType::Assignment) {
@@ -37,10 +42,15 @@ impl Parser {
             ));
         }
 
-        Some(LetStatement::new(ident_exp, exp))
+        Some(
+            LetStatement::new(ident_exp, exp)
+                .with_annotation(annotation)
+                .with_doc(doc),
+        )
     }
 
     pub(super) fn parse_return_stmt(&self) -> Option<ReturnStatement> {
+        let return_token = self.get_cur_token();
         // Move to the next token
         self.next_token();
         // Parse the expression
@@ -52,7 +62,75 @@ impl Parser {
             ));
         }
 
-        Some(ReturnStatement::new(exp))
+        Some(
+            ReturnStatement::new(exp)
+                .with_position(return_token.file_row_number(), return_token.column()),
+        )
+    }
+
+    pub(super) fn parse_throw_stmt(&self) -> Option<ThrowStatement> {
+        let throw_token = self.get_cur_token();
+        // Move to the next token
+        self.next_token();
+        // Parse the expression, a throw statement always has a value.
+        let exp = self.parse_expression(LEVEL_0)?;
+        if !self.expect_peek(&TokenType::Semicolon) {
+            self.store_error(&format!(
+                "Expected next token to be Semicolon, got {:?} instead.",
+                self.get_cur_token().token_type()
+            ));
+        }
+
+        Some(
+            ThrowStatement::new(exp)
+                .with_position(throw_token.file_row_number(), throw_token.column()),
+        )
+    }
+
+    /// Parse the try statement, it needs a catch block, a finally block or both of them.
+    /// For example:
+    ///
+    /// ```text
+    /// try { risky(); } catch (e) { log(e); } finally { close(); }
+    /// ```
+    pub(super) fn parse_try_stmt(&self) -> Option<TryStatement> {
+        // Move to LeftBrace token
+        if !self.expect_peek(&TokenType::LeftBrace) {
+            return None;
+        }
+        let body = self.parse_block_stmt()?;
+
+        let mut catch = None;
+        if self.peek_tok_is(&TokenType::Catch) {
+            // Move to Catch token
+            self.next_token();
+            if !self.expect_peek(&TokenType::LeftParen) || !self.expect_peek(&TokenType::Ident) {
+                return None;
+            }
+            let ident = self.cur_ident();
+            if !self.expect_peek(&TokenType::RightParen) || !self.expect_peek(&TokenType::LeftBrace)
+            {
+                return None;
+            }
+            catch = Some((ident, self.parse_block_stmt()?));
+        }
+
+        let mut finally = None;
+        if self.peek_tok_is(&TokenType::Finally) {
+            // Move to Finally token
+            self.next_token();
+            if !self.expect_peek(&TokenType::LeftBrace) {
+                return None;
+            }
+            finally = Some(self.parse_block_stmt()?);
+        }
+
+        if catch.is_none() && finally.is_none() {
+            self.store_error("expected `catch` or `finally` after the `try` block");
+            return None;
+        }
+
+        Some(TryStatement::new(body, catch, finally))
     }
 
     /// Parse the block statement.
@@ -72,11 +150,9 @@ impl Parser {
         self.next_token();
 
         while !self.cur_tok_is(&TokenType::RightBrace) && !self.cur_tok_is(&TokenType::Eof) {
-            if let Some(stmt) = self.parse_code() {
+            if let Some(stmt) = self.parse_node() {
                 block.add(Box::new(stmt));
             }
-
-            self.next_token();
         }
 
         Some(block)
@@ -90,22 +166,26 @@ impl Parser {
     /// func add(x, y) { return x + y; }
     /// ```
     pub(super) fn parse_func_stmt(&self) -> Option<FuncStatement> {
+        let doc = self.cur_doc();
+
         if !self.expect_peek(&TokenType::Ident) {
             return None;
         }
 
-        // Move to Ident token
-        let name = self.get_cur_token().literal().to_string();
-        // Create IdentifierExp
-        let ident_exp = IdentifierExp::new(name);
+        // Move to Ident token and create IdentifierExp
+        let ident_exp = self.cur_ident();
 
         // Move to LeftParen token
         if !self.expect_peek(&TokenType::LeftParen) {
             return None;
         }
 
-        // Parse the function parameters
-        let params = self.parse_func_parameters();
+        // Parse the function parameters and the return type
+        let (params, param_types): (Vec<_>, Vec<_>) =
+            self.parse_func_parameters()?.into_iter().unzip();
+        // The function without parameters has no parameter list.
+        let params = (!params.is_empty()).then_some(params);
+        let return_type = self.parse_annotation(&TokenType::Arrow)?;
 
         // Move to LeftBrace token
         if !self.expect_peek(&TokenType::LeftBrace) {
@@ -121,30 +201,32 @@ impl Parser {
             }
         };
 
-        Some(FuncStatement::new(ident_exp, params, block))
+        Some(
+            FuncStatement::new(ident_exp, params, block)
+                .with_types(param_types, return_type)
+                .with_doc(doc),
+        )
     }
 
-    /// Parse the function parameters.
+    /// Parse the function parameters and their type annotations.
     /// For example:
     ///
     /// ```
-    /// func add(x, y) { return x + y; }
+    /// func add(x: int, y) { return x + y; }
     /// ```
-    fn parse_func_parameters(&self) -> Option<Vec<IdentifierExp>> {
+    fn parse_func_parameters(&self) -> Option<Vec<(IdentifierExp, Option<Type>)>> {
         let mut params = Vec::new();
 
         // Check if there are no parameters
         if self.peek_tok_is(&TokenType::RightParen) {
             // Move to RightParen token
             self.next_token();
-            return None;
+            return Some(params);
         }
 
         // Move to the next token
         self.next_token();
-        let literal = self.get_cur_token().literal().to_string();
-        let ident = IdentifierExp::new(literal);
-        params.push(ident);
+        params.push((self.cur_ident(), self.parse_annotation(&TokenType::Colon)?));
 
         while self.peek_tok_is(&TokenType::Comma) {
             // Skip Comma token
@@ -153,9 +235,7 @@ impl Parser {
             // Move to next parameter
             self.next_token();
 
-            let literal = self.get_cur_token().literal().to_string();
-            let ident = IdentifierExp::new(literal);
-            params.push(ident);
+            params.push((self.cur_ident(), self.parse_annotation(&TokenType::Colon)?));
         }
 
         if !self.expect_peek(&TokenType::RightParen) {
@@ -164,4 +244,47 @@ impl Parser {
 
         Some(params)
     }
+
+    /// Parse the type annotation if the next token is the separator, `:` is before the type of
+    /// a variable or parameter, and `->` is before the return type of a function.
+    /// The outer `None` is an error, and the inner `None` means there is no annotation.
+    fn parse_annotation(&self, separator: &TokenType) -> Option<Option<Type>> {
+        if !self.peek_tok_is(separator) {
+            return Some(None);
+        }
+        // Skip the separator and move to the type.
+        self.next_token();
+        self.next_token();
+        self.parse_type().map(Some)
+    }
+
+    /// Parse the type at the current token.
+    /// For example: int, [str], [[float]]
+    fn parse_type(&self) -> Option<Type> {
+        let token = self.get_cur_token();
+        match token.token_type() {
+            TokenType::Ident => match Type::from_name(token.literal()) {
+                Some(ty) => Some(ty),
+                None => {
+                    self.store_error(&format!("unknown type `{}`", token.literal()));
+                    None
+                }
+            },
+            TokenType::LeftBracket => {
+                self.next_token();
+                let element = self.parse_type()?;
+                if !self.expect_peek(&TokenType::RightBracket) {
+                    return None;
+                }
+                Some(Type::Array(Box::new(element)))
+            }
+            _ => {
+                self.store_error(&format!(
+                    "expected a type, got `{:?}` instead",
+                    token.token_type()
+                ));
+                None
+            }
+        }
+    }
 }
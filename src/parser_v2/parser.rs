@@ -2,7 +2,10 @@ use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::rc::Rc;
 
+use crate::ast_v2::expressions::IdentifierExp;
+use crate::ast_v2::statements::ErrorStatement;
 use crate::ast_v2::{Expression, Node, Statement};
+use crate::lexer::error::LexError;
 use crate::lexer::lexer::{Lexer, TokensIter};
 use crate::token::precedence::*;
 use crate::token::token::Token;
@@ -30,21 +33,52 @@ pub struct Parser {
     // Collect errors that occur during parsing.
     errors: RefCell<Vec<String>>,
 
+    // Whether an error is found in the current statement, and the first error of it.
+    // The errors after the first one are not stored until the parser skips to the next statement,
+    // so a mistake is reported only once.
+    panicking: Cell<bool>,
+    panic_msg: RefCell<String>,
+
+    // The end offset of the token before the current token.
+    prev_end: Cell<usize>,
+    // The number of the `{` before the current token that are not closed yet.
+    brace_depth: Cell<usize>,
+
+    // The doc comments before the current and peek tokens.
+    cur_docs: RefCell<Vec<String>>,
+    peek_docs: RefCell<Vec<String>>,
+
     // The prefix and infix parsing functions.
     prefix_parse_fns: RefCell<HashMap<TokenType, PrefixParseFn>>,
     infix_parse_fns: RefCell<HashMap<TokenType, InfixParseFn>>,
 }
 
 impl Parser {
-    pub fn new(path: &str) -> Self {
+    /// Create a parser that parses the source file, it fails if the file can't be read.
+    pub fn new(path: &str) -> Result<Self, String> {
+        Ok(Self::from_lexer(Lexer::new(path)?))
+    }
+
+    /// Create a parser that parses the source code in memory.
+    pub fn from_source(source: &str) -> Self {
+        Self::from_lexer(Lexer::from_source(source))
+    }
+
+    fn from_lexer(lexer: Lexer) -> Self {
         let parser = Parser {
-            tokens: Lexer::new(path).tokens_iter(),
+            tokens: lexer.tokens_iter(),
             cur_token: RefCell::new(Rc::new(Token::new(TokenType::Illegal, "", "", 0, 0))),
             peek_token: RefCell::new(Rc::new(Token::new(TokenType::Illegal, "", "", 0, 0))),
             cmd_start_index: Cell::new(0),
             cmd_cur_index: Cell::new(0),
             programs: RefCell::new(Vec::new()),
             errors: RefCell::new(Vec::new()),
+            panicking: Cell::new(false),
+            panic_msg: RefCell::new(String::new()),
+            prev_end: Cell::new(0),
+            brace_depth: Cell::new(0),
+            cur_docs: RefCell::new(Vec::new()),
+            peek_docs: RefCell::new(Vec::new()),
             prefix_parse_fns: RefCell::new(HashMap::new()),
             infix_parse_fns: RefCell::new(HashMap::new()),
         };
@@ -58,13 +92,6 @@ impl Parser {
 
         parser.parse();
 
-        // Check if there are any errors during parsing.
-        if parser.errors.borrow().len() > 0 {
-            for error in parser.errors.borrow().iter() {
-                println!("{}", error);
-            }
-        }
-
         parser
     }
 
@@ -80,6 +107,19 @@ impl Parser {
         self.infix_parse_fns.borrow_mut().insert(token_type, func);
     }
 
+    /// Get the errors found while parsing, the code with an error is skipped in the AST.
+    /// The errors found by the lexer come first.
+    pub fn errors(&self) -> Vec<String> {
+        let mut errors: Vec<String> = self.lex_errors().iter().map(|e| e.to_string()).collect();
+        errors.extend(self.errors.borrow().iter().cloned());
+        errors
+    }
+
+    /// Get the errors found by the lexer, they have the positions in the source code.
+    pub fn lex_errors(&self) -> &[LexError] {
+        self.tokens.errors()
+    }
+
     /// Get AST from the parser.
     pub fn programs(self) -> Vec<Node> {
         self.programs.into_inner()
@@ -88,12 +128,98 @@ impl Parser {
     // This method is used to build the AST.
     fn parse(&self) {
         while !self.cur_token.borrow().is_eof() {
-            match self.parse_code() {
-                Some(node) => {
-                    self.programs.borrow_mut().push(node);
+            if let Some(node) = self.parse_node() {
+                self.programs.borrow_mut().push(node);
+            }
+        }
+    }
+
+    // Parse a statement or an expression and move to the token after it.
+    // The code with an error becomes an error statement, and the parser skips to the next statement.
+    pub(super) fn parse_node(&self) -> Option<Node> {
+        // The statement in a broken statement, such as the block of a broken if expression,
+        // is skipped with the outer statement.
+        if self.panicking.get() {
+            let node = self.parse_code();
+            self.next_token();
+            return node;
+        }
+
+        let start = self.get_cur_token();
+        let depth = self.brace_depth.get();
+        let node = self.parse_code();
+        if !self.panicking.get() {
+            self.next_token();
+            return node;
+        }
+
+        self.synchronize(&start, depth);
+        self.panicking.set(false);
+
+        let source = self.tokens.woc_file().source();
+        let text = &source[start.offset()..self.prev_end.get().max(start.offset())];
+        Some(Node::Stmt(Statement::Error(ErrorStatement::new(
+            &self.panic_msg.take(),
+            text.trim_end(),
+            start.file_row_number(),
+            start.column(),
+        ))))
+    }
+
+    // Skip the tokens to the start of the next statement, it's after a `;`, or at a statement keyword,
+    // the `}` of the block or the end of the file. The blocks that are opened in the broken statement
+    // are skipped with it, `depth` is the number of the open blocks where the statement starts.
+    // The `else`, `catch` and `finally` blocks after them belong to the broken statement too.
+    fn synchronize(&self, start: &Rc<Token>, depth: usize) {
+        // The statement fails at its first token, it's skipped so it won't be parsed again.
+        if Rc::ptr_eq(start, &self.get_cur_token()) {
+            self.next_token();
+        }
+
+        loop {
+            let nested = self.brace_depth.get() > depth;
+            match self.get_cur_token().token_type() {
+                TokenType::Eof => return,
+                // The `}` closes the block that contains the statement.
+                TokenType::RightBrace if !nested => return,
+                // The `}` closes the last block opened in the statement, which ends with it.
+                TokenType::RightBrace => {
+                    self.next_token();
+                    if self.brace_depth.get() > depth {
+                        continue;
+                    }
+                    match self.get_cur_token().token_type() {
+                        // The `if` of the `else if` doesn't start a new statement.
+                        TokenType::Else if self.peek_tok_is(&TokenType::If) => {
+                            self.next_token();
+                            self.next_token();
+                        }
+                        TokenType::Else | TokenType::Catch | TokenType::Finally => {
+                            self.next_token()
+                        }
+                        _ => return,
+                    }
+                }
+                TokenType::Semicolon => {
                     self.next_token();
+                    if !nested {
+                        return;
+                    }
                 }
-                None => self.next_token(),
+                _ if nested => self.next_token(),
+                TokenType::Let
+                | TokenType::Return
+                | TokenType::Func
+                | TokenType::If
+                | TokenType::While
+                | TokenType::For
+                | TokenType::Break
+                | TokenType::Continue
+                | TokenType::Throw
+                | TokenType::Try
+                | TokenType::Struct
+                | TokenType::Enum => return,
+                _ => self.next_token(),
             }
         }
     }
@@ -101,7 +227,12 @@ impl Parser {
     pub(super) fn parse_code(&self) -> Option<Node> {
         let cur_tok = self.get_cur_token();
         match cur_tok.token_type() {
-            TokenType::Let | TokenType::Return | TokenType::LeftBrace | TokenType::Func => {
+            TokenType::Let
+            | TokenType::Return
+            | TokenType::LeftBrace
+            | TokenType::Func
+            | TokenType::Throw
+            | TokenType::Try => {
                 return match self.parse_stmt() {
                     Some(stmt) => Some(stmt),
                     None => None,
@@ -113,11 +244,15 @@ impl Parser {
             | TokenType::True
             | TokenType::False
             | TokenType::String
+            | TokenType::Char
+            | TokenType::Byte
+            | TokenType::StringHead
             | TokenType::Not
             | TokenType::Minus
             | TokenType::LeftParen
             | TokenType::LeftBracket
-            | TokenType::If => match self.parse_expression(LEVEL_0) {
+            | TokenType::If
+            | TokenType::Illegal => match self.parse_expression(LEVEL_0) {
                 Some(exp) => {
                     let node = Node::Exp(exp);
                     return Some(node);
@@ -139,6 +274,13 @@ impl Parser {
         self.cur_token.borrow().clone()
     }
 
+    // Create the identifier of the current token with its position.
+    pub(super) fn cur_ident(&self) -> IdentifierExp {
+        let token = self.get_cur_token();
+        IdentifierExp::new(token.literal().to_string())
+            .with_position(token.file_row_number(), token.column())
+    }
+
     fn parse_stmt(&self) -> Option<Node> {
         match self.get_cur_token().token_type() {
             TokenType::Let => match self.parse_let_stmt() {
@@ -169,6 +311,20 @@ impl Parser {
                 }
                 None => None,
             },
+            TokenType::Throw => match self.parse_throw_stmt() {
+                Some(throw_stmt) => {
+                    let node = Node::Stmt(Statement::Throw(throw_stmt));
+                    Some(node)
+                }
+                None => None,
+            },
+            TokenType::Try => match self.parse_try_stmt() {
+                Some(try_stmt) => {
+                    let node = Node::Stmt(Statement::Try(try_stmt));
+                    Some(node)
+                }
+                None => None,
+            },
             _ => {
                 self.store_error("There is no such statement that starts with this token.");
                 None
@@ -223,7 +379,8 @@ impl Parser {
             }
             // Move to infix operator token and parse the infix expression.
             self.next_token();
-            left = infix_func.unwrap()(self, left.unwrap());
+            // The error of the left expression has been stored.
+            left = infix_func.unwrap()(self, left?);
         }
 
         left
@@ -255,6 +412,10 @@ impl Parser {
         if self.peek_tok_is(token_type) {
             self.next_token();
             true
+        } else if self.peek_tok_is(&TokenType::Illegal) {
+            // The illegal token has been reported by the lexer.
+            self.mark_error(self.peek_token.borrow().literal());
+            false
         } else {
             let msg = format!(
                 "expected next token to be `{:?}`, got `{:?}` instead",
@@ -269,11 +430,26 @@ impl Parser {
     // This method is used to parse the let statement.
     // It gets error code by calling [`lexer.joint_tokens_to_str_by_range()`] with the start and end indexes.
     pub(super) fn store_error(&self, msg: &str) {
+        // Only the first error of the statement is stored.
+        if self.panicking.get() {
+            return;
+        }
+
         // Update the start index of the next command.
         self.cmd_start_index.set(self.cmd_cur_index.get());
 
         // Store the error message.
         self.errors.borrow_mut().push(format!("get error: {}", msg));
+        self.mark_error(msg);
+    }
+
+    // Mark the current statement as broken without storing the error, it's used for the error
+    // that has been reported by the lexer.
+    pub(super) fn mark_error(&self, msg: &str) {
+        if !self.panicking.get() {
+            self.panicking.set(true);
+            *self.panic_msg.borrow_mut() = msg.to_string();
+        }
     }
 
     /// Update the [`cur_token`] and [`peek_token`].
@@ -283,13 +459,38 @@ impl Parser {
         if (*self.cur_token.borrow()).is_eof() {
             return;
         }
+        self.prev_end.set(self.cur_token.borrow().end());
+        let depth = self.brace_depth.get();
+        match self.cur_token.borrow().token_type() {
+            TokenType::LeftBrace => self.brace_depth.set(depth + 1),
+            TokenType::RightBrace => self.brace_depth.set(depth.saturating_sub(1)),
+            _ => {}
+        }
         *self.cur_token.borrow_mut() = self.peek_token.borrow().clone();
+        *self.cur_docs.borrow_mut() = self.peek_docs.take();
 
         // If the peek token already is EOF, it means the current token is the last token,
         // and haven't more tokens to read.
         if (*self.peek_token.borrow()).is_eof() {
             return;
         }
-        *self.peek_token.borrow_mut() = self.tokens.next().unwrap();
+
+        // The doc comments are not parsed, they're attached to the token after them.
+        let mut token = self.tokens.next().unwrap();
+        while token.token_type() == &TokenType::DocComment {
+            self.peek_docs.borrow_mut().push(token.literal().to_string());
+            token = self.tokens.next().unwrap();
+        }
+        *self.peek_token.borrow_mut() = token;
+    }
+
+    /// Get the doc comments before the current token, the lines are joined by '\n'.
+    pub(super) fn cur_doc(&self) -> Option<String> {
+        let docs = self.cur_docs.borrow();
+        if docs.is_empty() {
+            None
+        } else {
+            Some(docs.join("\n"))
+        }
     }
 }
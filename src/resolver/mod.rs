@@ -0,0 +1,3 @@
+// The resolver does the static scope analysis of the AST before it's evaluated.
+// - resolver: It resolves every identifier to the slot of the variable, and reports the scope errors.
+pub mod resolver;
@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+
+use crate::ast_v2::expressions::{
+    ArrayExp, ArrayIndexExp, CallExp, ElseExp, IdentifierExp, IfExp, InfixExp, InterpolatedExp,
+    PrefixExp,
+};
+use crate::ast_v2::statements::{
+    BlockStatement, FuncStatement, LetStatement, ReturnStatement, ThrowStatement, TryStatement,
+};
+use crate::ast_v2::{Expression, Node, Statement};
+use crate::builtins::builtins::BUILTINS;
+
+// The variables of a function or the top-level code, a block does not create a new scope.
+struct FuncScope {
+    // The slot indices of the variables that have been defined so far.
+    slots: HashMap<String, usize>,
+    num_slots: usize,
+    // The names that are not defined when they're used, they're checked when the scope ends.
+    missing: Vec<String>,
+}
+
+impl FuncScope {
+    fn new() -> Self {
+        Self {
+            slots: HashMap::new(),
+            num_slots: 0,
+            missing: Vec::new(),
+        }
+    }
+
+    fn define(&mut self, name: &str) -> usize {
+        if let Some(slot) = self.slots.get(name) {
+            return *slot;
+        }
+
+        let slot = self.num_slots;
+        self.slots.insert(name.to_string(), slot);
+        self.num_slots += 1;
+        slot
+    }
+}
+
+/// The resolver does the static scope analysis before the programs are evaluated.
+/// Every identifier is resolved to the (depth, slot) of the variable, which is used by the
+/// [`crate::evaluator_v2::evaluator::Evaluator`] to access the variable without looking up its name.
+///
+/// The scope rules are the same as the evaluator:
+/// - The top-level code and every function have their own scope, a block does not create a new scope.
+/// - The caught error of a `catch` is only visible in the catch block.
+/// - A function can only see the variables of the enclosing scopes that are defined before the function.
+/// - In a function, the parameters take the first slots, and the function itself takes the next one.
+/// - The builtin functions are defined in a scope that encloses the top-level code, so they can be shadowed.
+///
+/// The following errors are reported:
+/// - Undefined variables.
+/// - Duplicate parameters of a function.
+/// - Variables that are used before their definitions.
+pub struct Resolver {
+    // The stack of the scopes, the first one is the builtin functions and the second one is the top-level code.
+    scopes: Vec<FuncScope>,
+    errors: Vec<String>,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Self {
+            scopes: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Resolve the programs, all the errors found are returned.
+    pub fn resolve(mut self, programs: &[Node]) -> Result<Vec<Node>, Vec<String>> {
+        let mut builtins = FuncScope::new();
+        for builtin in BUILTINS {
+            builtins.define(builtin.name());
+        }
+        self.scopes.push(builtins);
+
+        self.scopes.push(FuncScope::new());
+        let programs = programs
+            .iter()
+            .map(|node| self.resolve_node(node))
+            .collect();
+        self.end_scope();
+        self.end_scope();
+
+        if self.errors.is_empty() {
+            Ok(programs)
+        } else {
+            Err(self.errors)
+        }
+    }
+
+    fn resolve_node(&mut self, node: &Node) -> Node {
+        match node {
+            Node::Exp(exp) => Node::Exp(self.resolve_exp(exp)),
+            Node::Stmt(stmt) => Node::Stmt(self.resolve_stmt(stmt)),
+        }
+    }
+
+    // =================== Resolve Statement ===================
+
+    fn resolve_stmt(&mut self, stmt: &Statement) -> Statement {
+        match stmt {
+            Statement::Let(let_stmt) => {
+                // The value is resolved first, so `let x = x;` uses the `x` defined before.
+                let value = let_stmt.value().map(|v| self.resolve_exp(v));
+                let ident = self.define(let_stmt.ident());
+                let doc = let_stmt.doc().map(|d| d.to_string());
+                Statement::Let(
+                    LetStatement::new(ident, value)
+                        .with_annotation(let_stmt.annotation().cloned())
+                        .with_doc(doc),
+                )
+            }
+            Statement::Return(ret_stmt) => Statement::Return(ReturnStatement::new(
+                ret_stmt.value().map(|v| self.resolve_exp(v)),
+            )),
+            Statement::Block(block_stmt) => Statement::Block(self.resolve_block(block_stmt)),
+            Statement::Func(func_stmt) => Statement::Func(self.resolve_func(func_stmt)),
+            Statement::Throw(throw_stmt) => Statement::Throw(ThrowStatement::new(
+                self.resolve_exp(throw_stmt.value()),
+            )),
+            Statement::Try(try_stmt) => {
+                let body = self.resolve_block(try_stmt.body());
+                // The caught error is only visible in the catch block, it takes a new slot of the
+                // function, so the variable of the same name outside the block is kept.
+                let catch = try_stmt.catch().map(|(ident, block)| {
+                    let name = ident.value();
+                    let outer = self.scopes.last_mut().unwrap().slots.remove(name);
+                    let ident = self.define(ident);
+                    let block = self.resolve_block(block);
+
+                    let slots = &mut self.scopes.last_mut().unwrap().slots;
+                    match outer {
+                        Some(slot) => slots.insert(name.to_string(), slot),
+                        None => slots.remove(name),
+                    };
+                    (ident, block)
+                });
+                let finally = try_stmt.finally().map(|block| self.resolve_block(block));
+                Statement::Try(TryStatement::new(body, catch, finally))
+            }
+            Statement::Error(error_stmt) => Statement::Error(error_stmt.clone()),
+        }
+    }
+
+    fn resolve_block(&mut self, block: &BlockStatement) -> BlockStatement {
+        let mut result = BlockStatement::new();
+        if let Some(stmts) = block.statements() {
+            for stmt in stmts {
+                result.add(Box::new(self.resolve_node(stmt)));
+            }
+        }
+        result
+    }
+
+    fn resolve_func(&mut self, func_stmt: &FuncStatement) -> FuncStatement {
+        let name = func_stmt.name();
+        let mut scope = FuncScope::new();
+
+        let params = func_stmt.params().map(|params| {
+            params
+                .iter()
+                .map(|param| {
+                    if scope.slots.contains_key(param.value()) {
+                        self.errors.push(format!(
+                            "duplicate parameter `{}` in function `{}`",
+                            param.value(),
+                            name
+                        ));
+                    }
+                    // The duplicate parameter still takes a slot, so the arguments are set in order.
+                    let slot = scope.num_slots;
+                    scope.slots.insert(param.value().to_string(), slot);
+                    scope.num_slots += 1;
+                    IdentifierExp::resolved(param.value().to_string(), 0, slot)
+                })
+                .collect()
+        });
+
+        // The function itself is set after the parameters when it's called.
+        let slot = scope.num_slots;
+        scope.slots.insert(name.to_string(), slot);
+        scope.num_slots += 1;
+
+        self.scopes.push(scope);
+        let body = self.resolve_block(func_stmt.body());
+        self.end_scope();
+
+        // The function is defined after its body, it can't be seen by itself in the enclosing scope.
+        let ident = self.define(func_stmt.ident());
+        let doc = func_stmt.doc().map(|d| d.to_string());
+        FuncStatement::new(ident, params, body)
+            .with_types(
+                func_stmt.param_types().to_vec(),
+                func_stmt.return_type().cloned(),
+            )
+            .with_doc(doc)
+    }
+
+    // =================== Resolve Expression ===================
+
+    fn resolve_exp(&mut self, exp: &Expression) -> Expression {
+        match exp {
+            Expression::Identifier(ident) => Expression::Identifier(self.resolve_ident(ident)),
+            Expression::Num(_)
+            | Expression::Boolean(_)
+            | Expression::Str(_)
+            | Expression::Char(_)
+            | Expression::Byte(_) => exp.clone(),
+            Expression::Interpolated(interp_exp) => Expression::Interpolated(InterpolatedExp::new(
+                interp_exp
+                    .parts()
+                    .iter()
+                    .map(|p| self.resolve_exp(p))
+                    .collect(),
+            )),
+            Expression::Arr(arr_exp) => Expression::Arr(ArrayExp::new(
+                arr_exp
+                    .elements()
+                    .iter()
+                    .map(|e| self.resolve_exp(e))
+                    .collect(),
+            )),
+            Expression::ArrIndex(arr_index) => {
+                let name = self.resolve_ident(arr_index.name());
+                let index = self.resolve_exp(arr_index.index());
+                Expression::ArrIndex(ArrayIndexExp::new(name, index))
+            }
+            Expression::If(if_exp) => Expression::If(self.resolve_if(if_exp)),
+            Expression::Call(call_exp) => {
+                let name = self.resolve_ident(call_exp.name());
+                let arguments = call_exp
+                    .arguments()
+                    .iter()
+                    .map(|a| self.resolve_exp(a))
+                    .collect();
+                Expression::Call(CallExp::new(name, arguments, call_exp.line()))
+            }
+            Expression::Prefix(pre_exp) => Expression::Prefix(PrefixExp::new(
+                pre_exp.operator().clone(),
+                self.resolve_exp(pre_exp.right()),
+            )),
+            Expression::Infix(infix_exp) => {
+                let left = self.resolve_exp(infix_exp.left());
+                let right = self.resolve_exp(infix_exp.right());
+                Expression::Infix(InfixExp::new(left, infix_exp.operator().clone(), right))
+            }
+        }
+    }
+
+    fn resolve_if(&mut self, if_exp: &IfExp) -> IfExp {
+        let condition = self.resolve_exp(if_exp.condition());
+        let consequence = self.resolve_block(if_exp.consequence());
+        let else_exp = if_exp
+            .else_exp()
+            .map(|else_exp| self.resolve_else(else_exp));
+        IfExp::new(condition, consequence, else_exp)
+    }
+
+    fn resolve_else(&mut self, else_exp: &ElseExp) -> ElseExp {
+        match else_exp.if_exp() {
+            Some(if_exp) => ElseExp::new(Some(Box::new(self.resolve_if(if_exp))), None),
+            None => ElseExp::new(
+                None,
+                else_exp
+                    .consequence()
+                    .map(|block| self.resolve_block(block)),
+            ),
+        }
+    }
+
+    // =================== Helper Functions ===================
+
+    // Define the variable in the current scope, the position is kept for the compiler
+    // to tell the definitions of the same name apart.
+    fn define(&mut self, ident: &IdentifierExp) -> IdentifierExp {
+        let name = ident.value();
+        let slot = self.scopes.last_mut().unwrap().define(name);
+        let resolved = IdentifierExp::resolved(name.to_string(), 0, slot);
+        match ident.position() {
+            Some((line, column)) => resolved.with_position(line, column),
+            None => resolved,
+        }
+    }
+
+    // Find the variable from the current scope to the top-level scope.
+    fn resolve_ident(&mut self, ident: &IdentifierExp) -> IdentifierExp {
+        let name = ident.value();
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if let Some(slot) = scope.slots.get(name) {
+                // The position is kept for the call stack of the evaluator.
+                let resolved = IdentifierExp::resolved(name.to_string(), depth, *slot);
+                return match ident.position() {
+                    Some((line, column)) => resolved.with_position(line, column),
+                    None => resolved,
+                };
+            }
+        }
+
+        // It may be defined later, which is checked when the scope ends.
+        let scope = self.scopes.last_mut().unwrap();
+        if !scope.missing.iter().any(|n| n == name) {
+            scope.missing.push(name.to_string());
+        }
+        ident.clone()
+    }
+
+    // End the current scope, the missing names that are defined later in this scope are used before
+    // their definitions, the others are passed to the enclosing scope.
+    fn end_scope(&mut self) {
+        let scope = self.scopes.pop().unwrap();
+
+        for name in scope.missing {
+            if scope.slots.contains_key(&name) {
+                self.errors
+                    .push(format!("variable `{}` is used before its definition", name));
+                continue;
+            }
+
+            match self.scopes.last_mut() {
+                Some(enclosing) => {
+                    if !enclosing.missing.contains(&name) {
+                        enclosing.missing.push(name);
+                    }
+                }
+                None => self.errors.push(format!("undefined variable `{}`", name)),
+            }
+        }
+    }
+}
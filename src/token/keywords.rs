@@ -0,0 +1,32 @@
+use super::types::TokenType;
+
+/// The keywords of the language, an identifier is a keyword only if it's exactly the same.
+/// A new keyword only needs a token type and an entry here.
+pub const KEYWORDS: &[(&str, TokenType)] = &[
+    ("while", TokenType::While),
+    ("for", TokenType::For),
+    ("if", TokenType::If),
+    ("else", TokenType::Else),
+    ("break", TokenType::Break),
+    ("continue", TokenType::Continue),
+    ("let", TokenType::Let),
+    ("func", TokenType::Func),
+    ("return", TokenType::Return),
+    ("struct", TokenType::Struct),
+    ("enum", TokenType::Enum),
+    ("throw", TokenType::Throw),
+    ("try", TokenType::Try),
+    ("catch", TokenType::Catch),
+    ("finally", TokenType::Finally),
+    ("none", TokenType::None),
+    ("true", TokenType::True),
+    ("false", TokenType::False),
+];
+
+/// Get the token type of the keyword, or `None` if the identifier is not a keyword.
+pub fn lookup(ident: &str) -> Option<TokenType> {
+    KEYWORDS
+        .iter()
+        .find(|(keyword, _)| *keyword == ident)
+        .map(|(_, token_type)| token_type.clone())
+}
@@ -1,3 +1,4 @@
+pub mod keywords;
 pub mod precedence;
 pub mod token;
 pub mod types;
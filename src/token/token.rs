@@ -1,3 +1,5 @@
+use std::fmt::{self, Display, Formatter};
+
 use crate::token::precedence::*;
 use crate::token::types::TokenType;
 
@@ -11,8 +13,16 @@ pub struct Token {
     // It records the line number of the token.
     file_row_number: usize,
 
-    // This field recodes the column of the token in a line.
-    token_number_in_line: usize,
+    // It records the column of the first char of the token in the line, it's counted in chars.
+    column: usize,
+
+    // The byte range of the token in the source code,
+    // the literal of a string may be different from its source text.
+    offset: usize,
+    end: usize,
+
+    // The source text and the trivia around it, it's only recorded by the lossless lexer.
+    trivia: Option<Box<Trivia>>,
 
     token_type: TokenType,
     literal: String,
@@ -20,16 +30,32 @@ pub struct Token {
 
 impl Token {
     /// Creates a new [`Token`].
-    pub fn new(token_type: TokenType, literal: &str, file_path: &str, line: usize, tok_num: usize) -> Token {
+    pub fn new(token_type: TokenType, literal: &str, file_path: &str, line: usize, column: usize) -> Token {
         Token {
             file_path: file_path.to_string(),
             file_row_number: line,
-            token_number_in_line: tok_num,
+            column,
+            offset: 0,
+            end: 0,
+            trivia: None,
             token_type,
             literal: literal.to_string(),
         }
     }
 
+    /// Set the byte range `[offset, end)` of the token in the source code.
+    pub fn with_span(mut self, offset: usize, end: usize) -> Token {
+        self.offset = offset;
+        self.end = end;
+        self
+    }
+
+    /// Attach the source text of the token and the trivia around it.
+    pub fn with_trivia(mut self, trivia: Trivia) -> Token {
+        self.trivia = Some(Box::new(trivia));
+        self
+    }
+
     pub fn is_eof(&self) -> bool {
         self.token_type == TokenType::Eof
     }
@@ -54,8 +80,20 @@ impl Token {
         self.file_row_number
     }
 
-    pub fn token_number_in_line(&self) -> usize {
-        self.token_number_in_line
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    pub fn trivia(&self) -> Option<&Trivia> {
+        self.trivia.as_deref()
     }
 
     pub fn precedence(&self) -> u32 {
@@ -86,3 +124,43 @@ impl Token {
         }
     }
 }
+
+/// The trivia are the whitespace, line breaks and comments that are not a part of any token.
+/// The trailing trivia of a token are the ones up to the end of its line, including the line break,
+/// and the others are the leading trivia of the next token.
+/// So the concatenated text of all the tokens with their trivia is exactly the source file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Trivia {
+    leading: String,
+    text: String,
+    trailing: String,
+}
+
+impl Trivia {
+    pub fn new(leading: &str, text: &str, trailing: &str) -> Self {
+        Self {
+            leading: leading.to_string(),
+            text: text.to_string(),
+            trailing: trailing.to_string(),
+        }
+    }
+
+    pub fn leading(&self) -> &str {
+        &self.leading
+    }
+
+    /// The source text of the token, the literal of a string may be different from it.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn trailing(&self) -> &str {
+        &self.trailing
+    }
+}
+
+impl Display for Trivia {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}{}{}", self.leading, self.text, self.trailing)
+    }
+}
@@ -7,6 +7,7 @@ pub enum TokenType {
     Dot,          // .
     Semicolon,    // ;
     Colon,        // :
+    Arrow,        // ->
     Assignment,   // =
     LeftParen,    // (
     RightParen,   // )
@@ -46,9 +47,17 @@ pub enum TokenType {
 
     // Data Types
     Ident,      // Identifier
-    IntegerNum, // integer number: 1, 2, -3, 1_000 etc.
-    FloatNum,   // float number: 1.0, 2.0, -3.0, 1_000.0 etc.
+    IntegerNum, // integer number: 1, 2, -3, 1_000, 0xff, 0o17, 0b1010 etc.
+    FloatNum,   // float number: 1.0, 2.0, -3.0, 1_000.0, .5, 1e-9 etc.
     String,     // string: "hello world"
+    Char,       // char: 'a', '\n', '\u{1F600}'
+    Byte,       // byte: b'a', b'\n'
+
+    // Interpolated string: "Hello, ${name}! You have ${count} items"
+    // The embedded expressions are lexed between the segments of the string.
+    StringHead,   // the segment before the first embedded expression: "Hello, ${
+    StringMiddle, // the segment between two embedded expressions: }! You have ${
+    StringTail,   // the segment after the last embedded expression: } items"
 
     // Syntax Keywords
     While,    // while
@@ -62,10 +71,17 @@ pub enum TokenType {
     Return,   // return
     Struct,   // struct
     Enum,     // enum
+    Throw,    // throw
+    Try,      // try
+    Catch,    // catch
+    Finally,  // finally
     None,     // null: None
     True,     // true: True
     False,    // false: False
 
+    // Trivia
+    DocComment, // doc comment: /// It's a doc comment.
+
     // Illegal
     Illegal,
 
@@ -80,6 +96,7 @@ impl Display for TokenType {
             TokenType::Dot => ".",
             TokenType::Semicolon => ";",
             TokenType::Colon => ":",
+            TokenType::Arrow => "->",
             TokenType::Assignment => "=",
             TokenType::LeftParen => "(",
             TokenType::RightParen => ")",
@@ -114,6 +131,11 @@ impl Display for TokenType {
             TokenType::IntegerNum => "Integer",
             TokenType::FloatNum => "Float",
             TokenType::String => "String",
+            TokenType::Char => "Char",
+            TokenType::Byte => "Byte",
+            TokenType::StringHead => "StringHead",
+            TokenType::StringMiddle => "StringMiddle",
+            TokenType::StringTail => "StringTail",
             TokenType::While => "while",
             TokenType::For => "for",
             TokenType::If => "if",
@@ -125,9 +147,14 @@ impl Display for TokenType {
             TokenType::Return => "return",
             TokenType::Struct => "struct",
             TokenType::Enum => "enum",
+            TokenType::Throw => "throw",
+            TokenType::Try => "try",
+            TokenType::Catch => "catch",
+            TokenType::Finally => "finally",
             TokenType::None => "None",
             TokenType::True => "True",
             TokenType::False => "False",
+            TokenType::DocComment => "DocComment",
             TokenType::Illegal => "Illegal",
             TokenType::Eof => "EOF",
         };
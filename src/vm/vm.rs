@@ -0,0 +1,483 @@
+use crate::builtins::builtins::BUILTINS;
+use crate::compiler::compiler::Bytecode;
+use crate::compiler::opcode::{Capture, OpCode};
+use crate::evaluator_v2::call_stack::DEFAULT_MAX_DEPTH;
+use crate::object::object::{Array, BaseValue, CallFrame, Closure, Error, Object, Str, Value};
+
+// A call frame of the function that is running.
+struct Frame {
+    closure: Closure,
+    // The index of the next instruction.
+    ip: usize,
+    // The index of the first local variable in the stack.
+    base: usize,
+}
+
+// The handler of the errors installed by `Try`, it's removed by `EndTry`.
+struct Handler {
+    // The number of frames when it's installed, the frames above them are unwound.
+    frames: usize,
+    // The length of the stack when it's installed.
+    stack: usize,
+    // The index of the instruction that handles the error.
+    target: usize,
+}
+
+/// The stack-based virtual machine that executes the bytecode generated by the compiler.
+/// The call frames are kept in a vector instead of the Rust stack,
+/// so the deep recursion of the script does not overflow the stack of the host.
+pub struct Vm {
+    bytecode: Bytecode,
+    globals: Vec<Object>,
+    stack: Vec<Object>,
+    frames: Vec<Frame>,
+    handlers: Vec<Handler>,
+    max_depth: usize,
+}
+
+impl Vm {
+    pub fn new(bytecode: Bytecode) -> Self {
+        let globals = vec![Object::Null; bytecode.num_globals() as usize];
+        let main = Closure::new(bytecode.main().clone(), Vec::new());
+
+        Self {
+            bytecode,
+            globals,
+            stack: Vec::new(),
+            frames: vec![Frame {
+                closure: main,
+                ip: 0,
+                base: 0,
+            }],
+            handlers: Vec::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+
+    /// Set the maximum depth of the nested function calls, a deeper call raises a "stack overflow"
+    /// error, the same as the evaluator. The default is [`DEFAULT_MAX_DEPTH`].
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Run the program and return the value of the last top-level statement.
+    /// The error that is not caught by `try` stops the program with its kind and message.
+    pub fn run(&mut self) -> Result<Object, String> {
+        loop {
+            match self.execute() {
+                Ok(obj) => return Ok(obj),
+                Err(err) => self.unwind(err)?,
+            }
+        }
+    }
+
+    // Execute the instructions until the program stops or an error is thrown.
+    fn execute(&mut self) -> Result<Object, Error> {
+        loop {
+            let frame = self.frames.last_mut().unwrap();
+            let op = match frame.closure.func().instructions().get(frame.ip) {
+                Some(op) => *op,
+                None => return Err("instruction pointer is out of range".to_string().into()),
+            };
+            frame.ip += 1;
+
+            match op {
+                // ===== Constants =====
+                OpCode::Constant(index) => {
+                    let obj = self.bytecode.constants().get(index as usize).cloned();
+                    self.push(obj.ok_or(format!("constant {} is not found", index))?);
+                }
+                OpCode::Null => self.push(Object::Null),
+                OpCode::True => self.push(Self::boolean(true)),
+                OpCode::False => self.push(Self::boolean(false)),
+                OpCode::Pop => {
+                    self.pop()?;
+                }
+
+                // ===== Variables =====
+                OpCode::GetGlobal(slot) => {
+                    let obj = self.globals.get(slot as usize).cloned();
+                    self.push(obj.ok_or(format!("global {} is not found", slot))?);
+                }
+                OpCode::SetGlobal(slot) => {
+                    let obj = self.pop()?;
+                    match self.globals.get_mut(slot as usize) {
+                        Some(global) => *global = obj,
+                        None => return Err(format!("global {} is not found", slot).into()),
+                    }
+                }
+                OpCode::GetLocal(slot) => {
+                    let index = self.frames.last().unwrap().base + slot as usize;
+                    let obj = self.stack.get(index).cloned();
+                    self.push(obj.ok_or(format!("local {} is not found", slot))?);
+                }
+                OpCode::SetLocal(slot) => {
+                    let obj = self.pop()?;
+                    let index = self.frames.last().unwrap().base + slot as usize;
+                    match self.stack.get_mut(index) {
+                        Some(local) => *local = obj,
+                        None => return Err(format!("local {} is not found", slot).into()),
+                    }
+                }
+                OpCode::GetCapture(index) => {
+                    let closure = &self.frames.last().unwrap().closure;
+                    let obj = closure.captures().get(index as usize).cloned();
+                    self.push(obj.ok_or(format!("capture {} is not found", index))?);
+                }
+                OpCode::CurrentClosure => {
+                    let closure = self.frames.last().unwrap().closure.clone();
+                    self.push(Object::Closure(closure));
+                }
+                OpCode::GetBuiltin(index) => match BUILTINS.get(index as usize) {
+                    Some(builtin) => self.push(Object::Builtin(*builtin)),
+                    None => return Err(format!("undefined builtin function {}", index).into()),
+                },
+
+                // ===== Array =====
+                OpCode::Array(len) => {
+                    let len = len as usize;
+                    if len > self.stack.len() {
+                        return Err("stack underflow".to_string().into());
+                    }
+                    let elements = self.stack.split_off(self.stack.len() - len);
+                    self.push(Object::Array(Array::new(elements)));
+                }
+                OpCode::Index => {
+                    let index = self.pop()?;
+                    let arr = self.pop()?;
+                    self.push(Self::index(&arr, &index));
+                }
+
+                // ===== String =====
+                OpCode::Concat(len) => {
+                    let len = len as usize;
+                    if len > self.stack.len() {
+                        return Err("stack underflow".to_string().into());
+                    }
+                    let value: String = self
+                        .stack
+                        .split_off(self.stack.len() - len)
+                        .iter()
+                        .map(|obj| obj.to_string())
+                        .collect();
+                    self.push(Object::Str(Str::new(value)));
+                }
+
+                // ===== Calculation =====
+                OpCode::Add
+                | OpCode::Sub
+                | OpCode::Mul
+                | OpCode::Div
+                | OpCode::Equal
+                | OpCode::NotEqual
+                | OpCode::Less
+                | OpCode::LessEqual
+                | OpCode::Greater
+                | OpCode::GreaterEqual => {
+                    let right = self.pop()?;
+                    let left = self.pop()?;
+                    let obj = Self::binary_op(op, &left, &right)?;
+                    self.push(obj);
+                }
+                OpCode::Not => {
+                    let obj = self.pop()?;
+                    self.push(Self::boolean(!obj.is_truthy()));
+                }
+                OpCode::Neg => {
+                    let obj = match self.pop()? {
+                        Object::Base(BaseValue::Integer(v)) => {
+                            Self::integer(v.value().checked_neg())?
+                        }
+                        Object::Base(BaseValue::Float(v)) => {
+                            Object::Base(BaseValue::Float(Value::new(-v.value())))
+                        }
+                        _ => Object::Null,
+                    };
+                    self.push(obj);
+                }
+
+                // ===== Control flow =====
+                OpCode::Jump(target) => self.frames.last_mut().unwrap().ip = target as usize,
+                OpCode::JumpIfFalse(target) => {
+                    let condition = self.pop()?;
+                    if !condition.is_truthy() {
+                        self.frames.last_mut().unwrap().ip = target as usize;
+                    }
+                }
+                // The operand that decides the result of `&&` or `||` is kept as the result.
+                OpCode::JumpIfFalseOrPop(target) | OpCode::JumpIfTrueOrPop(target) => {
+                    let truthy = match self.stack.last() {
+                        Some(obj) => obj.is_truthy(),
+                        None => return Err("stack underflow".to_string().into()),
+                    };
+                    if truthy == matches!(op, OpCode::JumpIfTrueOrPop(_)) {
+                        self.frames.last_mut().unwrap().ip = target as usize;
+                    } else {
+                        self.pop()?;
+                    }
+                }
+                OpCode::Call(argc) => self.call(argc as usize)?,
+                OpCode::Return => self.ret()?,
+                OpCode::TailCall(argc) => self.tail_call(argc as usize)?,
+                OpCode::Closure(index) => {
+                    let closure = self.make_closure(index)?;
+                    self.push(Object::Closure(closure));
+                }
+                OpCode::Halt => return Ok(self.stack.pop().unwrap_or(Object::Null)),
+                OpCode::Exit => return Ok(Object::Return(Box::new(self.pop()?))),
+
+                // ===== Errors =====
+                OpCode::Try(target) => self.handlers.push(Handler {
+                    frames: self.frames.len(),
+                    stack: self.stack.len(),
+                    target: target as usize,
+                }),
+                OpCode::EndTry => {
+                    self.handlers.pop();
+                }
+                OpCode::Throw => return Err(Error::thrown(self.pop()?)),
+            }
+        }
+    }
+
+    // Unwind the frames to the innermost handler, and jump to it with the error on the stack.
+    // The functions that are unwound are recorded in the trace of the error.
+    fn unwind(&mut self, mut err: Error) -> Result<(), String> {
+        let handler = self.handlers.pop();
+        let depth = handler.as_ref().map_or(1, |h| h.frames);
+        while self.frames.len() > depth {
+            let frame = self.frames.pop().unwrap();
+            // The call site is the instruction before the return address of the caller,
+            // only its line is kept in the bytecode.
+            let caller = self.frames.last().unwrap();
+            let line = caller.closure.func().line_of(caller.ip.saturating_sub(1));
+            err.push_trace(CallFrame::at_line(
+                frame.closure.func().name(),
+                line.map(|line| line as usize),
+            ));
+        }
+
+        let Some(handler) = handler else {
+            return Err(err.report());
+        };
+        self.stack.truncate(handler.stack);
+        self.push(Object::Error(err));
+        self.frames.last_mut().unwrap().ip = handler.target;
+        Ok(())
+    }
+
+    fn push(&mut self, obj: Object) {
+        self.stack.push(obj);
+    }
+
+    fn pop(&mut self) -> Result<Object, String> {
+        self.stack.pop().ok_or("stack underflow".to_string())
+    }
+
+    // Call the function that is below the arguments in the stack.
+    // Calling a non-function value or calling with the wrong number of arguments results in null.
+    fn call(&mut self, argc: usize) -> Result<(), Error> {
+        if argc + 1 > self.stack.len() {
+            return Err("stack underflow".to_string().into());
+        }
+
+        let base = self.stack.len() - argc;
+        let closure = match &self.stack[base - 1] {
+            Object::Closure(c) if c.func().arity() == argc => c.clone(),
+            // The builtin function is called directly, it doesn't need a frame.
+            // The error of the builtin function is thrown, the same as the evaluator.
+            Object::Builtin(b) => {
+                let result = b.call(&self.stack[base..]);
+                if let Object::Throw(err) = result {
+                    return Err(err);
+                }
+                self.stack.truncate(base - 1);
+                self.push(result);
+                return Ok(());
+            }
+            _ => {
+                self.stack.truncate(base - 1);
+                self.push(Object::Null);
+                return Ok(());
+            }
+        };
+
+        // The frame of the top-level program is not a function call.
+        if self.frames.len() > self.max_depth {
+            return Err(Error::new(
+                "RuntimeError",
+                format!("stack overflow, the maximum call depth is {}", self.max_depth),
+            ));
+        }
+
+        // Reserve the slots of the local variables that are not parameters.
+        let num_locals = closure.func().num_locals() as usize;
+        self.stack.resize(base + num_locals.max(argc), Object::Null);
+
+        self.frames.push(Frame {
+            closure,
+            ip: 0,
+            base,
+        });
+
+        Ok(())
+    }
+
+    // Return the top value of the stack from the current frame to the caller.
+    fn ret(&mut self) -> Result<(), Error> {
+        let obj = self.pop()?;
+        let frame = self.frames.pop().unwrap();
+        if self.frames.is_empty() {
+            return Err("return from the top-level code".to_string().into());
+        }
+
+        // Remove the locals and the function itself from the stack.
+        self.stack.truncate(frame.base - 1);
+        self.push(obj);
+        Ok(())
+    }
+
+    // Call the function in place of the current frame, the function and the arguments are moved
+    // down to the slots of the current function, so the tail recursion runs in one frame.
+    // The other values are called as usual, and their results are returned.
+    fn tail_call(&mut self, argc: usize) -> Result<(), Error> {
+        if argc + 1 > self.stack.len() {
+            return Err("stack underflow".to_string().into());
+        }
+
+        let base = self.stack.len() - argc;
+        let closure = match &self.stack[base - 1] {
+            Object::Closure(c) if c.func().arity() == argc => c.clone(),
+            _ => {
+                self.call(argc)?;
+                return self.ret();
+            }
+        };
+
+        let frame = self.frames.last_mut().unwrap();
+        self.stack.drain(frame.base - 1..base - 1);
+
+        let num_locals = closure.func().num_locals() as usize;
+        self.stack.resize(frame.base + num_locals.max(argc), Object::Null);
+        frame.closure = closure;
+        frame.ip = 0;
+
+        Ok(())
+    }
+
+    fn make_closure(&self, index: u32) -> Result<Closure, String> {
+        let func = match self.bytecode.functions().get(index as usize) {
+            Some(f) => f.clone(),
+            None => return Err(format!("function {} is not found", index)),
+        };
+
+        let frame = self.frames.last().unwrap();
+        let mut captures = Vec::with_capacity(func.captures().len());
+        for capture in func.captures() {
+            let obj = match capture {
+                Capture::Local(slot) => self.stack.get(frame.base + *slot as usize).cloned(),
+                Capture::Outer(i) => frame.closure.captures().get(*i as usize).cloned(),
+                Capture::Current => Some(Object::Closure(frame.closure.clone())),
+            };
+            captures.push(obj.ok_or("captured variable is not found".to_string())?);
+        }
+
+        Ok(Closure::new(func, captures))
+    }
+
+    fn index(arr: &Object, index: &Object) -> Object {
+        match (arr, index) {
+            (Object::Array(a), Object::Base(BaseValue::Integer(i))) => {
+                match a.get(*i.value() as usize) {
+                    Some(obj) => obj.clone(),
+                    None => Object::Null,
+                }
+            }
+            _ => Object::Null,
+        }
+    }
+
+    // The semantics of the binary operators are the same as the `Evaluator::eval_infix_exp`.
+    fn binary_op(op: OpCode, left: &Object, right: &Object) -> Result<Object, String> {
+        use BaseValue::{Float, Integer};
+
+        // All the values are compared by the equality and ordering of `Object`.
+        if matches!(
+            op,
+            OpCode::Equal
+                | OpCode::NotEqual
+                | OpCode::Less
+                | OpCode::LessEqual
+                | OpCode::Greater
+                | OpCode::GreaterEqual
+        ) {
+            return Ok(Self::compare(op, left, right));
+        }
+
+        let obj = match (left, right) {
+            (Object::Base(Integer(l)), Object::Base(Integer(r))) => {
+                let (l, r) = (*l.value(), *r.value());
+                match op {
+                    OpCode::Add => Self::integer(l.checked_add(r))?,
+                    OpCode::Sub => Self::integer(l.checked_sub(r))?,
+                    OpCode::Mul => Self::integer(l.checked_mul(r))?,
+                    OpCode::Div => {
+                        if r == 0 {
+                            return Err("division by zero".to_string());
+                        }
+                        Self::integer(l.checked_div(r))?
+                    }
+                    _ => Object::Null,
+                }
+            }
+            (Object::Base(Float(l)), Object::Base(Float(r))) => {
+                Self::float_op(op, *l.value(), *r.value())
+            }
+            (Object::Base(Integer(l)), Object::Base(Float(r))) => {
+                Self::float_op(op, *l.value() as f64, *r.value())
+            }
+            (Object::Base(Float(l)), Object::Base(Integer(r))) => {
+                Self::float_op(op, *l.value(), *r.value() as f64)
+            }
+            // The arithmetic on the values that don't support it results in null.
+            _ => Object::Null,
+        };
+
+        Ok(obj)
+    }
+
+    fn float_op(op: OpCode, l: f64, r: f64) -> Object {
+        match op {
+            OpCode::Add => Object::Base(BaseValue::Float(Value::new(l + r))),
+            OpCode::Sub => Object::Base(BaseValue::Float(Value::new(l - r))),
+            OpCode::Mul => Object::Base(BaseValue::Float(Value::new(l * r))),
+            OpCode::Div => Object::Base(BaseValue::Float(Value::new(l / r))),
+            _ => Object::Null,
+        }
+    }
+
+    fn compare<T: PartialOrd>(op: OpCode, l: T, r: T) -> Object {
+        let result = match op {
+            OpCode::Equal => l == r,
+            OpCode::NotEqual => l != r,
+            OpCode::Less => l < r,
+            OpCode::LessEqual => l <= r,
+            OpCode::Greater => l > r,
+            OpCode::GreaterEqual => l >= r,
+            _ => false,
+        };
+        Self::boolean(result)
+    }
+
+    fn integer(value: Option<i64>) -> Result<Object, String> {
+        match value {
+            Some(v) => Ok(Object::Base(BaseValue::Integer(Value::new(v)))),
+            None => Err("integer overflow".to_string()),
+        }
+    }
+
+    fn boolean(value: bool) -> Object {
+        Object::Base(BaseValue::Boolean(Value::new(value)))
+    }
+}
@@ -0,0 +1,309 @@
+#[cfg(test)]
+mod checker_test {
+    use woc_lang::checker::checker::TypeChecker;
+    use woc_lang::parser_v2::parser::Parser;
+
+    // Check the source code, the result is the (line, column, message) of every error.
+    fn check(source: &str) -> Vec<(usize, usize, String)> {
+        let parser = Parser::from_source(source);
+        assert!(parser.errors().is_empty(), "{:?}", parser.errors());
+        match TypeChecker::new().check(&parser.programs()) {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .iter()
+                .map(|e| (e.line(), e.column(), e.message().to_string()))
+                .collect(),
+        }
+    }
+
+    fn messages(source: &str) -> Vec<String> {
+        check(source).into_iter().map(|(_, _, m)| m).collect()
+    }
+
+    #[test]
+    fn test_untyped_code() {
+        let source = "
+func add(a, b) { return a + b; }
+let x = add(1, 2.5) * 2;
+let arr = [x, 1];
+if (arr[0] > 1 && true) { return str(x); }
+";
+        assert_eq!(check(source), vec![]);
+    }
+
+    #[test]
+    fn test_let_annotations() {
+        let source = "
+let a: int = 1;
+let b: float = a;
+let c: str = a;
+let d: [int] = [1, 2];
+let e: [float] = [1, 2.5];
+let f: [str] = [1, 2];
+let g: char = int('c');
+";
+        assert_eq!(
+            check(source),
+            vec![
+                (
+                    4,
+                    5,
+                    "variable `c` is `str`, but the value is `int`".to_string()
+                ),
+                (
+                    7,
+                    5,
+                    "variable `f` is `[str]`, but the value is `[int]`".to_string()
+                ),
+                (
+                    8,
+                    5,
+                    "variable `g` is `char`, but the value is `int`".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inferred_types() {
+        // The type of a variable without annotation is inferred from its value.
+        let source = "
+let a = 1;
+let b = a + 1.5;
+let s: str = b;
+let arr = [\"x\"];
+let t: int = arr[0];
+let u: [int] = [1, \"x\"];
+let v: [[float]] = [[1, 2.5], [true]];
+let w: [int] = [1, 2];
+let mixed = [1, \"x\"];
+func f() -> [str] { return [\"a\", 'b']; }
+";
+        assert_eq!(
+            messages(source),
+            vec![
+                "variable `s` is `str`, but the value is `float`",
+                "variable `t` is `int`, but the value is `str`",
+                "variable `u` is `[int]`, but the value is `[str]`",
+                "variable `v` is `[[float]]`, but the value is `[[bool]]`",
+                "the function returns `[str]`, but the value is `[char]`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_infix_operands() {
+        let source = "
+let a = 1 + 2.5 - 3 * 4 / 5;
+let b = 'a' < 'b' == true;
+let c = \"a\" + 1;
+let d = 'a' < 1;
+let e: int = 1 && 2;
+let f = 1 && true || 2.5;
+let g: bool = 0 || 1;
+";
+        assert_eq!(
+            check(source),
+            vec![
+                (4, 13, "`+` can't be applied to `str` and `int`".to_string()),
+                (
+                    5,
+                    13,
+                    "`<` can't be applied to `char` and `int`".to_string()
+                ),
+                (
+                    8,
+                    5,
+                    "variable `g` is `bool`, but the value is `int`".to_string()
+                ),
+            ]
+        );
+
+        // The strings and arrays are ordered, only the values of compatible types are ordered,
+        // and every value can be compared with `==`.
+        assert_eq!(
+            messages("let a = \"a\" < \"b\"; let b = [1] == [2.5]; let c = \"a\" == 1; let d = [1] < [\"s\"];"),
+            vec!["`<` can't be applied to `[int]` and `[str]`"]
+        );
+        assert!(
+            messages("let a = true == 1; let b = 'a' != \"a\"; let c = [1] == 1.5;").is_empty()
+        );
+
+        // The negation of any value is a boolean.
+        assert_eq!(
+            messages("let a = -\"s\"; let b: bool = !'c'; let c: bool = ![1];"),
+            vec!["`-` can't be applied to `str`"]
+        );
+    }
+
+    #[test]
+    fn test_calls() {
+        let source = "
+func add(a: int, b: int) -> int { return a + b; }
+let x: int = add(1, 2);
+add(1);
+add(1, \"2\");
+let y = 1;
+y(2);
+func f(g) { return g(1, 2, 3); }
+let z: str = f(add);
+";
+        assert_eq!(
+            check(source),
+            vec![
+                (
+                    4,
+                    1,
+                    "function `add` takes 2 argument(s), but 1 were given".to_string()
+                ),
+                (
+                    5,
+                    1,
+                    "argument 2 of function `add` must be `int`, but it's `str`".to_string()
+                ),
+                (7, 1, "`y` is `int`, it can't be called".to_string()),
+            ]
+        );
+
+        // The builtin conversions return the converted types.
+        assert_eq!(
+            messages("let a: str = int(\"1\"); let b: int = str(1, 2);"),
+            vec![
+                "variable `a` is `str`, but the value is `int`",
+                "function `str` takes 1 argument(s), but 2 were given",
+                "variable `b` is `int`, but the value is `str`",
+            ]
+        );
+
+        assert_eq!(
+            messages("let a: int = float(1); let b: bool = is_int(a); let c: str = type(b);"),
+            vec!["variable `a` is `int`, but the value is `float`"]
+        );
+
+        // The caught error is `any`, and its fields have the types of the error builtins.
+        assert_eq!(
+            messages(
+                "try { throw error(1, \"m\"); } catch (e) { let k: int = error_kind(e); let t: [str] = error_trace(e); }"
+            ),
+            vec![
+                "argument 1 of function `error` must be `str`, but it's `int`",
+                "variable `k` is `int`, but the value is `str`",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_return_types() {
+        let source = "
+func name(n: int) -> str {
+    if (n > 0) {
+        return \"positive\";
+    }
+    return n;
+}
+func count() -> int {
+    return \"many\";
+}
+func fact(n: int) -> int {
+    if (n < 2) { return 1; }
+    return n * fact(n - 1);
+}
+let s: str = fact(3);
+return 1;
+";
+        assert_eq!(
+            check(source),
+            vec![
+                (
+                    6,
+                    5,
+                    "the function returns `str`, but the value is `int`".to_string()
+                ),
+                (
+                    9,
+                    5,
+                    "the function returns `int`, but the value is `str`".to_string()
+                ),
+                (
+                    15,
+                    5,
+                    "variable `s` is `str`, but the value is `int`".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_return() {
+        let source = "
+func f(a: int) -> int {
+    if (a > 0) { return 1; }
+}
+func g(a: int) -> int {
+    if (a > 0) { return 1; } else if (a < 0) { return -1; } else { 0; }
+}
+func h(a: int) -> str {
+    try { return str(a); } catch (e) { throw e; }
+}
+func add(a: int, b: int) -> int { a + b; }
+func untyped(a: int) { if (a > 0) { return 1; } }
+func last(a: int) -> float {
+    let b = a * 2.5;
+}
+";
+        assert_eq!(
+            check(source),
+            vec![
+                (
+                    2,
+                    6,
+                    "function `f` returns `int`, but not all paths return a value".to_string()
+                ),
+                (
+                    13,
+                    6,
+                    "function `last` returns `float`, but not all paths return a value".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_conditions_and_indexes() {
+        let source = "
+let arr: [int] = [1, 2];
+let s = \"str\";
+if (s) { arr; } else if (arr[\"0\"]) { arr; }
+let x = s[0];
+";
+        assert_eq!(
+            check(source),
+            vec![
+                (
+                    4,
+                    1,
+                    "the condition must be `bool`, `int` or `float`, but it's `str`".to_string()
+                ),
+                (4, 26, "the index must be `int`, but it's `str`".to_string()),
+                (
+                    5,
+                    9,
+                    "`s` is `str`, only an array can be indexed".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scopes() {
+        // The parameters are only in the function, and a name can be defined again with another type.
+        let source = "
+let x = \"s\";
+func f(x: int) -> int { return x + 1; }
+let y: str = x;
+let x = 1;
+let z: int = x;
+";
+        assert_eq!(check(source), vec![]);
+    }
+}
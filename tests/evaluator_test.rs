@@ -1,9 +1,13 @@
 #[cfg(test)]
 mod evaluator_test {
     use woc_lang::{
-        evaluator_v2::evaluator::Evaluator,
-        object::object::{Array, BaseValue, Object, Str, Value},
+        evaluator_v2::{
+            call_stack::{DEFAULT_MAX_DEPTH, STACK_PER_CALL},
+            evaluator::Evaluator,
+        },
+        object::object::{Array, BaseValue, Error, Object, Str, Value},
         parser_v2::parser::Parser,
+        resolver::resolver::Resolver,
     };
 
     #[test]
@@ -59,6 +63,242 @@ mod evaluator_test {
         }
     }
 
+    #[test]
+    fn test_eval_interpolated_str() {
+        let tests = vec![
+            (
+                "let name = \"woc\"; let n = 2; \"Hello, ${name}! You have ${n + 1} items\";",
+                "Hello, woc! You have 3 items",
+            ),
+            (
+                "func half(x) { return x / 2; } \"${half(5.0)} ${1 < 2} ${[1, 2]}\";",
+                "2.5 true [1, 2]",
+            ),
+            ("let s = \"in\"; \"${\"nested ${s}\"}\";", "nested in"),
+            ("\"\\${escaped} $ {x}\";", "${escaped} $ {x}"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input);
+            test_equal_object(evaluated, Object::Str(Str::new(expected.to_string())));
+        }
+    }
+
+    #[test]
+    fn test_eval_char_exp() {
+        let tests = vec![
+            ("'a';", Object::Base(BaseValue::Char(Value::new('a')))),
+            ("'\\n';", Object::Base(BaseValue::Char(Value::new('\n')))),
+            (
+                "'\\u{1F600}';",
+                Object::Base(BaseValue::Char(Value::new('😀'))),
+            ),
+            ("b'z';", Object::Base(BaseValue::Byte(Value::new(b'z')))),
+            ("b'\\xFF';", Object::Base(BaseValue::Byte(Value::new(255)))),
+            (
+                "'a' < 'b';",
+                Object::Base(BaseValue::Boolean(Value::new(true))),
+            ),
+            (
+                "'a' == 'a';",
+                Object::Base(BaseValue::Boolean(Value::new(true))),
+            ),
+            (
+                "b'a' >= b'b';",
+                Object::Base(BaseValue::Boolean(Value::new(false))),
+            ),
+            (
+                "'a' == b'a';",
+                Object::Base(BaseValue::Boolean(Value::new(false))),
+            ),
+            ("'a' + 'b';", Object::Null),
+            (
+                "func f(c) { return c; } f('x');",
+                Object::Base(BaseValue::Char(Value::new('x'))),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input);
+            test_equal_object(evaluated, expected);
+        }
+    }
+
+    #[test]
+    fn test_eval_conversions() {
+        let tests = vec![
+            (
+                "int('a');",
+                Object::Base(BaseValue::Integer(Value::new(97))),
+            ),
+            (
+                "int(b'a');",
+                Object::Base(BaseValue::Integer(Value::new(97))),
+            ),
+            (
+                "int(-1.9);",
+                Object::Base(BaseValue::Integer(Value::new(-1))),
+            ),
+            (
+                "int(\"42\");",
+                Object::Base(BaseValue::Integer(Value::new(42))),
+            ),
+            (
+                "int(\"4x\");",
+                thrown("ConversionError", "cannot convert \"4x\" to int"),
+            ),
+            ("char(97);", Object::Base(BaseValue::Char(Value::new('a')))),
+            (
+                "char(b'a');",
+                Object::Base(BaseValue::Char(Value::new('a'))),
+            ),
+            (
+                "char(\"é\");",
+                Object::Base(BaseValue::Char(Value::new('é'))),
+            ),
+            (
+                "char(\"ab\");",
+                thrown("ConversionError", "cannot convert \"ab\" to char"),
+            ),
+            (
+                "char(55296);",
+                thrown("ConversionError", "cannot convert 55296 to char"),
+            ),
+            ("byte(97);", Object::Base(BaseValue::Byte(Value::new(b'a')))),
+            (
+                "byte('a');",
+                Object::Base(BaseValue::Byte(Value::new(b'a'))),
+            ),
+            (
+                "byte(256);",
+                thrown("ConversionError", "cannot convert 256 to byte"),
+            ),
+            (
+                "byte('😀');",
+                thrown("ConversionError", "cannot convert '😀' to byte"),
+            ),
+            ("str('a');", Object::Str(Str::new("a".to_string()))),
+            ("str(b'a');", Object::Str(Str::new("97".to_string()))),
+            ("str(1 + 2);", Object::Str(Str::new("3".to_string()))),
+            (
+                "str();",
+                thrown("ArgumentError", "`str` takes 1 argument, but 0 were given"),
+            ),
+            (
+                "let str = 1; str;",
+                Object::Base(BaseValue::Integer(Value::new(1))),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input);
+            test_equal_object(evaluated, expected);
+        }
+    }
+
+    #[test]
+    fn test_eval_type_builtins() {
+        let tests = vec![
+            ("type(1);", "int"),
+            ("type(1.5);", "float"),
+            ("type(true);", "bool"),
+            ("type('c');", "char"),
+            ("type(b'c');", "byte"),
+            ("type(\"s\");", "str"),
+            ("type([1, 2]);", "array"),
+            ("type(type);", "func"),
+            ("func f() { return 1; } type(f);", "func"),
+            ("type(if (false) { 1; });", "null"),
+            ("str([1, 2]);", "[1, 2]"),
+            ("str(float(3));", "3"),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input);
+            test_equal_object(evaluated, Object::Str(Str::new(expected.to_string())));
+        }
+
+        let tests = vec![
+            ("is_int(1);", true),
+            ("is_int(1.0);", false),
+            ("is_float(1.0);", true),
+            ("is_bool(false);", true),
+            ("is_char('c');", true),
+            ("is_byte('c');", false),
+            ("is_str(\"s\");", true),
+            ("is_array([1]);", true),
+            ("is_func(is_func);", true),
+            ("is_null(if (false) { 1; });", true),
+            ("bool(0);", false),
+            ("bool(2.5);", true),
+            ("bool(\"true\");", true),
+            ("bool(if (false) { 1; });", false),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input);
+            test_equal_object(
+                evaluated,
+                Object::Base(BaseValue::Boolean(Value::new(expected))),
+            );
+        }
+
+        let tests = vec![
+            ("float(3);", Object::Base(BaseValue::Float(Value::new(3.0)))),
+            (
+                "float(\" 2.5 \");",
+                Object::Base(BaseValue::Float(Value::new(2.5))),
+            ),
+            (
+                "int(true);",
+                Object::Base(BaseValue::Integer(Value::new(1))),
+            ),
+            (
+                "float(\"x\");",
+                thrown("ConversionError", "cannot convert \"x\" to float"),
+            ),
+            (
+                "bool(\"yes\");",
+                thrown("ConversionError", "cannot convert \"yes\" to bool"),
+            ),
+            (
+                "int([1]);",
+                thrown("ConversionError", "cannot convert [1] to int"),
+            ),
+            (
+                "type(1, 2);",
+                thrown("ArgumentError", "`type` takes 1 argument, but 2 were given"),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input);
+            test_equal_object(evaluated, expected);
+        }
+    }
+
+    #[test]
+    fn test_eval_runtime_errors() {
+        // The error stops the evaluation, the rest of the code is not run.
+        let tests = vec![
+            "let x = int(\"x\"); 1;",
+            "func f(a) { let y = int(a); return 1; } f(\"x\") + 1;",
+            "if (int(\"x\") > 0) { 1; } else { 2; }",
+            "[1, int(\"x\")];",
+            "-int(\"x\");",
+            "str(int(\"x\"));",
+            "let s = \"x\"; \"value: ${int(s)}\";",
+        ];
+
+        for input in tests {
+            let evaluated = test_eval(input);
+            test_equal_object(
+                evaluated,
+                thrown("ConversionError", "cannot convert \"x\" to int"),
+            );
+        }
+    }
+
     #[test]
     fn test_recursion() {
         let tests = vec![(
@@ -107,6 +347,10 @@ mod evaluator_test {
                 ",
                 Object::Base(BaseValue::Integer(Value::new(822))),
             ),
+            (
+                "func one() { return 1; } one();",
+                Object::Base(BaseValue::Integer(Value::new(1))),
+            ),
         ];
 
         for (input, expected) in tests {
@@ -181,11 +425,11 @@ mod evaluator_test {
         let tests = vec![
             (
                 "return 1 && 1;",
-                Object::Return(Box::new(Object::Base(BaseValue::Boolean(Value::new(true))))),
+                Object::Return(Box::new(Object::Base(BaseValue::Integer(Value::new(1))))),
             ),
             (
                 "return 1 || 1;",
-                Object::Return(Box::new(Object::Base(BaseValue::Boolean(Value::new(true))))),
+                Object::Return(Box::new(Object::Base(BaseValue::Integer(Value::new(1))))),
             ),
             (
                 "return 10;",
@@ -378,10 +622,6 @@ mod evaluator_test {
             ("!!0;", false),
             ("1 && true;", true),
             ("1 && false;", false),
-            ("0 && true;", false),
-            ("0 && false;", false),
-            ("1 || true;", true),
-            ("1 || false;", true),
             ("0 || true;", true),
             ("0 || false;", false),
             ("!1;", false),
@@ -407,6 +647,434 @@ mod evaluator_test {
         }
     }
 
+    #[test]
+    fn test_eval_equality_and_ordering() {
+        let tests = vec![
+            ("1 == 1.0;", true),
+            ("2.5 > 2;", true),
+            ("-2.5 < -2;", true),
+            // The integers above 2^53 are not rounded to a float.
+            ("9007199254740993 == 9007199254740992.0;", false),
+            ("9007199254740992.0 == 9007199254740992;", true),
+            ("9007199254740993 > 9007199254740992.0;", true),
+            ("9007199254740992.0 < 9007199254740993;", true),
+            ("9223372036854775807 < 9223372036854775808.0;", true),
+            ("true == 1;", false),
+            ("'a' == b'a';", false),
+            ("\"abc\" == \"abc\";", true),
+            ("\"abc\" != \"abd\";", true),
+            ("\"abc\" < \"abd\";", true),
+            ("\"b\" > \"abc\";", true),
+            ("\"1\" == 1;", false),
+            ("[1, [2, \"x\"]] == [1, [2, \"x\"]];", true),
+            ("[1, [2, \"x\"]] == [1, [2, \"y\"]];", false),
+            ("[1, 2] == [1.0, 2];", true),
+            ("[1, 2] < [1, 3];", true),
+            ("[1, 2] < [1, 2, 0];", true),
+            ("[2] > [1, 5];", true),
+            ("[1, 2] == [1, 2, 3];", false),
+            ("let n = if (false) { 1; }; n == n;", true),
+            ("let n = if (false) { 1; }; n == 0;", false),
+            ("let n = if (false) { 1; }; n < 1;", false),
+            ("func f(x) { return x; } f == f;", true),
+            (
+                "func f(x) { return x; } func g(x) { return x; } f == g;",
+                false,
+            ),
+            ("int == int;", true),
+            ("int == str;", false),
+            ("true < false;", false),
+            ("true <= true;", true),
+            ("[1, \"a\"] < [1, 2];", false),
+            ("[1, \"a\"] > [1, 2];", false),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input);
+            test_equal_object(
+                evaluated,
+                Object::Base(BaseValue::Boolean(Value::new(expected))),
+            );
+        }
+    }
+
+    #[test]
+    fn test_eval_short_circuit() {
+        // The result is the operand that decides it.
+        let tests = vec![
+            (
+                "0 && true;",
+                Object::Base(BaseValue::Integer(Value::new(0))),
+            ),
+            (
+                "1 || false;",
+                Object::Base(BaseValue::Integer(Value::new(1))),
+            ),
+            ("0 || 2.5;", Object::Base(BaseValue::Float(Value::new(2.5)))),
+            ("1 && \"s\";", Object::Str(Str::new("s".to_string()))),
+            (
+                "\"s\" || 'c';",
+                Object::Base(BaseValue::Char(Value::new('c'))),
+            ),
+            ("\"s\" && 'c';", Object::Str(Str::new("s".to_string()))),
+            (
+                "let n = if (false) { 1; }; n || 3;",
+                Object::Base(BaseValue::Integer(Value::new(3))),
+            ),
+            (
+                "func f(x) { return x || 5; } f(0) + 1;",
+                Object::Base(BaseValue::Integer(Value::new(6))),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input);
+            test_equal_object(evaluated, expected);
+        }
+
+        // The right operand isn't evaluated if the left one decides the result, so its error doesn't happen.
+        let tests = vec![
+            (
+                "false && int(\"x\");",
+                Object::Base(BaseValue::Boolean(Value::new(false))),
+            ),
+            (
+                "true || int(\"x\");",
+                Object::Base(BaseValue::Boolean(Value::new(true))),
+            ),
+            (
+                "let arr = [1]; is_array(arr) && arr[0] > 0;",
+                Object::Base(BaseValue::Boolean(Value::new(true))),
+            ),
+            (
+                "let arr = 1; is_array(arr) && arr[0] > 0;",
+                Object::Base(BaseValue::Boolean(Value::new(false))),
+            ),
+            (
+                "true && int(\"x\");",
+                thrown("ConversionError", "cannot convert \"x\" to int"),
+            ),
+            (
+                "false || int(\"x\");",
+                thrown("ConversionError", "cannot convert \"x\" to int"),
+            ),
+            // The left operand is evaluated first.
+            (
+                "int(\"a\") && int(\"b\");",
+                thrown("ConversionError", "cannot convert \"a\" to int"),
+            ),
+            (
+                "int(\"a\") || int(\"b\");",
+                thrown("ConversionError", "cannot convert \"a\" to int"),
+            ),
+            (
+                "false && int(\"a\") || int(\"b\");",
+                thrown("ConversionError", "cannot convert \"b\" to int"),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input);
+            test_equal_object(evaluated, expected);
+        }
+    }
+
+    #[test]
+    fn test_eval_try_catch() {
+        let int = |v: i64| Object::Base(BaseValue::Integer(Value::new(v)));
+        let str = |v: &str| Object::Str(Str::new(v.to_string()));
+        let tests =
+            vec![
+            ("let r = 0; try { throw 1; } catch (e) { let r = 2; } r;", int(2)),
+            ("try { 1; } catch (e) { 2; }", int(1)),
+            ("try { throw 1; } catch (e) { 2; }", int(2)),
+            // The caught error is only visible in the catch block.
+            (
+                "let e = 5; try { throw \"x\"; } catch (e) { 1; } e;",
+                int(5),
+            ),
+            (
+                "func f() { let e = 5; try { throw 1; } catch (e) { let e = 2; } return e; } f();",
+                int(5),
+            ),
+            // The value that is not an error becomes the message of an `Error`.
+            (
+                "try { throw 42; } catch (e) { \"${error_kind(e)}: ${error_message(e)}\"; }",
+                str("Error: 42"),
+            ),
+            (
+                "try { int(\"x\"); } catch (e) { error_kind(e); }",
+                str("ConversionError"),
+            ),
+            (
+                "try { 1 / 0; } catch (e) { str(e); }",
+                str("RuntimeError: division by zero"),
+            ),
+            (
+                "try { 9223372036854775807 + 1; } catch (e) { error_message(e); }",
+                str("integer overflow"),
+            ),
+            (
+                "let m = -9223372036854775807 - 1; try { -m; } catch (e) { error_message(e); }",
+                str("integer overflow"),
+            ),
+            // The error unwinds through the function calls, and records them in its trace.
+            (
+                "
+                func inner(x) { throw error(\"ValueError\", \"bad ${x}\"); }
+                func outer(x) { return inner(x) + 1; }
+                try { outer(1); } catch (e) { \"${error_message(e)} ${error_trace(e)}\"; }
+                ",
+                str("bad 1 [\"inner\", \"outer\"]"),
+            ),
+            (
+                "
+                func safe(x) { try { return int(x); } catch (e) { return -1; } }
+                safe(\"7\") + safe(\"x\");
+                ",
+                int(6),
+            ),
+            // The error thrown again keeps its kind.
+            (
+                "try { try { int(\"x\"); } catch (e) { throw e; } } catch (e) { error_kind(e); }",
+                str("ConversionError"),
+            ),
+            // The error value is a plain value until it's thrown.
+            (
+                "let e = error(\"KeyError\", \"k\"); \"${type(e)} ${is_error(e)} ${e}\";",
+                str("error true KeyError: k"),
+            ),
+            (
+                "error(\"KeyError\", \"k\") == error(\"KeyError\", \"k\");",
+                Object::Base(BaseValue::Boolean(Value::new(true))),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input);
+            test_equal_object(evaluated, expected);
+        }
+    }
+
+    #[test]
+    fn test_eval_finally() {
+        let tests = vec![
+            (
+                "let n = 0; try { let n = 1; } finally { let n = n + 10; } n;",
+                Object::Base(BaseValue::Integer(Value::new(11))),
+            ),
+            (
+                "let n = 0; try { throw 1; } catch (e) { let n = 1; } finally { let n = n + 10; } n;",
+                Object::Base(BaseValue::Integer(Value::new(11))),
+            ),
+            // The `return` of the finally block overrides the result of the body and the catch block.
+            (
+                "func f(x) { try { return x; } finally { return 2; } } f(1) + 0;",
+                Object::Base(BaseValue::Integer(Value::new(2))),
+            ),
+            (
+                "func f(x) { try { throw x; } finally { return 2; } } f(1) + 0;",
+                Object::Base(BaseValue::Integer(Value::new(2))),
+            ),
+            (
+                "func f(x) { try { throw x; } catch (e) { throw 2; } finally { return 3; } } f(1) + 0;",
+                Object::Base(BaseValue::Integer(Value::new(3))),
+            ),
+            // The error that isn't caught goes on after the finally block.
+            (
+                "let n = 0; try { throw \"a\"; } finally { let n = 1; } n;",
+                thrown("Error", "a"),
+            ),
+            (
+                "try { throw 1; } catch (e) { int(\"x\"); } finally { 1; }",
+                thrown("ConversionError", "cannot convert \"x\" to int"),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let evaluated = test_eval(input);
+            test_equal_object(evaluated, expected);
+        }
+    }
+
+    #[test]
+    fn test_eval_backtrace() {
+        let input = "
+func div(a) {
+    return a / 0;
+}
+func run(a) {
+    let x = div(a);
+    return x;
+}
+run(1);
+";
+        let evaluator = Evaluator::new(None);
+        let err = match eval_with(&evaluator, input) {
+            Object::Throw(err) => err,
+            obj => panic!("the result is not thrown, got={:?}", obj),
+        };
+
+        // Every call records its call site, the innermost one first.
+        assert_eq!(
+            err.report(),
+            "RuntimeError: division by zero\n    at div (line 6, column 13)\n    at run (line 9, column 1)"
+        );
+        assert_eq!(evaluator.call_stack().depth(), 0);
+    }
+
+    #[test]
+    fn test_eval_max_depth() {
+        let input = "
+            func count(n) { if (n == 0) { return 0; } return count(n - 1) + 1; }
+            let ok = count(49);
+            let err = 0;
+            try { count(50); } catch (e) { let err = e; }
+            let trace = error_trace(err);
+            \"${ok} ${err} ${trace[49]}\";
+        ";
+        let evaluator = Evaluator::new(None).with_max_depth(50);
+        test_equal_object(
+            eval_with(&evaluator, input),
+            Object::Str(Str::new(
+                "49 RuntimeError: stack overflow, the maximum call depth is 50 count".to_string(),
+            )),
+        );
+        assert_eq!(evaluator.call_stack().depth(), 0);
+
+        // The repeated calls of the recursion are folded in the backtrace.
+        let input = "func loop(n) { return 1 + loop(n + 1); } loop(0);";
+        let report = match eval_with(&Evaluator::new(None).with_max_depth(20), input) {
+            Object::Throw(err) => err.report(),
+            obj => panic!("the result is not thrown, got={:?}", obj),
+        };
+        assert_eq!(
+            report,
+            "RuntimeError: stack overflow, the maximum call depth is 20\n    \
+             at loop (line 1, column 27)\n    ... repeated 18 more times\n    \
+             at loop (line 1, column 42)"
+        );
+    }
+
+    #[test]
+    fn test_eval_tail_call() {
+        let int = |v: i64| Object::Base(BaseValue::Integer(Value::new(v)));
+        let str = |v: &str| Object::Str(Str::new(v.to_string()));
+        let tests = vec![
+            // The tail recursion is evaluated in a loop, it's not limited by the maximum depth.
+            (
+                "
+                func sum(n, acc) { if (n == 0) { return acc; } return sum(n - 1, acc + n); }
+                sum(1000000, 0);
+                ",
+                int(500000500000),
+            ),
+            (
+                "
+                func count(n) { if (n == 0) { return \"done\"; } else { return count(n - 1); } }
+                count(1000000);
+                ",
+                str("done"),
+            ),
+            // The value of a call is the returned value, and the caller goes on.
+            (
+                "func b() { return 1; } func a(x) { b(); return 2; } a(0);",
+                int(2),
+            ),
+            (
+                "func g(x) { return \"s\"; } func h(x) { return g(x); } h(1);",
+                str("s"),
+            ),
+            // The call in a `try` block is finished in the block, so its error is caught.
+            (
+                "
+                func fail() { throw error(\"E\", \"m\"); }
+                func safe() { try { return fail(); } catch (e) { return error_kind(e); } }
+                safe();
+                ",
+                str("E"),
+            ),
+            (
+                "func f(x) { let y = if (x) { return 1; } else { 2; }; return y + 1; } f(true);",
+                int(2),
+            ),
+        ];
+
+        for (input, expected) in tests {
+            test_equal_object(test_eval(input), expected);
+        }
+
+        // The tail call replaces the frame of the caller in the call stack and the trace.
+        let input = "
+            func down(n) { if (n == 0) { throw \"bottom\"; } return down(n - 1); }
+            func start(n) { let r = down(n); return r; }
+            let t = 0;
+            try { start(100000); } catch (e) { let t = error_trace(e); }
+            t;
+        ";
+        let evaluator = Evaluator::new(None).with_max_depth(10);
+        test_equal_object(
+            eval_with(&evaluator, input),
+            Object::Array(Array::new(vec![str("down"), str("start")])),
+        );
+        assert_eq!(evaluator.call_stack().depth(), 0);
+    }
+
+    // The default evaluator raises the error before it overflows the stack of an ordinary thread,
+    // however much stack a call of the function takes.
+    #[test]
+    fn test_eval_default_stack_limit() {
+        let tests = [
+            "func f(n) { return 1 + f(n + 1); } f(0);",
+            "
+            func f(n) {
+                let a = [n, n * 2];
+                if (n > -1) { let b = \"${n} ${a}\"; return 1 + (2 * (3 + (f(n + 1) - 4))); }
+                return 0;
+            }
+            f(0);
+            ",
+        ];
+
+        for input in tests {
+            let message = std::thread::spawn(move || match test_eval(input) {
+                Object::Throw(err) => err.to_string(),
+                obj => format!("{:?}", obj),
+            })
+            .join()
+            .unwrap();
+            assert!(
+                message.starts_with("RuntimeError: stack overflow"),
+                "{}",
+                message
+            );
+        }
+    }
+
+    // The host that gives the evaluator a larger stack can raise the limit to reach the default depth.
+    #[test]
+    fn test_eval_default_max_depth() {
+        let stack_size = STACK_PER_CALL * (DEFAULT_MAX_DEPTH + 1);
+        let handle = std::thread::Builder::new()
+            .stack_size(stack_size)
+            .spawn(move || {
+                let evaluator = Evaluator::new(None).with_stack_limit(stack_size / 2);
+                let input = "func loop(n) { return 1 + loop(n + 1); } loop(0);";
+                match eval_with(&evaluator, input) {
+                    Object::Throw(err) => err.to_string(),
+                    obj => format!("{:?}", obj),
+                }
+            })
+            .unwrap();
+        assert_eq!(
+            handle.join().unwrap(),
+            format!(
+                "RuntimeError: stack overflow, the maximum call depth is {}",
+                DEFAULT_MAX_DEPTH
+            )
+        );
+    }
+
     #[test]
     fn test_eval_prefix_exp() {
         let _tests = vec![
@@ -416,6 +1084,10 @@ mod evaluator_test {
             ("!!true;", true),
             ("!!false;", false),
             ("!!5;", true),
+            ("!0.0;", true),
+            ("!\"s\";", true),
+            ("![1];", true),
+            ("!!'c';", false),
         ];
 
         for (input, expected) in _tests {
@@ -482,14 +1154,24 @@ mod evaluator_test {
         }
     }
 
+    fn thrown(kind: &str, message: &str) -> Object {
+        Object::Throw(Error::new(kind, message.to_string()))
+    }
+
     fn test_eval(input: &str) -> Object {
-        let parser = Parser::new(input);
-        let program = parser.programs();
-        let evaluator = Evaluator::new(None);
+        eval_with(&Evaluator::new(None), input)
+    }
+
+    fn eval_with(evaluator: &Evaluator, input: &str) -> Object {
+        let parser = Parser::from_source(input);
+        let program = Resolver::new().resolve(&parser.programs()).unwrap();
 
         let mut result = Object::Null;
         for node in program.iter() {
             result = evaluator.eval(node);
+            if result.is_thrown() {
+                break;
+            }
         }
 
         return result;
@@ -513,12 +1195,27 @@ mod evaluator_test {
             (Object::Base(BaseValue::Boolean(v)), Object::Base(BaseValue::Boolean(e))) => {
                 assert_eq!(v.value(), e.value());
             }
+            (Object::Base(BaseValue::Char(v)), Object::Base(BaseValue::Char(e))) => {
+                assert_eq!(v.value(), e.value());
+            }
+            (Object::Base(BaseValue::Byte(v)), Object::Base(BaseValue::Byte(e))) => {
+                assert_eq!(v.value(), e.value());
+            }
             (Object::Return(v), Object::Return(e)) => {
                 test_equal_object(*v, *e);
             }
             (Object::Str(v), Object::Str(e)) => {
                 assert_eq!(v.value(), e.value());
             }
+            (Object::Array(v), Object::Array(e)) => {
+                assert_eq!(v.elements().len(), e.elements().len());
+                for (v, e) in v.elements().iter().zip(e.elements()) {
+                    test_equal_object(v.clone(), e.clone());
+                }
+            }
+            (Object::Throw(v), Object::Throw(e)) | (Object::Error(v), Object::Error(e)) => {
+                assert_eq!(v.to_string(), e.to_string());
+            }
             _ => panic!("The object is not equal, got={:?}, want={:?}", get, want),
         }
     }
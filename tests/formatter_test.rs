@@ -0,0 +1,194 @@
+#[cfg(test)]
+mod formatter_test {
+    use std::fs;
+
+    use woc_lang::formatter::formatter::{format_source, FormatConfig};
+
+    fn format(source: &str) -> String {
+        format_source(source, &FormatConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn test_format_file() {
+        let source = fs::read_to_string("woc_test_files/formatter/unformatted.woc").unwrap();
+        let expected = fs::read_to_string("woc_test_files/formatter/formatted.woc").unwrap();
+
+        assert_eq!(format(&source), expected);
+        assert_eq!(format(&expected), expected);
+    }
+
+    #[test]
+    fn test_spaces_and_breaks() {
+        let tests = [
+            ("let a=1+2*3;", "let a = 1 + 2 * 3;\n"),
+            (
+                "let a = - ( 1 ) ;let b=! a;",
+                "let a = -(1);\nlet b = !a;\n",
+            ),
+            ("let x = a - -1;", "let x = a - -1;\n"),
+            ("add ( 1 ,2 ) + arr [ 0 ];", "add(1, 2) + arr[0];\n"),
+            ("let arr = [ [1], [ 2,3 ] ];", "let arr = [[1], [2, 3]];\n"),
+            ("func f ( ) { }", "func f() {}\n"),
+            (
+                "if (a) { 1; } else { if (b) { 2; } }",
+                "if (a) {\n    1;\n} else {\n    if (b) {\n        2;\n    }\n}\n",
+            ),
+            (
+                "try { f(); }\ncatch(e) { throw e; }\nfinally { g(); }",
+                "try {\n    f();\n} catch (e) {\n    throw e;\n} finally {\n    g();\n}\n",
+            ),
+            (
+                "let s = \"${ a }\" ;  let c = 'x';",
+                "let s = \"${ a }\";\nlet c = 'x';\n",
+            ),
+            ("", ""),
+            ("\n\n", ""),
+        ];
+
+        for (source, expected) in tests {
+            assert_eq!(format(source), expected, "source: {:?}", source);
+        }
+    }
+
+    #[test]
+    fn test_if_expression() {
+        // The blocks in an expression are indented from the line of the statement,
+        // and their `}` are put back at its indent.
+        let tests = [
+            (
+                "let z = if (x > 0) { 1 } else { 2 };",
+                "let z = if (x > 0) {\n    1\n} else {\n    2\n};\n",
+            ),
+            (
+                "func f(x) { return 1 + if (x) { 2 } else { if (y) { 3 } else { 4 } }; }",
+                "func f(x) {
+    return 1 + if (x) {
+        2
+    } else {
+        if (y) {
+            3
+        } else {
+            4
+        }
+    };
+}
+",
+            ),
+            ("let e = if (x) {} else {};", "let e = if (x) {} else {};\n"),
+        ];
+
+        for (source, expected) in tests {
+            assert_eq!(format(source), expected, "source: {:?}", source);
+            assert_eq!(format(expected), expected, "source: {:?}", expected);
+        }
+    }
+
+    #[test]
+    fn test_comments() {
+        let source = "
+// The first line.
+let a = 1;   // after a
+/* before */ let b = 2;
+let c = 1 + /* inline */ 2;
+func f() { // after brace
+    // inside
+    return 1;
+    /* at the end */
+}
+/// Docs
+/// more docs
+let d = 3;
+// The last line.";
+        let expected = "// The first line.
+let a = 1; // after a
+/* before */
+let b = 2;
+let c = 1 + /* inline */ 2;
+func f() { // after brace
+    // inside
+    return 1;
+    /* at the end */
+}
+/// Docs
+/// more docs
+let d = 3;
+// The last line.
+";
+        assert_eq!(format(source), expected);
+    }
+
+    #[test]
+    fn test_blank_lines() {
+        let source =
+            "let a = 1;\n\n\n\nlet b = 2;\nfunc f() {\n\n    let c = 3;\n\n    let d = 4;\n\n}\n\n";
+        let expected =
+            "let a = 1;\n\nlet b = 2;\nfunc f() {\n    let c = 3;\n\n    let d = 4;\n}\n";
+        assert_eq!(format(source), expected);
+    }
+
+    #[test]
+    fn test_config() {
+        let source =
+            "func f(x) { if (x) { return add(first_argument, second_argument, [1, 2, 3]); } }";
+
+        let config = FormatConfig::new(2, 40);
+        let expected = "func f(x) {
+  if (x) {
+    return add(
+      first_argument,
+      second_argument,
+      [1, 2, 3]
+    );
+  }
+}
+";
+        assert_eq!(format_source(source, &config).unwrap(), expected);
+
+        // A line within the width is not split.
+        let config = FormatConfig::new(3, 80);
+        let expected = "func f(x) {
+   if (x) {
+      return add(first_argument, second_argument, [1, 2, 3]);
+   }
+}
+";
+        assert_eq!(format_source(source, &config).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_idempotent() {
+        let sources = [
+            fs::read_to_string("woc_test_files/formatter/unformatted.woc").unwrap(),
+            fs::read_to_string("woc_test_files/parser/test_parse_arr_exp.woc").unwrap(),
+            fs::read_to_string("woc_test_files/parser/test_string.woc").unwrap(),
+            "let r = 1 + // half\n2 + /* c */\n3;".to_string(),
+            "let a = f(g(1, 2, 3), [4, 5, h(6, 7)], 8); // a comment that is longer than the width"
+                .to_string(),
+        ];
+
+        for width in [10, 30, 100] {
+            let config = FormatConfig::new(4, width);
+            for source in sources.iter() {
+                let once = format_source(source, &config).unwrap();
+                let twice = format_source(&once, &config).unwrap();
+                assert_eq!(once, twice, "source: {:?}", source);
+            }
+        }
+    }
+
+    #[test]
+    fn test_line_breaks_and_bom() {
+        let source = "\u{FEFF}let a=1;\r\n\r\n\r\nlet b=2;";
+        assert_eq!(format(source), "\u{FEFF}let a = 1;\r\n\r\nlet b = 2;\r\n");
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let err = format_source("let a = ;\nlet b = @;", &FormatConfig::default()).unwrap_err();
+        assert!(
+            err.contains("illegal character `@` at line 2, column 9"),
+            "{}",
+            err
+        );
+    }
+}
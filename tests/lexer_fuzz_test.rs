@@ -0,0 +1,157 @@
+mod old_lexer;
+
+#[cfg(test)]
+mod lexer_fuzz_test {
+    use std::rc::Rc;
+
+    use woc_lang::lexer::lexer::Lexer;
+    use woc_lang::token::token::Token;
+
+    use crate::old_lexer;
+
+    // The identifiers, keywords and numbers.
+    // The old lexer split an identifier that starts with a keyword: `letter` was `let` and `ter`,
+    // and `__` was two identifiers, so the identifiers like them are not generated.
+    const WORDS: &[&str] = &[
+        "while", "for", "if", "else", "break", "continue", "let", "func", "return", "struct", "enum",
+        "none", "true", "false", "wh", "whil", "fo", "fu", "fun", "fa", "i", "e", "el", "en", "br",
+        "co", "contin", "le", "re", "retur", "st", "n", "tr", "t", "x", "foo_bar", "_", "_a1", "a_",
+        "r", "b", "rb", "é", "数", "x9", "0", "42", "1.5", "0xff", "1e-9", ".5", "1_000", "2z", "-3",
+    ];
+
+    const SYMBOLS: &[&str] = &[
+        ",", ".", ";", ":", "=", "==", "!", "!=", ">", ">=", "<", "<=", "&", "&&", "|", "||", "~",
+        "+", "+=", "-", "-=", "*", "*=", "/", "/=", "%", "(", ")", "{", "}", "[", "]", "@", "$", "#",
+    ];
+
+    // The old lexer kept the unknown escape sequences, so the raw string only has a known one,
+    // in case it ends up in a string that is not raw.
+    const LITERALS: &[&str] = &[
+        "\"a\"",
+        "\"\"",
+        "\"a\\n\\u{1F600}\"",
+        "r\"c:\\t\"",
+        "\"\"\"t \"q\" \"\"\"",
+        "\"line\r\nbreak\"",
+        "'c'",
+        "'\\''",
+        "b'z'",
+        "''",
+        "'ab'",
+        "\"${}\"",
+        "\"open",
+        "// comment\n",
+        "/// doc\n",
+        "/* a /* nested */ */",
+        "/* open",
+    ];
+
+    const BLANKS: &[&str] = &[" ", "\n", "\t", "\r\n", "  "];
+
+    #[test]
+    fn test_same_as_old_lexer() {
+        let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+        for _ in 0..3000 {
+            let source = random_source(&mut rng, 40, true);
+
+            let new_tokens = Lexer::from_source(&source).tokens_iter();
+            let mut tokens = Vec::new();
+            loop {
+                let token = new_tokens.next().unwrap();
+                tokens.push(summary(&token));
+                if token.is_eof() {
+                    break;
+                }
+            }
+
+            let mut old_tokens: Vec<_> = old_lexer::Lexer::from_source(&source)
+                .tokens()
+                .iter()
+                .map(summary)
+                .collect();
+            assert!(
+                tokens.windows(2).all(|pair| pair[0].3 <= pair[1].2),
+                "source: {:?}",
+                source
+            );
+            skip_unterminated_start(&mut tokens);
+            skip_unterminated_start(&mut old_tokens);
+            assert_eq!(tokens, old_tokens, "source: {:?}", source);
+        }
+    }
+
+    // Generate the source code with the random pieces, the strings may embed the expressions.
+    fn random_source(rng: &mut Rng, max_pieces: usize, interpolation: bool) -> String {
+        let mut source = String::new();
+        for _ in 0..rng.below(max_pieces + 1) {
+            let piece = match rng.below(10) {
+                0..=3 => rng.pick(WORDS).to_string(),
+                4..=6 => rng.pick(SYMBOLS).to_string(),
+                7 if interpolation => format!("\"a ${{{}}} b\"", random_source(rng, 6, false)),
+                7 | 8 => rng.pick(LITERALS).to_string(),
+                _ => rng.pick(BLANKS).to_string(),
+            };
+
+            // Two words are always separated by a blank.
+            if source.ends_with(is_word_char) && piece.starts_with(is_word_char) {
+                source.push(' ');
+            }
+            // The old lexer has no `->`, so a `-` and a `>` are separated by a blank.
+            if source.ends_with('-') && piece.starts_with('>') {
+                source.push(' ');
+            }
+            source.push_str(&piece);
+
+            // The unterminated literal and comment end the source code.
+            if piece.ends_with("open") {
+                break;
+            }
+        }
+        source
+    }
+
+    // The old lexer started the token of the unterminated string at its opening quote, so it
+    // overlapped the segments and the embedded expressions before it, now it starts after them.
+    // Its start is not compared, and the new tokens are checked not to overlap instead.
+    fn skip_unterminated_start(tokens: &mut [Summary]) {
+        for token in tokens.iter_mut() {
+            if token.1.starts_with("unterminated string") {
+                (token.2, token.4, token.5) = (0, 0, 0);
+            }
+        }
+    }
+
+    fn is_word_char(c: char) -> bool {
+        c.is_alphanumeric() || c == '_'
+    }
+
+    // The type, literal, offset, end, line and column of a token.
+    type Summary = (String, String, usize, usize, usize, usize);
+
+    fn summary(token: &Rc<Token>) -> Summary {
+        (
+            format!("{:?}", token.token_type()),
+            token.literal().to_string(),
+            token.offset(),
+            token.end(),
+            token.file_row_number(),
+            token.column(),
+        )
+    }
+
+    // A xorshift generator with a fixed seed, so a failure can be reproduced.
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+
+        fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+            items[self.below(items.len())]
+        }
+    }
+}
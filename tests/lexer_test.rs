@@ -4,6 +4,7 @@ mod lexer_test {
     use std::vec;
 
     use woc_lang::lexer::lexer::Lexer;
+    use woc_lang::token::keywords;
     use woc_lang::token::token::Token;
     use woc_lang::token::types::TokenType;
 
@@ -18,41 +19,42 @@ mod lexer_test {
 
     #[test]
     fn test_woc_file_tokens() {
-        let lexer = Lexer::new("woc_test_files/hello_world.woc");
+        let lexer = Lexer::new("woc_test_files/hello_world.woc").unwrap();
         let tokens_iter = lexer.tokens_iter();
 
         // let a = 1;
         // let b = 2;
         // let c = 3;
         // let arr = [a, b, c];
+        // The columns are counted in chars, and the EOF is right after the last char.
         let expects = vec![
             Token::new(TokenType::Let, "let", "woc_test_files/hello_world.woc", 1, 1),
-            Token::new(TokenType::Ident, "a", "woc_test_files/hello_world.woc", 1, 2),
-            Token::new(TokenType::Assignment, "=", "woc_test_files/hello_world.woc", 1, 3),
-            Token::new(TokenType::IntegerNum, "1", "woc_test_files/hello_world.woc", 1, 4),
-            Token::new(TokenType::Semicolon, ";", "woc_test_files/hello_world.woc", 1, 5),
+            Token::new(TokenType::Ident, "a", "woc_test_files/hello_world.woc", 1, 5),
+            Token::new(TokenType::Assignment, "=", "woc_test_files/hello_world.woc", 1, 7),
+            Token::new(TokenType::IntegerNum, "1", "woc_test_files/hello_world.woc", 1, 9),
+            Token::new(TokenType::Semicolon, ";", "woc_test_files/hello_world.woc", 1, 10),
             Token::new(TokenType::Let, "let", "woc_test_files/hello_world.woc", 2, 1),
-            Token::new(TokenType::Ident, "b", "woc_test_files/hello_world.woc", 2, 2),
-            Token::new(TokenType::Assignment, "=", "woc_test_files/hello_world.woc", 2, 3),
-            Token::new(TokenType::IntegerNum, "2", "woc_test_files/hello_world.woc", 2, 4),
-            Token::new(TokenType::Semicolon, ";", "woc_test_files/hello_world.woc", 2, 5),
+            Token::new(TokenType::Ident, "b", "woc_test_files/hello_world.woc", 2, 5),
+            Token::new(TokenType::Assignment, "=", "woc_test_files/hello_world.woc", 2, 7),
+            Token::new(TokenType::IntegerNum, "2", "woc_test_files/hello_world.woc", 2, 9),
+            Token::new(TokenType::Semicolon, ";", "woc_test_files/hello_world.woc", 2, 10),
             Token::new(TokenType::Let, "let", "woc_test_files/hello_world.woc", 3, 1),
-            Token::new(TokenType::Ident, "c", "woc_test_files/hello_world.woc", 3, 2),
-            Token::new(TokenType::Assignment, "=", "woc_test_files/hello_world.woc", 3, 3),
-            Token::new(TokenType::IntegerNum, "3", "woc_test_files/hello_world.woc", 3, 4),
-            Token::new(TokenType::Semicolon, ";", "woc_test_files/hello_world.woc", 3, 5),
+            Token::new(TokenType::Ident, "c", "woc_test_files/hello_world.woc", 3, 5),
+            Token::new(TokenType::Assignment, "=", "woc_test_files/hello_world.woc", 3, 7),
+            Token::new(TokenType::IntegerNum, "3", "woc_test_files/hello_world.woc", 3, 9),
+            Token::new(TokenType::Semicolon, ";", "woc_test_files/hello_world.woc", 3, 10),
             Token::new(TokenType::Let, "let", "woc_test_files/hello_world.woc", 4, 1),
-            Token::new(TokenType::Ident, "arr", "woc_test_files/hello_world.woc", 4, 2),
-            Token::new(TokenType::Assignment, "=", "woc_test_files/hello_world.woc", 4, 3),
-            Token::new(TokenType::LeftBracket, "[", "woc_test_files/hello_world.woc", 4, 4),
-            Token::new(TokenType::Ident, "a", "woc_test_files/hello_world.woc", 4, 5),
-            Token::new(TokenType::Comma, ",", "woc_test_files/hello_world.woc", 4, 6),
-            Token::new(TokenType::Ident, "b", "woc_test_files/hello_world.woc", 4, 7),
-            Token::new(TokenType::Comma, ",", "woc_test_files/hello_world.woc", 4, 8),
-            Token::new(TokenType::Ident, "c", "woc_test_files/hello_world.woc", 4, 9),
-            Token::new(TokenType::RightBracket, "]", "woc_test_files/hello_world.woc", 4, 10),
-            Token::new(TokenType::Semicolon, ";", "woc_test_files/hello_world.woc", 4, 11),
-            Token::new(TokenType::Eof, "", "woc_test_files/hello_world.woc", 5, 1),
+            Token::new(TokenType::Ident, "arr", "woc_test_files/hello_world.woc", 4, 5),
+            Token::new(TokenType::Assignment, "=", "woc_test_files/hello_world.woc", 4, 9),
+            Token::new(TokenType::LeftBracket, "[", "woc_test_files/hello_world.woc", 4, 11),
+            Token::new(TokenType::Ident, "a", "woc_test_files/hello_world.woc", 4, 12),
+            Token::new(TokenType::Comma, ",", "woc_test_files/hello_world.woc", 4, 13),
+            Token::new(TokenType::Ident, "b", "woc_test_files/hello_world.woc", 4, 15),
+            Token::new(TokenType::Comma, ",", "woc_test_files/hello_world.woc", 4, 16),
+            Token::new(TokenType::Ident, "c", "woc_test_files/hello_world.woc", 4, 18),
+            Token::new(TokenType::RightBracket, "]", "woc_test_files/hello_world.woc", 4, 19),
+            Token::new(TokenType::Semicolon, ";", "woc_test_files/hello_world.woc", 4, 20),
+            Token::new(TokenType::Eof, "", "woc_test_files/hello_world.woc", 4, 21),
         ];
 
         for expect in expects {
@@ -61,11 +63,461 @@ mod lexer_test {
         }
     }
 
+    #[test]
+    fn test_comments() {
+        let input = "
+            // A line comment.
+            let a = 1; // The rest of the line is skipped.
+            let b /* inline */ = a / 2;
+            /* A block comment
+               spans lines /* and can be nested */
+               let c = 3;
+            */
+            //// This is not a doc comment.
+            /// The doc comment
+            /// of the function.
+            func f() {}
+        ";
+
+        let expects = vec![
+            (TokenType::Let, "let"),
+            (TokenType::Ident, "a"),
+            (TokenType::Assignment, "="),
+            (TokenType::IntegerNum, "1"),
+            (TokenType::Semicolon, ";"),
+            (TokenType::Let, "let"),
+            (TokenType::Ident, "b"),
+            (TokenType::Assignment, "="),
+            (TokenType::Ident, "a"),
+            (TokenType::Slash, "/"),
+            (TokenType::IntegerNum, "2"),
+            (TokenType::Semicolon, ";"),
+            (TokenType::DocComment, "The doc comment"),
+            (TokenType::DocComment, "of the function."),
+            (TokenType::Func, "func"),
+            (TokenType::Ident, "f"),
+            (TokenType::LeftParen, "("),
+            (TokenType::RightParen, ")"),
+            (TokenType::LeftBrace, "{"),
+            (TokenType::RightBrace, "}"),
+        ];
+
+        assert_tokens(input, expects);
+    }
+
+    #[test]
+    fn test_strings() {
+        let input = r#"
+            let s = "a, b; (c)!";
+            "tab\there \"quoted\" \\ \u{1F600} \u{e9}";
+            "keep \w and \d"; "\x41\x7e";
+            r"C:\new\dir" r"";
+            "";
+            """
+  say "hi"
+    to ""you""!"""
+            "two
+lines";
+            "bad \u{110000}";
+        "#;
+
+        let expects = vec![
+            (TokenType::Let, "let"),
+            (TokenType::Ident, "s"),
+            (TokenType::Assignment, "="),
+            (TokenType::String, "a, b; (c)!"),
+            (TokenType::Semicolon, ";"),
+            (
+                TokenType::String,
+                "tab\there \"quoted\" \\ \u{1F600} \u{e9}",
+            ),
+            (TokenType::Semicolon, ";"),
+            (
+                TokenType::Illegal,
+                "unknown escape sequence `\\w` at line 4, column 19",
+            ),
+            (TokenType::Semicolon, ";"),
+            (TokenType::String, "A~"),
+            (TokenType::Semicolon, ";"),
+            (TokenType::String, "C:\\new\\dir"),
+            (TokenType::String, ""),
+            (TokenType::Semicolon, ";"),
+            (TokenType::String, ""),
+            (TokenType::Semicolon, ";"),
+            (TokenType::String, "  say \"hi\"\n    to \"\"you\"\"!"),
+            (TokenType::String, "two\nlines"),
+            (TokenType::Semicolon, ";"),
+            (
+                TokenType::Illegal,
+                "invalid unicode escape `\\u{110000}` at line 12, column 18",
+            ),
+            (TokenType::Semicolon, ";"),
+        ];
+
+        assert_tokens(input, expects);
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let input = r#""Hi, ${name}! ${n + 1}""#;
+
+        let expects = vec![
+            (TokenType::StringHead, "Hi, "),
+            (TokenType::Ident, "name"),
+            (TokenType::StringMiddle, "! "),
+            (TokenType::Ident, "n"),
+            (TokenType::Plus, "+"),
+            (TokenType::IntegerNum, "1"),
+            (TokenType::StringTail, ""),
+        ];
+
+        assert_tokens(input, expects);
+    }
+
+    #[test]
+    fn test_unterminated_string() {
+        let input = "let a = 1;\n  let s = \"abc;\nlet b = 2;";
+
+        let expects = vec![
+            (TokenType::Let, "let"),
+            (TokenType::Ident, "a"),
+            (TokenType::Assignment, "="),
+            (TokenType::IntegerNum, "1"),
+            (TokenType::Semicolon, ";"),
+            (TokenType::Let, "let"),
+            (TokenType::Ident, "s"),
+            (TokenType::Assignment, "="),
+            (
+                TokenType::Illegal,
+                "unterminated string starting at line 2, column 11",
+            ),
+        ];
+
+        assert_tokens(input, expects);
+    }
+
+    #[test]
+    fn test_chars() {
+        let input = r#"let c = 'a' '\n' '\'' '"' '\u{1F600}' b'z' b'\\'; "it's ${'}'}""#;
+
+        let expects = vec![
+            (TokenType::Let, "let"),
+            (TokenType::Ident, "c"),
+            (TokenType::Assignment, "="),
+            (TokenType::Char, "a"),
+            (TokenType::Char, "\n"),
+            (TokenType::Char, "'"),
+            (TokenType::Char, "\""),
+            (TokenType::Char, "\u{1F600}"),
+            (TokenType::Byte, "z"),
+            (TokenType::Byte, "\\"),
+            (TokenType::Semicolon, ";"),
+            (TokenType::StringHead, "it's "),
+            (TokenType::Char, "}"),
+            (TokenType::StringTail, ""),
+        ];
+
+        assert_tokens(input, expects);
+    }
+
+    #[test]
+    fn test_invalid_chars() {
+        let input =
+            "'' 'ab' b'\\u{e9}' '\\u{D800}' b'\\xFF' '\\xFF' '\\x41' b'\\x7F' '\\q'\nlet c = 'x\nlet d = 1;";
+
+        let expects = vec![
+            (TokenType::Illegal, "empty char literal at line 1, column 1"),
+            (
+                TokenType::Illegal,
+                "char literal must contain exactly one character at line 1, column 4",
+            ),
+            (
+                TokenType::Illegal,
+                "byte literal must be an ASCII character at line 1, column 10",
+            ),
+            (
+                TokenType::Illegal,
+                "invalid unicode escape `\\u{D800}` at line 1, column 20",
+            ),
+            (TokenType::Byte, "\u{ff}"),
+            (
+                TokenType::Illegal,
+                "hex escape out of range `\\xFF` at line 1, column 39",
+            ),
+            (TokenType::Char, "A"),
+            (TokenType::Byte, "\x7F"),
+            (
+                TokenType::Illegal,
+                "unknown escape sequence `\\q` at line 1, column 61",
+            ),
+            (TokenType::Let, "let"),
+            (TokenType::Ident, "c"),
+            (TokenType::Assignment, "="),
+            (
+                TokenType::Illegal,
+                "unterminated char literal starting at line 2, column 9",
+            ),
+            (TokenType::Let, "let"),
+            (TokenType::Ident, "d"),
+            (TokenType::Assignment, "="),
+            (TokenType::IntegerNum, "1"),
+            (TokenType::Semicolon, ";"),
+        ];
+
+        assert_tokens(input, expects);
+    }
+
+    #[test]
+    fn test_keywords() {
+        // The identifiers that start with a keyword are not split.
+        let input = "letter format iffy __ whiles let1 none_x r b";
+
+        let expects = vec![
+            (TokenType::Ident, "letter"),
+            (TokenType::Ident, "format"),
+            (TokenType::Ident, "iffy"),
+            (TokenType::Ident, "__"),
+            (TokenType::Ident, "whiles"),
+            (TokenType::Ident, "let1"),
+            (TokenType::Ident, "none_x"),
+            (TokenType::Ident, "r"),
+            (TokenType::Ident, "b"),
+        ];
+        assert_tokens(input, expects);
+
+        for (keyword, token_type) in keywords::KEYWORDS {
+            assert_tokens(keyword, vec![(token_type.clone(), keyword)]);
+        }
+    }
+
+    #[test]
+    fn test_arrow() {
+        let input = "func f() -> int a->b -=1 - >";
+
+        let expects = vec![
+            (TokenType::Func, "func"),
+            (TokenType::Ident, "f"),
+            (TokenType::LeftParen, "("),
+            (TokenType::RightParen, ")"),
+            (TokenType::Arrow, "->"),
+            (TokenType::Ident, "int"),
+            (TokenType::Ident, "a"),
+            (TokenType::Arrow, "->"),
+            (TokenType::Ident, "b"),
+            (TokenType::MinusAssign, "-="),
+            (TokenType::IntegerNum, "1"),
+            (TokenType::Minus, "-"),
+            (TokenType::Greater, ">"),
+        ];
+        assert_tokens(input, expects);
+    }
+
+    #[test]
+    fn test_illegal_chars() {
+        let input = "let a = 1 @ 2;\nlet $b = a#c; \"${x ? y}\" /* open /* nested */";
+
+        let expects = vec![
+            (TokenType::Let, "let"),
+            (TokenType::Ident, "a"),
+            (TokenType::Assignment, "="),
+            (TokenType::IntegerNum, "1"),
+            (TokenType::Illegal, "illegal character `@` at line 1, column 11"),
+            (TokenType::IntegerNum, "2"),
+            (TokenType::Semicolon, ";"),
+            (TokenType::Let, "let"),
+            (TokenType::Illegal, "illegal character `$` at line 2, column 5"),
+            (TokenType::Ident, "b"),
+            (TokenType::Assignment, "="),
+            (TokenType::Ident, "a"),
+            (TokenType::Illegal, "illegal character `#` at line 2, column 11"),
+            (TokenType::Ident, "c"),
+            (TokenType::Semicolon, ";"),
+            (TokenType::StringHead, ""),
+            (TokenType::Ident, "x"),
+            (TokenType::Illegal, "illegal character `?` at line 2, column 20"),
+            (TokenType::Ident, "y"),
+            (TokenType::StringTail, ""),
+            (
+                TokenType::Illegal,
+                "unterminated block comment starting at line 2, column 26",
+            ),
+        ];
+        assert_tokens(input, expects);
+
+        // The errors are collected in the order of the source code.
+        let errors: Vec<(String, usize, usize)> = Lexer::from_source(input)
+            .errors()
+            .iter()
+            .map(|e| (e.message().to_string(), e.line(), e.column()))
+            .collect();
+        let expects = vec![
+            ("illegal character `@`", 1, 11),
+            ("illegal character `$`", 2, 5),
+            ("illegal character `#`", 2, 11),
+            ("illegal character `?`", 2, 20),
+            ("unterminated block comment starting", 2, 26),
+        ];
+        let expects: Vec<(String, usize, usize)> = expects
+            .into_iter()
+            .map(|(m, l, c)| (m.to_string(), l, c))
+            .collect();
+        assert_eq!(errors, expects);
+    }
+
+    #[test]
+    fn test_positions() {
+        // The BOM is skipped, the "\r\n" is a line break and the columns are counted in chars.
+        let input = "\u{FEFF}let s = \"é😀\";\r\n\tlet t = \"a\r\nb\" /* c\r\n */ + 'd';\r\n";
+        let source = input.trim_start_matches('\u{FEFF}');
+
+        let expects = vec![
+            (TokenType::Let, "let", 1, 1, "let"),
+            (TokenType::Ident, "s", 1, 5, "s"),
+            (TokenType::Assignment, "=", 1, 7, "="),
+            (TokenType::String, "é😀", 1, 9, "\"é😀\""),
+            (TokenType::Semicolon, ";", 1, 13, ";"),
+            (TokenType::Let, "let", 2, 2, "let"),
+            (TokenType::Ident, "t", 2, 6, "t"),
+            (TokenType::Assignment, "=", 2, 8, "="),
+            (TokenType::String, "a\nb", 2, 10, "\"a\r\nb\""),
+            (TokenType::Plus, "+", 4, 5, "+"),
+            (TokenType::Char, "d", 4, 7, "'d'"),
+            (TokenType::Semicolon, ";", 4, 10, ";"),
+            (TokenType::Eof, "", 5, 1, ""),
+        ];
+
+        let tokens_iter = Lexer::from_source(input).tokens_iter();
+        for (token_type, literal, line, column, text) in expects {
+            let token = tokens_iter.next().unwrap();
+            assert_eq!(token.token_type(), &token_type);
+            assert_eq!(token.literal(), literal);
+            assert_eq!((token.file_row_number(), token.column()), (line, column));
+            assert_eq!(&source[token.offset()..token.end()], text);
+        }
+    }
+
+    #[test]
+    fn test_interpolation_positions() {
+        let input = "let s = \"a ${x +\n  1} b\";";
+
+        let expects = vec![
+            (TokenType::StringHead, 1, 9, "\"a ${"),
+            (TokenType::Ident, 1, 14, "x"),
+            (TokenType::Plus, 1, 16, "+"),
+            (TokenType::IntegerNum, 2, 3, "1"),
+            (TokenType::StringTail, 2, 4, "} b\""),
+        ];
+
+        let tokens_iter = Lexer::from_source(input).tokens_iter();
+        for _ in 0..3 {
+            tokens_iter.next();
+        }
+        for (token_type, line, column, text) in expects {
+            let token = tokens_iter.next().unwrap();
+            assert_eq!(token.token_type(), &token_type);
+            assert_eq!((token.file_row_number(), token.column()), (line, column));
+            assert_eq!(&input[token.offset()..token.end()], text);
+        }
+    }
+
+    #[test]
+    fn test_trivia() {
+        let input = "let a = 1; // one\n\n/* two */ a;";
+
+        let expects = vec![
+            ("", "let", " "),
+            ("", "a", " "),
+            ("", "=", " "),
+            ("", "1", ""),
+            ("", ";", " // one\n"),
+            ("\n/* two */ ", "a", ""),
+            ("", ";", ""),
+            ("", "", ""),
+        ];
+
+        let tokens_iter = Lexer::from_source(input).with_trivia().tokens_iter();
+        for (leading, text, trailing) in expects {
+            let token = tokens_iter.next().unwrap();
+            let trivia = token.trivia().unwrap();
+            assert_eq!(
+                (trivia.leading(), trivia.text(), trivia.trailing()),
+                (leading, text, trailing)
+            );
+        }
+    }
+
+    #[test]
+    fn test_lossless_tokens() {
+        let inputs = vec![
+            "",
+            "  \n",
+            "\u{FEFF}let s = \"é😀\";\r\n\tlet t = \"a\r\nb\" /* c\r\n */ + 'd';\r\n",
+            "/* a /* nested\n */ */ fn(x) { r\"raw\\\" + \"\"\"\nmulti\"\"\" } /// doc\n",
+            "let s = \"a ${x + \"${y}\"} b\"; let c = b'\\n'; // end",
+            "let s = \"unterminated\n",
+            "\"${",
+            "\"a${x}b",
+            "let s = \"a ${x} b ${y + 1",
+        ];
+        for input in inputs {
+            assert_eq!(lossless_text(Lexer::from_source(input)), input);
+        }
+
+        let file_path = "woc_test_files/hello_world.woc";
+        let source = std::fs::read_to_string(file_path).unwrap();
+        assert_eq!(lossless_text(Lexer::new(file_path).unwrap()), source);
+    }
+
+    #[test]
+    fn test_tokens_source_file() {
+        let file_path = "woc_test_files/hello_world.woc";
+        let tokens_iter = Lexer::new(file_path).unwrap().tokens_iter();
+        let woc_file = tokens_iter.woc_file();
+        assert_eq!(woc_file.get_path(), file_path);
+        assert_eq!(
+            woc_file.source(),
+            std::fs::read_to_string(file_path).unwrap()
+        );
+    }
+
+    // Concatenate the text and the trivia of all the tokens.
+    fn lossless_text(lexer: Lexer) -> String {
+        let tokens_iter = lexer.with_trivia().tokens_iter();
+        let mut text = String::new();
+        loop {
+            let token = tokens_iter.next().unwrap();
+            text.push_str(&token.trivia().unwrap().to_string());
+            if token.is_eof() {
+                break;
+            }
+        }
+        text
+    }
+
+    // Compare the types and literals of the tokens before the EOF.
+    fn assert_tokens(input: &str, expects: Vec<(TokenType, &str)>) {
+        let tokens_iter = Lexer::from_source(input).tokens_iter();
+        let mut tokens = Vec::new();
+        loop {
+            let token = tokens_iter.next().unwrap();
+            if token.is_eof() {
+                break;
+            }
+            tokens.push((token.token_type().clone(), token.literal().to_string()));
+        }
+
+        let expects: Vec<(TokenType, String)> = expects
+            .into_iter()
+            .map(|(t, l)| (t, l.to_string()))
+            .collect();
+        assert_eq!(tokens, expects);
+    }
+
     fn test_equal_tokens(expect: Token, real: Rc<Token>) {
         assert_eq!(expect.token_type(), real.token_type());
         assert_eq!(expect.literal(), real.literal());
         assert_eq!(expect.file_path(), real.file_path());
         assert_eq!(expect.file_row_number(), real.file_row_number());
-        assert_eq!(expect.token_number_in_line(), real.token_number_in_line());
+        assert_eq!(expect.column(), real.column());
     }
 }
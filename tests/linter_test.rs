@@ -0,0 +1,329 @@
+#[cfg(test)]
+mod linter_test {
+    use std::process::Command;
+
+    use woc_lang::linter::linter::{lint_source, LintConfig, RULES};
+    use woc_lang::lsp::json::Json;
+
+    // Lint the source with all the rules, the result is the (rule, line, column) of every problem.
+    fn lint(source: &str) -> Vec<(&'static str, usize, usize)> {
+        lint_with(source, &LintConfig::default())
+    }
+
+    fn lint_with(source: &str, config: &LintConfig) -> Vec<(&'static str, usize, usize)> {
+        lint_source(source, config)
+            .unwrap()
+            .iter()
+            .map(|lint| (lint.rule(), lint.position().0, lint.position().1))
+            .collect()
+    }
+
+    #[test]
+    fn test_unused() {
+        let source = "
+let a = 1;
+let _b = 2;
+func f(x, y) {
+    let c = x;
+    return a;
+}
+f(1, 2);
+";
+        assert_eq!(
+            lint(source),
+            vec![("unused_parameter", 4, 11), ("unused_variable", 5, 9),]
+        );
+    }
+
+    #[test]
+    fn test_shadowing() {
+        let source = "
+let a = 1;
+func f(a) {
+    let b = a;
+    let b = b;
+    func g() {
+        let a = 2;
+        return a + b;
+    }
+    return g();
+}
+f(a);
+";
+        assert_eq!(
+            lint(source),
+            vec![("shadowing", 3, 8), ("shadowing", 7, 13)]
+        );
+
+        // The recursive call doesn't shadow the function itself.
+        let source = "func f(n) { return f(n - 1); } f(1);";
+        assert_eq!(lint(source), vec![]);
+    }
+
+    #[test]
+    fn test_unreachable_code() {
+        let source = "
+func f() {
+    return 1;
+    let a = 2;
+    print(a);
+}
+f();
+return 0;
+print(1);
+";
+        assert_eq!(
+            lint(source),
+            vec![("unreachable_code", 4, 9), ("unreachable_code", 9, 1)]
+        );
+
+        let source = "
+func f(x) {
+    try {
+        throw x;
+        print(x);
+    } catch (_e) {
+        return 1;
+    }
+}
+f(1);
+";
+        assert_eq!(lint(source), vec![("unreachable_code", 5, 9)]);
+    }
+
+    #[test]
+    fn test_constant_condition() {
+        let source = "
+let a = 1;
+if (1 + 2 > 3) { print(a); }
+if (a > 3) { print(a); } else if (!false) { print(a); }
+";
+        assert_eq!(
+            lint(source),
+            vec![("constant_condition", 3, 1), ("constant_condition", 4, 31)]
+        );
+    }
+
+    #[test]
+    fn test_self_comparison() {
+        let source = "
+let a = [1];
+print(a == a, a[0] < a[0], a + 1 != a + 1, a == 1, a + a);
+print(f() == f());
+func f() { return 1; }
+";
+        let lints = lint_source(source, &LintConfig::default()).unwrap();
+        let messages: Vec<String> = lints.iter().map(|lint| lint.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "3:7: `a` is compared with itself, the result is always true [self_comparison]",
+                "3:15: `a[0]` is compared with itself, the result is always false [self_comparison]",
+                "3:28: `(a + 1)` is compared with itself, the result is always false [self_comparison]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_return() {
+        let source = "
+func f(a) {
+    if (a) {
+        return 1;
+    }
+}
+func g(a) {
+    if (a) {
+        return 1;
+    } else if (a > 1) {
+        return 2;
+    } else {
+        return 3;
+    }
+}
+func h(a) {
+    print(a);
+}
+f(1);
+g(1);
+h(1);
+";
+        assert_eq!(lint(source), vec![("missing_return", 2, 6)]);
+
+        // A path that throws doesn't need a return, and the error of the body goes to the catch block.
+        let source = "
+func f(a) {
+    if (a) {
+        return 1;
+    }
+    throw a;
+}
+func g(a) {
+    try {
+        return int(a);
+    } catch (e) {
+        print(e);
+    }
+}
+func h(a) {
+    try {
+        return int(a);
+    } finally {
+        print(a);
+    }
+}
+f(1);
+g(1);
+h(1);
+";
+        assert_eq!(lint(source), vec![("missing_return", 8, 6)]);
+    }
+
+    #[test]
+    fn test_config() {
+        let source = "let a = 1; if (true) { print(2); }";
+
+        let mut config = LintConfig::default();
+        config.set("unused_variable", false).unwrap();
+        assert_eq!(
+            lint_with(source, &config),
+            vec![("constant_condition", 1, 12)]
+        );
+
+        config.set("unused_variable", true).unwrap();
+        config.set("constant_condition", false).unwrap();
+        assert_eq!(lint_with(source, &config), vec![("unused_variable", 1, 5)]);
+
+        assert!(config.set("no_such_rule", false).is_err());
+        assert!(RULES
+            .iter()
+            .all(|(rule, _)| config.is_enabled(rule) || *rule == "constant_condition"));
+    }
+
+    #[test]
+    fn test_inline_comments() {
+        let source = "
+// lint: allow unused_variable
+let a = 1;
+let b = 2; // lint: allow unused_variable, shadowing
+let c = 3;
+if (true) { print(4); } /* lint: allow constant_condition */
+";
+        assert_eq!(
+            lint(source),
+            vec![("unused_variable", 5, 5), ("constant_condition", 6, 1)]
+        );
+
+        let source = "// lint: disable unused_variable\nlet a = 1;";
+        assert_eq!(lint(source), vec![]);
+
+        let mut config = LintConfig::default();
+        config.set("unused_variable", false).unwrap();
+        let source = "// lint: enable unused_variable\nlet a = 1;";
+        assert_eq!(lint_with(source, &config), vec![("unused_variable", 2, 5)]);
+
+        // The unknown rules and commands are reported on the comment, and the rest of the file is still checked.
+        let tests = vec![
+            (
+                "let a = 1;\n// lint: disable no_such_rule\nlet b = a;",
+                vec![("unknown_directive", 2, 1), ("unused_variable", 3, 5)],
+            ),
+            (
+                "let a = 1; // lint: allow unused_variable, typo\n",
+                vec![("unknown_directive", 1, 12)],
+            ),
+            (
+                "// lint: ignore unused_variable\nlet a = 1;",
+                vec![("unknown_directive", 1, 1), ("unused_variable", 2, 5)],
+            ),
+        ];
+        for (source, expected) in tests {
+            assert_eq!(lint(source), expected);
+        }
+
+        let lints = lint_source(
+            "// lint: ignore x\nlet a = 1; // lint: allow typo",
+            &LintConfig::default(),
+        )
+        .unwrap();
+        let messages: Vec<String> = lints.iter().map(|lint| lint.to_string()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "1:1: unknown lint command `ignore` [unknown_directive]",
+                "2:5: variable `a` is never used [unused_variable]",
+                "2:12: unknown lint rule `typo` [unknown_directive]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        assert!(lint_source("let a = ;", &LintConfig::default()).is_err());
+    }
+
+    #[test]
+    fn test_json_output() {
+        let output = Command::new(env!("CARGO_BIN_EXE_woc"))
+            .args(["lint", "woc_test_files/linter/lints.woc", "--json"])
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+
+        let json = Json::parse(&String::from_utf8(output.stdout).unwrap()).unwrap();
+        let lints = json.as_array().unwrap();
+        assert_eq!(lints.len(), 1);
+        assert_eq!(
+            lints[0].get("file").unwrap().as_str(),
+            Some("woc_test_files/linter/lints.woc")
+        );
+        assert_eq!(lints[0].get("line").unwrap().as_i64(), Some(2));
+        assert_eq!(lints[0].get("column").unwrap().as_i64(), Some(5));
+        assert_eq!(
+            lints[0].get("rule").unwrap().as_str(),
+            Some("self_comparison")
+        );
+
+        let output = Command::new(env!("CARGO_BIN_EXE_woc"))
+            .args(["lint", "woc_test_files/linter/lints.woc", "--json"])
+            .args(["--disable", "self_comparison"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "[]\n");
+    }
+
+    #[test]
+    fn test_json_unknown_directive() {
+        let output = Command::new(env!("CARGO_BIN_EXE_woc"))
+            .args([
+                "lint",
+                "woc_test_files/linter/unknown_directive.woc",
+                "--json",
+            ])
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+
+        let json = Json::parse(&String::from_utf8(output.stdout).unwrap()).unwrap();
+        let lints: Vec<_> = json
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|lint| {
+                (
+                    lint.get("rule").unwrap().as_str().unwrap().to_string(),
+                    lint.get("line").unwrap().as_i64().unwrap(),
+                    lint.get("column").unwrap().as_i64().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            lints,
+            vec![
+                ("unknown_directive".to_string(), 1, 12),
+                ("self_comparison".to_string(), 2, 5),
+            ]
+        );
+    }
+}
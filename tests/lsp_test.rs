@@ -0,0 +1,341 @@
+#[cfg(test)]
+mod lsp_test {
+    use std::io::{BufReader, Cursor, Write};
+    use std::process::{Command, Stdio};
+
+    use woc_lang::lsp::analysis::{Analysis, SymbolKind};
+    use woc_lang::lsp::json::Json;
+    use woc_lang::lsp::server::{self, read_message, Server};
+
+    const SOURCE: &str = "/// Adds two numbers.
+func add(x, y) {
+    let sum = x + y;
+    return sum;
+}
+let a = add(1, 2);
+let pi = 3.14;
+let s = \"é${a}\";
+let ok = !(pi > 3);
+let n = int(s) * 2;
+";
+
+    #[test]
+    fn test_json() {
+        let text = r#" {"a": [1, -2.5, 1e3, true, false, null], "b": {"c": "q\"\\\n\u00e9\ud83d\ude00"}, "d": []} "#;
+        let json = Json::parse(text).unwrap();
+
+        assert_eq!(json.get("a").unwrap().as_array().unwrap().len(), 6);
+        assert_eq!(
+            json.get("a").unwrap().as_array().unwrap()[2].as_i64(),
+            Some(1000)
+        );
+        assert_eq!(
+            json.get("b").unwrap().get("c").unwrap().as_str(),
+            Some("q\"\\\né😀")
+        );
+        assert_eq!(
+            json.to_string(),
+            r#"{"a":[1,-2.5,1000,true,false,null],"b":{"c":"q\"\\\né😀"},"d":[]}"#
+        );
+        assert_eq!(Json::parse(&json.to_string()).unwrap(), json);
+
+        for invalid in [
+            "",
+            "[1,",
+            "{\"a\" 1}",
+            "\"open",
+            "tru",
+            "[1] 2",
+            "\"\\ud83d\"",
+        ] {
+            assert!(Json::parse(invalid).is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_symbols() {
+        let analysis = Analysis::new(SOURCE);
+        assert!(analysis.diagnostics().is_empty());
+
+        // The uses of a name refer to its definition.
+        let add = analysis.symbol_at(6, 9).unwrap();
+        assert_eq!(add.kind(), SymbolKind::Function);
+        assert_eq!(add.position(), Some((2, 6)));
+        assert_eq!(add.signature(), "func add(x, y)");
+        assert_eq!(add.doc(), Some("Adds two numbers."));
+
+        let x = analysis.symbol_at(3, 15).unwrap();
+        assert_eq!(
+            (x.kind(), x.position()),
+            (SymbolKind::Parameter, Some((2, 10)))
+        );
+        // The position right after a name is accepted.
+        let sum = analysis.symbol_at(4, 15).unwrap();
+        assert_eq!(sum.position(), Some((3, 9)));
+        // The names in the interpolated strings are resolved.
+        assert_eq!(analysis.symbol_at(8, 13).unwrap().position(), Some((6, 5)));
+        assert_eq!(
+            analysis.symbol_at(10, 9).unwrap().kind(),
+            SymbolKind::Builtin
+        );
+        assert!(analysis.symbol_at(7, 12).is_none());
+
+        let types: Vec<String> = analysis
+            .symbols()
+            .iter()
+            .filter(|s| s.kind() == SymbolKind::Variable)
+            .map(|s| s.signature())
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                "let sum: unknown",
+                "let a: unknown",
+                "let pi: float",
+                "let s: string",
+                "let ok: boolean",
+                "let n: integer",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_outline_and_diagnostics() {
+        let source = "func outer() {
+    func inner(v) { return v; }
+    let b = = 1;
+    return inner(c);
+}
+let d = e + 1;
+let e = 2;
+let f = 1 @ 2;
+";
+        let analysis = Analysis::new(source);
+
+        let outline = analysis.outline();
+        assert_eq!(outline.len(), 1);
+        assert_eq!(outline[0].symbol().name(), "outer");
+        assert_eq!(
+            outline[0].children()[0].symbol().signature(),
+            "func inner(v)"
+        );
+
+        let diagnostics: Vec<_> = analysis
+            .diagnostics()
+            .iter()
+            .map(|d| (d.message(), d.position(), d.end()))
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                (
+                    "no prefix parse function for `Assignment` found",
+                    (3, 5),
+                    (3, 17)
+                ),
+                ("undefined variable `c`", (4, 18), (4, 19)),
+                (
+                    "variable `e` is used before its definition",
+                    (6, 9),
+                    (6, 10)
+                ),
+                ("illegal character `@`", (8, 11), (8, 12)),
+            ]
+        );
+    }
+
+    // Frame the messages of a client script.
+    fn script(messages: &[&str]) -> Vec<u8> {
+        let mut input = Vec::new();
+        for message in messages {
+            write!(
+                input,
+                "Content-Length: {}\r\n\r\n{}",
+                message.len(),
+                message
+            )
+            .unwrap();
+        }
+        input
+    }
+
+    fn read_all(output: &[u8]) -> Vec<Json> {
+        let mut reader = BufReader::new(output);
+        let mut messages = Vec::new();
+        while let Some(body) = read_message(&mut reader).unwrap() {
+            messages.push(Json::parse(&body).unwrap());
+        }
+        messages
+    }
+
+    fn open_message(source: &str) -> String {
+        Json::object(vec![
+            ("jsonrpc", "2.0".into()),
+            ("method", "textDocument/didOpen".into()),
+            (
+                "params",
+                Json::object(vec![(
+                    "textDocument",
+                    Json::object(vec![
+                        ("uri", "file:///a.woc".into()),
+                        ("languageId", "woc".into()),
+                        ("version", 1usize.into()),
+                        ("text", source.into()),
+                    ]),
+                )]),
+            ),
+        ])
+        .to_string()
+    }
+
+    fn position_request(id: usize, method: &str, line: usize, character: usize) -> String {
+        format!(
+            r#"{{"jsonrpc":"2.0","id":{},"method":"{}","params":{{"textDocument":{{"uri":"file:///a.woc"}},"position":{{"line":{},"character":{}}}}}}}"#,
+            id, method, line, character
+        )
+    }
+
+    #[test]
+    fn test_session() {
+        let open = open_message(SOURCE);
+        let definition = position_request(2, "textDocument/definition", 5, 9);
+        let hover = position_request(3, "textDocument/hover", 5, 4);
+        let hover_none = position_request(4, "textDocument/hover", 5, 14);
+        let change = r#"{"jsonrpc":"2.0","method":"textDocument/didChange","params":{"textDocument":{"uri":"file:///a.woc","version":2},"contentChanges":[{"text":"let é = x;\n"}]}}"#;
+        let input = script(&[
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"capabilities":{}}}"#,
+            r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#,
+            &open,
+            &definition,
+            &hover,
+            &hover_none,
+            r#"{"jsonrpc":"2.0","id":5,"method":"textDocument/documentSymbol","params":{"textDocument":{"uri":"file:///a.woc"}}}"#,
+            r#"{"jsonrpc":"2.0","id":6,"method":"textDocument/completion","params":{"textDocument":{"uri":"file:///a.woc"},"position":{"line":0,"character":0}}}"#,
+            change,
+            r#"{"jsonrpc":"2.0","id":7,"method":"textDocument/rename","params":{}}"#,
+            "{not json",
+            r#"{"jsonrpc":"2.0","id":8,"method":"shutdown"}"#,
+            r#"{"jsonrpc":"2.0","method":"exit"}"#,
+        ]);
+
+        let mut output = Vec::new();
+        server::run(&mut Cursor::new(input), &mut output).unwrap();
+        let messages = read_all(&output);
+        assert_eq!(messages.len(), 11);
+
+        let capabilities = messages[0]
+            .get("result")
+            .unwrap()
+            .get("capabilities")
+            .unwrap();
+        assert_eq!(capabilities.get("hoverProvider"), Some(&Json::Bool(true)));
+
+        assert_eq!(
+            messages[1].to_string(),
+            r#"{"jsonrpc":"2.0","method":"textDocument/publishDiagnostics","params":{"uri":"file:///a.woc","diagnostics":[]}}"#
+        );
+        assert_eq!(
+            messages[2].to_string(),
+            r#"{"jsonrpc":"2.0","id":2,"result":{"uri":"file:///a.woc","range":{"start":{"line":1,"character":5},"end":{"line":1,"character":8}}}}"#
+        );
+        assert_eq!(
+            messages[3].get("result").unwrap().to_string(),
+            r#"{"contents":{"kind":"markdown","value":"```woc\nlet a: unknown\n```"}}"#
+        );
+        assert!(messages[4].get("result").unwrap().is_null());
+
+        let symbols = messages[5].get("result").unwrap().as_array().unwrap();
+        assert_eq!(
+            symbols[0].to_string(),
+            r#"{"name":"add","detail":"func add(x, y)","kind":12,"range":{"start":{"line":1,"character":5},"end":{"line":1,"character":8}},"selectionRange":{"start":{"line":1,"character":5},"end":{"line":1,"character":8}},"children":[]}"#
+        );
+
+        let labels: Vec<&str> = messages[6]
+            .get("result")
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item.get("label").unwrap().as_str().unwrap())
+            .collect();
+        for label in ["while", "func", "int", "str", "add", "x", "sum", "pi"] {
+            assert!(labels.contains(&label), "{}", label);
+        }
+        assert_eq!(labels.iter().filter(|l| **l == "add").count(), 1);
+
+        // The columns are counted in UTF-16 code units.
+        assert_eq!(
+            messages[7].to_string(),
+            r#"{"jsonrpc":"2.0","method":"textDocument/publishDiagnostics","params":{"uri":"file:///a.woc","diagnostics":[{"range":{"start":{"line":0,"character":8},"end":{"line":0,"character":9}},"severity":1,"source":"woc","message":"undefined variable `x`"}]}}"#
+        );
+        assert_eq!(
+            messages[8]
+                .get("error")
+                .unwrap()
+                .get("code")
+                .unwrap()
+                .as_i64(),
+            Some(-32601)
+        );
+        assert_eq!(
+            messages[9]
+                .get("error")
+                .unwrap()
+                .get("code")
+                .unwrap()
+                .as_i64(),
+            Some(-32700)
+        );
+        assert!(messages[9].get("id").unwrap().is_null());
+        assert!(messages[10].get("result").unwrap().is_null());
+    }
+
+    #[test]
+    fn test_exit_without_shutdown() {
+        let mut server = Server::new();
+        let exit = Json::parse(r#"{"jsonrpc":"2.0","method":"exit"}"#).unwrap();
+        assert!(server.handle(&exit).is_empty());
+        assert!(server.is_exited() && !server.is_shutdown());
+
+        let input = script(&[r#"{"jsonrpc":"2.0","method":"exit"}"#]);
+        assert!(server::run(&mut Cursor::new(input), &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_stdio() {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_woc"))
+            .arg("lsp")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        let open = open_message("let a = @;\n");
+        let input = script(&[
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#,
+            &open,
+            r#"{"jsonrpc":"2.0","id":2,"method":"shutdown"}"#,
+            r#"{"jsonrpc":"2.0","method":"exit"}"#,
+        ]);
+        child.stdin.take().unwrap().write_all(&input).unwrap();
+
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success());
+
+        // Nothing but the protocol messages is written to stdout.
+        let messages = read_all(&output.stdout);
+        assert_eq!(messages.len(), 3);
+        let diagnostics = messages[1]
+            .get("params")
+            .unwrap()
+            .get("diagnostics")
+            .unwrap();
+        assert_eq!(
+            diagnostics.as_array().unwrap()[0]
+                .get("message")
+                .unwrap()
+                .as_str(),
+            Some("illegal character `@`")
+        );
+    }
+}
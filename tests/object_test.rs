@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod object_test {
+    use std::cmp::Ordering;
+
+    use woc_lang::object::object::{Array, BaseValue, Object, Str, Value};
+
+    fn int(v: i64) -> Object {
+        Object::Base(BaseValue::Integer(Value::new(v)))
+    }
+
+    fn float(v: f64) -> Object {
+        Object::Base(BaseValue::Float(Value::new(v)))
+    }
+
+    fn str(v: &str) -> Object {
+        Object::Str(Str::new(v.to_string()))
+    }
+
+    fn arr(elements: Vec<Object>) -> Object {
+        Object::Array(Array::new(elements))
+    }
+
+    #[test]
+    fn test_equality() {
+        assert_eq!(int(1), float(1.0));
+        assert_eq!(Object::Null, Object::Null);
+        assert_eq!(
+            arr(vec![int(1), arr(vec![str("a")])]),
+            arr(vec![float(1.0), arr(vec![str("a")])])
+        );
+        assert_eq!(Object::Return(Box::new(int(2))), int(2));
+        assert_ne!(int(1), str("1"));
+        assert_ne!(Object::Null, int(0));
+        assert_ne!(float(f64::NAN), float(f64::NAN));
+        assert_ne!(arr(vec![int(1)]), arr(vec![int(1), int(2)]));
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert_eq!(int(1).partial_cmp(&float(1.5)), Some(Ordering::Less));
+        assert_eq!(str("b").partial_cmp(&str("ab")), Some(Ordering::Greater));
+        assert_eq!(
+            arr(vec![int(1), int(2)]).partial_cmp(&arr(vec![int(1), int(2), int(0)])),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            Object::Null.partial_cmp(&Object::Null),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(Object::Null.partial_cmp(&int(0)), None);
+        assert_eq!(str("1").partial_cmp(&int(1)), None);
+    }
+
+    #[test]
+    fn test_sort_and_dedup() {
+        let mut values = vec![int(3), float(1.5), int(1), float(3.0), int(2), int(1)];
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        values.dedup();
+        assert_eq!(values, vec![int(1), float(1.5), int(2), int(3)]);
+
+        let mut values = vec![str("pear"), str("apple"), str("fig"), str("apple")];
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        values.dedup();
+        assert_eq!(values, vec![str("apple"), str("fig"), str("pear")]);
+
+        let mut values = vec![
+            arr(vec![int(2)]),
+            arr(vec![int(1), int(5)]),
+            arr(vec![int(1)]),
+            arr(vec![int(2)]),
+        ];
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        values.dedup();
+        assert_eq!(
+            values,
+            vec![
+                arr(vec![int(1)]),
+                arr(vec![int(1), int(5)]),
+                arr(vec![int(2)])
+            ]
+        );
+    }
+}
@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod optimizer_test {
+    use woc_lang::{
+        ast_v2::Node, compiler::compiler::Compiler, evaluator_v2::evaluator::Evaluator,
+        object::object::Object, optimizer::optimizer, parser_v2::parser::Parser,
+        resolver::resolver::Resolver, vm::vm::Vm,
+    };
+
+    #[test]
+    fn test_fold_constant() {
+        let tests = vec![
+            ("2 * 3 + 1;", "7"),
+            ("1 + 2.5;", "3.5"),
+            ("10 / 3;", "3"),
+            ("-(2 + 3);", "-5"),
+            ("!(1 < 2);", "false"),
+            ("!!0 && true;", "false"),
+            ("1 && 1.5;", "1.5"),
+            ("0 || true;", "true"),
+            ("let x = (1 + 2) * 3;", "let x = 9;"),
+            ("x + 2 * 3;", "(x + 6)"),
+            ("add(1 + 1, [2 * 2]);", "add(2, [4])"),
+            ("func f() { return 4 / 2; }", "func f() {return 2;}"),
+            // The expressions that fail or don't produce a literal are kept.
+            ("1 / 0;", "(1 / 0)"),
+            ("9223372036854775807 + 1;", "(9223372036854775807 + 1)"),
+            ("true + 1;", "(true + 1)"),
+            ("\"a\" == \"a\";", "(a == a)"),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(optimize(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_prune_branch() {
+        let tests = vec![
+            ("if (true) { 1; } else { 2; }", "{1}"),
+            ("if (1 > 2) { 1; } else { 2; }", "{2}"),
+            ("if (0) { 1; }", "{}"),
+            ("if (false) { 1; } else if (2.5) { 2; } else { 3; }", "{2}"),
+            (
+                "if (x) { 1; } else if (true) { 2; } else { 3; }",
+                "if x {1} else {2}",
+            ),
+            ("if (x) { 1; } else if (false) { 2; }", "if x {1} else {}"),
+            (
+                "let a = if (1 == 1) { 5; } else { 6; };",
+                "let a = if true {5};",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(optimize(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_remove_after_return() {
+        let tests = vec![
+            ("{ 1; return 2; 3; let x = 4; }", "{1 return 2;}"),
+            ("func f() { return 1; f(); }", "func f() {return 1;}"),
+            (
+                "if (x) { return 1; 2; } else { return 3; 4; }",
+                "if x {return 1;} else {return 3;}",
+            ),
+            // The top-level statements are all executed.
+            ("return 1; 2;", "return 1; 2"),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(optimize(input), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_same_result() {
+        let tests = vec![
+            "let a = 2 * 3 + 1; a;",
+            "if (1 > 2) { 10; } else { 20; return 30; 40; }",
+            "if (10 > 1) { if (10 > 1) { return 30; } return 1; }",
+            "if (1 == 2) { 10; } else if ( 2 > 1 ) { 20; } else { 30; }",
+            "if (false) { 10; }",
+            "!!0 && true;",
+            "5.5 / 5 + -1;",
+            "
+            func abs(a) { if ( a < 0 ) { return -1 * a; } else { return a; } 0; }
+            let a = abs(-800 - 21);
+            a;
+            ",
+            "
+            func counter(x) {
+                if (x > 100 - 1) {
+                    true;
+                } else {
+                    counter(x + 1 * 1);
+                }
+            }
+            counter(0);
+            ",
+        ];
+
+        for input in tests {
+            let programs = Resolver::new()
+                .resolve(&Parser::from_source(input).programs())
+                .unwrap();
+            let optimized = optimizer::optimize(&programs);
+
+            let expected = eval(&programs).to_string();
+            assert_eq!(eval(&optimized).to_string(), expected, "input: {}", input);
+
+            let bytecode = Compiler::new().compile(&optimized);
+            let got = Vm::new(bytecode).run().unwrap();
+            assert_eq!(got.to_string(), expected, "input: {}", input);
+        }
+    }
+
+    fn optimize(input: &str) -> String {
+        optimizer::optimize(&Parser::from_source(input).programs())
+            .iter()
+            .map(|node| node.to_string())
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    fn eval(programs: &[Node]) -> Object {
+        let evaluator = Evaluator::new(None);
+
+        let mut result = Object::Null;
+        for node in programs.iter() {
+            result = evaluator.eval(node);
+        }
+        result
+    }
+}
@@ -1,12 +1,14 @@
 #[cfg(test)]
 mod parser_test {
+    use woc_lang::ast_v2::types::Type;
+    use woc_lang::ast_v2::{Node, Statement};
     use woc_lang::parser_v2::parser::Parser;
 
     #[test]
     fn test_parse_arr_exp() {
-        let path = "woc_test_files/parser/test_parsing_logic.woc";
+        let path = "woc_test_files/parser/test_parse_arr_exp.woc";
 
-        let parser = Parser::new(path);
+        let parser = Parser::new(path).unwrap();
         let programs = parser.programs();
 
         assert_eq!(programs.len(), 3);
@@ -25,7 +27,7 @@ mod parser_test {
     fn test_string() {
         let path = "woc_test_files/parser/test_string.woc";
 
-        let parser = Parser::new(path);
+        let parser = Parser::new(path).unwrap();
         let programs = parser.programs();
 
         assert_eq!(programs.len(), 2);
@@ -37,6 +39,69 @@ mod parser_test {
         assert_eq!(identifier_exp.to_string(), "str");
     }
 
+    #[test]
+    fn test_unreadable_file() {
+        let err = Parser::new("woc_test_files/parser/missing.woc")
+            .err()
+            .unwrap();
+        assert!(err.starts_with("failed to read 'woc_test_files/parser/missing.woc'"));
+
+        let err = Parser::new("woc_test_files/parser/test_invalid_utf8.woc")
+            .err()
+            .unwrap();
+        assert!(err.contains("valid UTF-8"), "{}", err);
+    }
+
+    #[test]
+    fn test_doc_comment() {
+        let input = "
+            /// The answer.
+            let answer = 42;
+
+            // A normal comment.
+            let x = /* the value */ 1;
+
+            /// Add two numbers.
+            /// It returns the sum.
+            func add(a, b) {
+                /// The result.
+                let c = a + b;
+                return c;
+            }
+        ";
+
+        let programs = Parser::from_source(input).programs();
+        assert_eq!(programs.len(), 3);
+
+        let docs: Vec<Option<String>> = programs
+            .iter()
+            .map(|node| match node {
+                Node::Stmt(Statement::Let(let_stmt)) => let_stmt.doc().map(|d| d.to_string()),
+                Node::Stmt(Statement::Func(func_stmt)) => func_stmt.doc().map(|d| d.to_string()),
+                node => panic!("the node is not a declaration, got={:?}", node),
+            })
+            .collect();
+        assert_eq!(
+            docs,
+            vec![
+                Some("The answer.".to_string()),
+                None,
+                Some("Add two numbers.\nIt returns the sum.".to_string()),
+            ]
+        );
+
+        assert_eq!(programs[1].to_string(), "let x = 1;");
+
+        let body = match &programs[2] {
+            Node::Stmt(Statement::Func(func_stmt)) => func_stmt.body().statements().unwrap(),
+            _ => unreachable!(),
+        };
+        match body[0].as_ref() {
+            Node::Stmt(Statement::Let(let_stmt)) => assert_eq!(let_stmt.doc(), Some("The result.")),
+            node => panic!("the node is not a let statement, got={:?}", node),
+        }
+    }
+
     #[test]
     fn test_string_exp() {
         let input = "
@@ -47,12 +112,12 @@ mod parser_test {
             \"hello
             world\"
 
-            \"hello\\world\"
+            \"hello\\\\world\"
 
             \"hello 666 world\"
         ";
 
-        let parser = Parser::new(input);
+        let parser = Parser::from_source(input);
         let programs = parser.programs();
 
         assert_eq!(programs.len(), 5);
@@ -80,7 +145,7 @@ mod parser_test {
             add(5, 5);
         ";
 
-        let parser = Parser::new(input);
+        let parser = Parser::from_source(input);
         let programs = parser.programs();
 
         assert_eq!(programs.len(), 2);
@@ -96,7 +161,7 @@ mod parser_test {
     fn test_parse_multi_prefix() {
         let input = "!!true;";
 
-        let parser = Parser::new(input);
+        let parser = Parser::from_source(input);
         let programs = parser.programs();
 
         assert_eq!(programs.len(), 1);
@@ -105,11 +170,210 @@ mod parser_test {
         assert_eq!(multi_prefix.to_string(), "!!true");
     }
 
+    #[test]
+    fn test_number_literals() {
+        let tests = vec![
+            ("1_000;", "1000"),
+            ("0xFF;", "255"),
+            ("0xfe + 1;", "(254 + 1)"),
+            ("-0x10;", "-16"),
+            ("0o17;", "15"),
+            ("0b1010_1010;", "170"),
+            ("1e-9;", "0.000000001"),
+            ("2.5E+3;", "2500"),
+            ("1_000.5e1;", "10005"),
+            (".5;", "0.5"),
+            ("let x = .25;", "let x = 0.25;"),
+        ];
+
+        for (input, expected) in tests {
+            let parser = Parser::from_source(input);
+            assert!(parser.errors().is_empty(), "input: {}", input);
+
+            let programs = parser.programs();
+            assert_eq!(programs.len(), 1, "input: {}", input);
+            assert_eq!(programs[0].to_string(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_malformed_number() {
+        let tests = vec![
+            (
+                "1abc;",
+                "invalid suffix `abc` for the number literal `1abc`",
+            ),
+            ("0x;", "missing digits in the number literal `0x`"),
+            ("0b102;", "invalid digit `2` for a base 2 literal `0b102`"),
+            ("0o8;", "invalid digit `8` for a base 8 literal `0o8`"),
+            (
+                "99999999999999999999;",
+                "the integer literal `99999999999999999999` is out of range",
+            ),
+            (
+                "1e;",
+                "missing digits in the exponent of the float literal `1e`",
+            ),
+            ("1.5x;", "invalid suffix `x` for the number literal `1.5x`"),
+            ("1e999;", "the float literal `1e999` is out of range"),
+            (
+                "add(1, 2z);",
+                "invalid suffix `z` for the number literal `2z`",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let parser = Parser::from_source(input);
+            let errors = parser.errors();
+            assert_eq!(errors.len(), 1, "input: {}", input);
+            assert!(errors[0].contains(expected), "got: {}", errors[0]);
+        }
+    }
+
+    #[test]
+    fn test_string_errors() {
+        let parser = Parser::from_source("\"bad \\u{zz}\";\nlet s = \"abc;");
+        let errors = parser.errors();
+
+        assert!(errors[0].contains("invalid unicode escape `\\u{zz}` at line 1, column 6"));
+        assert!(errors[1].contains("unterminated string starting at line 2, column 9"));
+    }
+
+    #[test]
+    fn test_lex_errors() {
+        let parser = Parser::from_source("let a = 1 @ 2;\nlet b = \"${a $}\";\nlet c = 3;");
+
+        let lex_errors: Vec<String> = parser.lex_errors().iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            lex_errors,
+            vec![
+                "illegal character `@` at line 1, column 11",
+                "illegal character `$` at line 2, column 14",
+            ]
+        );
+
+        // The lexer errors come first, and the parser goes on after the illegal tokens.
+        let errors = parser.errors();
+        assert_eq!(errors[..2], lex_errors[..]);
+        assert_eq!(parser.programs().last().unwrap().to_string(), "let c = 3;");
+    }
+
+    #[test]
+    fn test_error_recovery() {
+        let input = "let a = 1 @ 2;
+let = 5;
+func f(x) {
+    let y = ;
+    return x + 1;
+}
+let b = (1 + ;
+if (b { 1; }
+func g(a, b { return a; }
+if (x { 1; } else if (y) { 2; } else { 3; }
+try { 1; } catch (e { 2; } finally { 3; }
+let c = f(2);";
+
+        // Every mistake is reported once, the illegal char is reported by the lexer,
+        // and the blocks of a broken statement are skipped with it, including its `else`, `catch`
+        // and `finally` blocks.
+        let parser = Parser::from_source(input);
+        let errors = parser.errors();
+        assert_eq!(errors.len(), 8, "errors: {:?}", errors);
+        assert!(errors[0].contains("illegal character `@` at line 1, column 11"));
+        assert!(errors[1].contains("expected next token to be `Ident`, got `Assignment` instead"));
+        assert!(errors[2].contains("no prefix parse function for `Semicolon` found"));
+        assert!(errors[3].contains("no prefix parse function for `Semicolon` found"));
+        for error in errors[4..].iter() {
+            assert!(
+                error.contains("expected next token to be `RightParen`, got `LeftBrace` instead")
+            );
+        }
+
+        // The broken statements are kept as the error statements with their source text.
+        let expects = vec![
+            ("let a = 1 @ 2;", Some((1, 1))),
+            ("let = 5;", Some((2, 1))),
+            (
+                "func f(x) {error(no prefix parse function for `Semicolon` found) let y = ; return (x + 1);}",
+                None,
+            ),
+            ("let b = (1 + ;", Some((7, 1))),
+            ("if (b { 1; }", Some((8, 1))),
+            ("func g(a, b { return a; }", Some((9, 1))),
+            (
+                "if (x { 1; } else if (y) { 2; } else { 3; }",
+                Some((10, 1)),
+            ),
+            ("try { 1; } catch (e { 2; } finally { 3; }", Some((11, 1))),
+            ("let c = f(2);", None),
+        ];
+        let programs = parser.programs();
+        assert_eq!(programs.len(), expects.len());
+        for (node, (text, position)) in programs.iter().zip(expects) {
+            assert_eq!(node.to_string(), text);
+            match node {
+                Node::Stmt(Statement::Error(error_stmt)) => {
+                    assert_eq!(Some(error_stmt.position()), position)
+                }
+                _ => assert_eq!(position, None),
+            }
+        }
+    }
+
+    #[test]
+    fn test_interpolated_string() {
+        let tests = vec![
+            ("\"Hello, ${name}!\";", "Hello, ${name}!"),
+            ("\"${a + b * 2}${c}\";", "${(a + (b * 2))}${c}"),
+            (
+                "\"${f(1, \"}\")} and ${if (x) { 1; } else { 2; }}\";",
+                "${f(1, })} and ${if x {1} else {2}}",
+            ),
+            ("\"a${\"b${c}\"}\";", "a${b${c}}"),
+        ];
+
+        for (input, expected) in tests {
+            let parser = Parser::from_source(input);
+            assert!(parser.errors().is_empty(), "input: {}", input);
+
+            let programs = parser.programs();
+            assert_eq!(programs.len(), 1, "input: {}", input);
+            assert_eq!(programs[0].to_string(), expected, "input: {}", input);
+        }
+
+        let parser = Parser::from_source("\"a ${} b\";");
+        assert!(parser.errors()[0].contains("empty interpolation `${}` at line 1, column 4"));
+    }
+
+    #[test]
+    fn test_char_and_byte() {
+        let tests = vec![
+            ("'a';", "'a'"),
+            ("'\\n' == '\\'';", "('\\n' == '\\'')"),
+            ("let b = b'a';", "let b = b'a';"),
+            ("b'\\xFF' == b'\\x41';", "(b'\\xff' == b'A')"),
+            ("int('\\u{41}') + 1;", "(int('A') + 1)"),
+        ];
+
+        for (input, expected) in tests {
+            let parser = Parser::from_source(input);
+            assert!(parser.errors().is_empty(), "input: {}", input);
+
+            let programs = parser.programs();
+            assert_eq!(programs.len(), 1, "input: {}", input);
+            assert_eq!(programs[0].to_string(), expected, "input: {}", input);
+        }
+
+        let parser = Parser::from_source("let c = 'ab';");
+        assert!(parser.errors()[0]
+            .contains("char literal must contain exactly one character at line 1, column 9"));
+    }
+
     #[test]
     fn test_parse_let_stmt() {
         let input = "let x = 822;";
 
-        let parser = Parser::new(input);
+        let parser = Parser::from_source(input);
         let programs = parser.programs();
 
         assert_eq!(programs.len(), 1);
@@ -118,11 +382,47 @@ mod parser_test {
         assert_eq!(let_stmt.to_string(), "let x = 822;");
     }
 
+    #[test]
+    fn test_parse_type_annotations() {
+        let tests = [
+            ("let x: int = 822;", "let x: int = 822;"),
+            ("let a:[[int]]=[[1]];", "let a: [[int]] = [[1]];"),
+            (
+                "func add(x: int, y) -> float { return x + y; }",
+                "func add(x: int, y) -> float {return (x + y);}",
+            ),
+            ("func f() -> any {}", "func f() -> any {}"),
+        ];
+        for (input, expected) in tests {
+            let parser = Parser::from_source(input);
+            assert!(
+                parser.errors().is_empty(),
+                "{}: {:?}",
+                input,
+                parser.errors()
+            );
+            assert_eq!(parser.programs()[0].to_string(), expected);
+        }
+
+        let parser = Parser::from_source("func add(x: int, y) -> float {}");
+        let programs = parser.programs();
+        let Node::Stmt(Statement::Func(func_stmt)) = &programs[0] else {
+            panic!("expected a function statement");
+        };
+        assert_eq!(func_stmt.param_types(), &[Some(Type::Int), None]);
+        assert_eq!(func_stmt.return_type(), Some(&Type::Float));
+
+        let parser = Parser::from_source("let x: number = 1;");
+        assert!(parser.errors()[0].contains("unknown type `number`"));
+        let parser = Parser::from_source("func f(x: ) {}");
+        assert!(parser.errors()[0].contains("expected a type"));
+    }
+
     #[test]
     fn test_parse_return_stmt() {
         let input = "return 822;";
 
-        let parser = Parser::new(input);
+        let parser = Parser::from_source(input);
         let programs = parser.programs();
 
         assert_eq!(programs.len(), 1);
@@ -131,11 +431,67 @@ mod parser_test {
         assert_eq!(return_stmt.to_string(), "return 822;");
     }
 
+    #[test]
+    fn test_parse_throw_and_try_stmt() {
+        let tests = [
+            ("throw error(kind, message);", "throw error(kind, message);"),
+            ("throw 1 + 2;", "throw (1 + 2);"),
+            (
+                "try { f(); } catch (e) { return e; }",
+                "try {f()} catch (e) {return e;}",
+            ),
+            ("try { f(); } finally { g(); }", "try {f()} finally {g()}"),
+            (
+                "try {} catch (err) {} finally { g(); }",
+                "try {} catch (err) {} finally {g()}",
+            ),
+        ];
+        for (input, expected) in tests {
+            let parser = Parser::from_source(input);
+            assert!(
+                parser.errors().is_empty(),
+                "{}: {:?}",
+                input,
+                parser.errors()
+            );
+            assert_eq!(parser.programs()[0].to_string(), expected);
+        }
+
+        let parser = Parser::from_source("try { f(); } catch (e) {}");
+        let programs = parser.programs();
+        let Node::Stmt(Statement::Try(try_stmt)) = &programs[0] else {
+            panic!("expected a try statement");
+        };
+        let (ident, _) = try_stmt.catch().unwrap();
+        assert_eq!((ident.value(), ident.position()), ("e", Some((1, 21))));
+        assert!(try_stmt.finally().is_none());
+
+        let errors = [
+            (
+                "try { f(); }",
+                "expected `catch` or `finally` after the `try` block",
+            ),
+            (
+                "try { f(); } catch { g(); }",
+                "expected next token to be `LeftParen`",
+            ),
+            ("throw;", "no prefix parse function for `Semicolon` found"),
+        ];
+        for (input, expected) in errors {
+            let parser = Parser::from_source(input);
+            assert!(
+                parser.errors()[0].contains(expected),
+                "{:?}",
+                parser.errors()
+            );
+        }
+    }
+
     #[test]
     fn test_parse_identifier_exp() {
         let input = "foobar;";
 
-        let parser = Parser::new(input);
+        let parser = Parser::from_source(input);
         let programs = parser.programs();
 
         assert_eq!(programs.len(), 1);
@@ -148,7 +504,7 @@ mod parser_test {
     fn test_parse_prefix_exp() {
         let input = "!822; -x;";
 
-        let parser = Parser::new(input);
+        let parser = Parser::from_source(input);
         let programs = parser.programs();
 
         assert_eq!(programs.len(), 2);
@@ -164,7 +520,7 @@ mod parser_test {
     fn test_parse_group_exp() {
         let input = "822;";
 
-        let parser = Parser::new(input);
+        let parser = Parser::from_source(input);
         let programs = parser.programs();
 
         assert_eq!(programs.len(), 1);
@@ -177,7 +533,7 @@ mod parser_test {
     fn test_parse_boolean_exp() {
         let input = "true; false;";
 
-        let parser = Parser::new(input);
+        let parser = Parser::from_source(input);
         let programs = parser.programs();
 
         assert_eq!(programs.len(), 2);
@@ -206,7 +562,7 @@ mod parser_test {
             true || false;
         ";
 
-        let parser = Parser::new(input);
+        let parser = Parser::from_source(input);
         let programs = parser.programs();
 
         assert_eq!(programs.len(), 12);
@@ -258,7 +614,7 @@ mod parser_test {
             }
         ";
 
-        let parser = Parser::new(input);
+        let parser = Parser::from_source(input);
         let programs = parser.programs();
 
         assert_eq!(programs.len(), 1);
@@ -274,7 +630,7 @@ mod parser_test {
     fn test_func_stmt() {
         let input = "func add(x, y) { return x + y; }";
 
-        let parser = Parser::new(input);
+        let parser = Parser::from_source(input);
         let programs = parser.programs();
 
         assert_eq!(programs.len(), 1);
@@ -287,7 +643,7 @@ mod parser_test {
     fn test_parse_if_exp() {
         let input = "if (x < y) { return x; }";
 
-        let parser = Parser::new(input);
+        let parser = Parser::from_source(input);
         let programs = parser.programs();
 
         assert_eq!(programs.len(), 1);
@@ -300,7 +656,7 @@ mod parser_test {
     fn test_parse_if_else_exp() {
         let input = "if (x < y) { return x; } else if (x == y) { return x; } else { return x; }";
 
-        let parser = Parser::new(input);
+        let parser = Parser::from_source(input);
         let programs = parser.programs();
 
         assert_eq!(programs.len(), 1);
@@ -316,7 +672,7 @@ mod parser_test {
     fn test_parse_call_exp() {
         let input = "add(5, 5);";
 
-        let parser = Parser::new(input);
+        let parser = Parser::from_source(input);
         let programs = parser.programs();
 
         assert_eq!(programs.len(), 1);
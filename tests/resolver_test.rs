@@ -0,0 +1,133 @@
+#[cfg(test)]
+mod resolver_test {
+    use woc_lang::{
+        ast_v2::{Expression, Node, Statement},
+        parser_v2::parser::Parser,
+        resolver::resolver::Resolver,
+    };
+
+    #[test]
+    fn test_resolve_slot() {
+        let programs = resolve(
+            "
+            let a = 1;
+            let b = 2;
+            func add(x, y) {
+                let z = x + y;
+                z + b;
+            }
+            let a = 3;
+            a;
+            ",
+        )
+        .unwrap();
+
+        // The redefined variable takes the same slot.
+        assert_eq!(let_slot(&programs[0]), Some((0, 0)));
+        assert_eq!(let_slot(&programs[1]), Some((0, 1)));
+        assert_eq!(let_slot(&programs[3]), Some((0, 0)));
+        assert_eq!(ident_slot(&programs[4]), Some((0, 0)));
+
+        let func = match &programs[2] {
+            Node::Stmt(Statement::Func(func)) => func,
+            node => panic!("the node is not a function statement, got={:?}", node),
+        };
+        assert_eq!(func.ident().slot(), Some((0, 2)));
+
+        let params: Vec<_> = func.params().unwrap().iter().map(|p| p.slot()).collect();
+        assert_eq!(params, vec![Some((0, 0)), Some((0, 1))]);
+
+        // The parameters take the slots 0 and 1, the function itself takes the slot 2.
+        let body = func.body().statements().unwrap();
+        assert_eq!(let_slot(&body[0]), Some((0, 3)));
+        match body[1].as_ref() {
+            Node::Exp(Expression::Infix(infix)) => {
+                assert_eq!(exp_slot(infix.left()), Some((0, 3)));
+                assert_eq!(exp_slot(infix.right()), Some((1, 1)));
+            }
+            node => panic!("the node is not an infix expression, got={:?}", node),
+        }
+    }
+
+    #[test]
+    fn test_resolve_error() {
+        let tests = vec![
+            ("x;", vec!["undefined variable `x`"]),
+            ("func f() { return g(); }", vec!["undefined variable `g`"]),
+            (
+                "func f(a, b, a) { a; }",
+                vec!["duplicate parameter `a` in function `f`"],
+            ),
+            (
+                "x; let x = 1;",
+                vec!["variable `x` is used before its definition"],
+            ),
+            (
+                "let x = x + 1;",
+                vec!["variable `x` is used before its definition"],
+            ),
+            (
+                "func f() { return later; } let later = 1;",
+                vec!["variable `later` is used before its definition"],
+            ),
+            (
+                "try { throw 1; } catch (e) { e; } e;",
+                vec!["undefined variable `e`"],
+            ),
+            (
+                "func f() { y; let y = 1; } a; b;",
+                vec![
+                    "variable `y` is used before its definition",
+                    "undefined variable `a`",
+                    "undefined variable `b`",
+                ],
+            ),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(resolve(input).unwrap_err(), expected, "input: {}", input);
+        }
+    }
+
+    #[test]
+    fn test_resolve_ok() {
+        let tests = vec![
+            // The function can call itself.
+            "func f(x) { if (x > 0) { return f(x - 1); } return 0; } f(3);",
+            // A block does not create a new scope.
+            "if (true) { let x = 1; } x;",
+            // The nested function sees the variables defined before it.
+            "func outer(a) { let b = a; func inner() { return a + b + outer(0); } return inner(); }",
+            "let arr = [1, 2]; func get(i) { return arr[i]; } get(1);",
+        ];
+
+        for input in tests {
+            assert!(resolve(input).is_ok(), "input: {}", input);
+        }
+    }
+
+    fn resolve(input: &str) -> Result<Vec<Node>, Vec<String>> {
+        Resolver::new().resolve(&Parser::from_source(input).programs())
+    }
+
+    fn let_slot(node: &Node) -> Option<(usize, usize)> {
+        match node {
+            Node::Stmt(Statement::Let(let_stmt)) => let_stmt.ident().slot(),
+            node => panic!("the node is not a let statement, got={:?}", node),
+        }
+    }
+
+    fn ident_slot(node: &Node) -> Option<(usize, usize)> {
+        match node {
+            Node::Exp(exp) => exp_slot(exp),
+            node => panic!("the node is not an expression, got={:?}", node),
+        }
+    }
+
+    fn exp_slot(exp: &Expression) -> Option<(usize, usize)> {
+        match exp {
+            Expression::Identifier(ident) => ident.slot(),
+            exp => panic!("the expression is not an identifier, got={:?}", exp),
+        }
+    }
+}
@@ -0,0 +1,591 @@
+#[cfg(test)]
+mod vm_test {
+    use woc_lang::{
+        compiler::{compiler::Compiler, opcode::OpCode, wocc},
+        evaluator_v2::evaluator::Evaluator,
+        object::object::{BaseValue, CallFrame, Error, Object, Value},
+        parser_v2::parser::Parser,
+        resolver::resolver::Resolver,
+        vm::vm::Vm,
+    };
+
+    #[test]
+    fn test_same_as_evaluator() {
+        let tests = vec![
+            // let statement
+            "let a = 5; a;",
+            "let a = 5 * 5; a;",
+            "let a = 5; let b = a; b;",
+            "let a = 5; let b = a; let c = a + b + 5; c;",
+            // the functions keep the globals that are redefined after them
+            "let x = 1; func f() { return x; } let x = 2; f();",
+            "func f() { return 1; } func h() { return f(); } func f() { return 2; } h();",
+            "let x = 1; let x = x + 1; func f() { return x; } f() + x;",
+            // return in block statement
+            "{ return 10; }",
+            "{ return 10; return 20; }",
+            "{ return 10; 20; }",
+            "{ 10; return 20; }",
+            "{ 10; 20; return 30; }",
+            "if (1 > 2) { 10; } else { return 20; }",
+            "if (1 > 2) { 10; } else { 20; return 30; }",
+            "if (10 > 1) { if (10 > 1) { return 30; } return 1; }",
+            // return statement
+            "return 1 && 1;",
+            "return 1 || 1;",
+            "return 10;",
+            "return 8.22;",
+            "return true;",
+            "return 1 < 2;",
+            "return (1 < 2) == true;",
+            "return (1 > 2) == true;",
+            // if expression
+            "if (true) { 10; }",
+            "if (1) { 10; }",
+            "if (1 < 2) { 10; }",
+            "if (1 > 2) { 10; } else { 20; }",
+            "if (1 < 2) { 10; } else { 20; }",
+            "if (false) { 10; }",
+            "if (1 > 2) { 10; }",
+            "if (1 < 2) { 10; } else if ( 2 > 1 ) { 20; } else { 30; }",
+            "if (1 == 2) { 10; } else if ( 2 > 1 ) { 20; } else { 30; }",
+            "if (1 == 2) { 10; } else if ( 2 < 1 ) { 20; } else { 30; }",
+            // boolean expression
+            "true;",
+            "false;",
+            "1 < 2;",
+            "1 > 1;",
+            "1 == 1;",
+            "1 != 2;",
+            "true == false;",
+            "false != true;",
+            "(1 > 2) == false;",
+            "true && false;",
+            "false || true;",
+            "(1 < 2) && (1 != 1);",
+            "(1 > 2) || (1 == 1);",
+            "!1;",
+            "!0;",
+            "!!1;",
+            "1 && true;",
+            "0 || false;",
+            "!1 || true;",
+            "!!0 && true;",
+            "!\"s\";",
+            "![1] || !'c';",
+            "1 && 1.5;",
+            "true + 1;",
+            "true < false;",
+            // number expression
+            "5;",
+            "-10;",
+            "-5.5;",
+            "5 + 5.5;",
+            "5.5 - 5;",
+            "5.5 * 5.5;",
+            "5.5 / 5;",
+            "10 / 3;",
+            "5 >= 5.0;",
+            // string and array
+            "let str = \"hello\"; str;",
+            "let arr = [1, 2, 3, 4, 5]; arr;",
+            "let arr = [1, 2, \"666\", 4, 5]; arr[2];",
+            "let arr = [1, 2]; arr[5];",
+            "let arr = [1, 2]; arr[-1];",
+            // function
+            "
+            func abs(a) { if ( a < 0 ) { return -1 * a; } else { return a; } }
+            func add(a, b) { return a + b; }
+            let a = abs(-821);
+            a;
+            ",
+            "
+            func add(a, b) { a + b; }
+            func abs(a) { if ( a < 0 ) { return -1 * a; } else { return a; } }
+            let a = abs(-821);
+            a;
+            let c = add(a, 1);
+            c;
+            ",
+            "
+            func counter(x) {
+                if (x > 100) {
+                    true;
+                } else {
+                    counter(x + 1);
+                }
+            }
+            counter(0);
+            ",
+            "func add(a, b) { a + b; } add(1);",
+            // interpolated string
+            "let n = 2; \"${n} + ${n * 1.5} = ${n + n * 1.5}, ${n > 1}\";",
+            "func f(x) { return x; } let s = \"a\"; \"${f(1)}${s}${[s, s]}\";",
+            "let x = 1; x(2);",
+            // char and byte
+            "'a' < 'b';",
+            "b'a' == b'a';",
+            "'a' == b'a';",
+            "'a' + 'b';",
+            "func f(c) { return c; } let c = f('\\u{1F600}'); c;",
+            "\"${'a'}${b'a'}\";",
+            // equality and ordering
+            "\"abc\" == \"abc\";",
+            "\"abc\" < \"abd\";",
+            "[1, [2, \"x\"]] == [1, [2, \"x\"]];",
+            "[1, 2] == [1.0, 2];",
+            "[1, 2] < [1, 2, 0];",
+            "\"1\" == 1;",
+            "true <= true;",
+            "func f(x) { return x; } func g(x) { return x; } f == f && f != g;",
+            "int == int;",
+            // short-circuit evaluation
+            "0 && true;",
+            "0 || 2.5;",
+            "1 && \"s\";",
+            "false && int(\"x\");",
+            "true || int(\"x\");",
+            "let arr = 1; is_array(arr) && arr[0] > 0;",
+            "func f(x) { return x > 0 && x < 10 || x == 100; } let a = f(5); let b = f(100); let c = f(50); a && b && !c;",
+            "let x = 0 || (1 && 2); x;",
+            // builtin functions
+            "int('a') + int(b'a') + int(1.9) + int(\" 12 \");",
+            "char(97) == 'a';",
+            "byte('a');",
+            "str(char(int('a') + 1));",
+            "float(3) + float(\"0.5\");",
+            "bool(0) || bool(\"true\");",
+            "type([1, 2]) + type(str);",
+            "is_int(1) && !is_str(1);",
+            "str([1, 2]);",
+            "let int = 1; int;",
+            "func f(x) { return int(x); } let n = f(1.5); n;",
+            // the value of a call is the returned value
+            "func b() { return 1; } func a(x) { b(); return 2; } a(0);",
+            "func one() { return 1; } one();",
+            "func g(x) { return \"s\"; } func h(x) { return g(x); } h(1);",
+            "func f(x) { return [x]; } let a = f(1); a;",
+        ];
+
+        for input in tests {
+            let expected = eval(input);
+            let got = run(input);
+            assert!(
+                same_object(&got, &expected),
+                "input: {}\ngot={:?}, want={:?}",
+                input,
+                got,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_func_equality() {
+        // The functions are equal only if they come from the same definition, even if the
+        // definitions have the same name and body.
+        let tests = vec![
+            (
+                "func f(x) { return x; } let a = f; func f(x) { return x; } a == f;",
+                false,
+            ),
+            ("func f(x) { return x; } let a = f; a == f;", true),
+            (
+                "func g() { func h() { return 1; } return h; } let a = g(); let b = g(); a == b;",
+                true,
+            ),
+        ];
+
+        for (input, expected) in tests {
+            for got in [eval(input), run(input)] {
+                assert!(
+                    same_object(
+                        &got,
+                        &Object::Base(BaseValue::Boolean(Value::new(expected)))
+                    ),
+                    "input: {}\ngot={:?}",
+                    input,
+                    got
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_nested_func() {
+        let tests = vec![
+            (
+                "
+                func outer(a) {
+                    let b = a * 2;
+                    func inner(c) { return a + b + c; }
+                    return inner(1);
+                }
+                outer(10);
+                ",
+                31,
+            ),
+            (
+                "
+                func outer(n) {
+                    func fact(x) { if (x < 2) { return 1; } return x * fact(x - 1); }
+                    return fact(n);
+                }
+                outer(5);
+                ",
+                120,
+            ),
+            (
+                "
+                func a(x) {
+                    func b(y) {
+                        func c(z) { return x + y + z; }
+                        return c(3);
+                    }
+                    return b(2);
+                }
+                a(1);
+                ",
+                6,
+            ),
+            ("func zero() { return 822; } zero();", 822),
+        ];
+
+        for (input, expected) in tests {
+            match run(input) {
+                Object::Base(BaseValue::Integer(v)) => assert_eq!(*v.value(), expected),
+                obj => panic!(
+                    "input: {}\nthe result is not an integer, got={:?}",
+                    input, obj
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn test_deep_recursion() {
+        // The frames are not kept in the Rust stack, so the limit can be raised far beyond the default.
+        let input = "
+            func count(x) { if (x == 0) { return 0; } return 1 + count(x - 1); }
+            count(100000);
+        ";
+        let bytecode = Compiler::new().compile(&Parser::from_source(input).programs());
+
+        match Vm::new(bytecode).with_max_depth(1000000).run().unwrap() {
+            Object::Base(BaseValue::Integer(v)) => assert_eq!(*v.value(), 100000),
+            obj => panic!("the result is not an integer, got={:?}", obj),
+        }
+    }
+
+    #[test]
+    fn test_tail_call() {
+        // The tail recursion runs in one frame.
+        let input = "
+            func sum(n, acc) { if (n == 0) { return acc; } return sum(n - 1, acc + n); }
+            sum(1000000, 0);
+        ";
+        match run(input) {
+            Object::Base(BaseValue::Integer(v)) => assert_eq!(*v.value(), 500000500000),
+            obj => panic!("the result is not an integer, got={:?}", obj),
+        }
+
+        // The call in a `try` region has to leave the region after it returns.
+        let input = "
+            func f(x) { try { return f(x - 1); } catch (e) { return 0; } }
+            func g(x) { return f(x); }
+        ";
+        let bytecode = Compiler::new().compile(&Parser::from_source(input).programs());
+        let tail_calls = |i: usize| {
+            bytecode.functions()[i]
+                .instructions()
+                .iter()
+                .filter(|op| matches!(op, OpCode::TailCall(_)))
+                .count()
+        };
+        assert_eq!(tail_calls(1), 0);
+        assert_eq!(tail_calls(2), 1);
+    }
+
+    #[test]
+    fn test_backtrace() {
+        let input = "
+func div(a) {
+    return a / 0;
+}
+func run(a) {
+    let x = div(a);
+    return x;
+}
+run(1);
+";
+        // Every call records the line of its call site, the innermost one first.
+        let bytecode = Compiler::new().compile(&Parser::from_source(input).programs());
+        let stripped = wocc::decode(&wocc::encode(&bytecode, false)).unwrap();
+        assert_eq!(
+            Vm::new(bytecode).run().unwrap_err(),
+            "RuntimeError: division by zero\n    at div (line 6)\n    at run (line 9)"
+        );
+
+        // The bytecode without the debug line table has no lines.
+        assert_eq!(
+            Vm::new(stripped).run().unwrap_err(),
+            "RuntimeError: division by zero\n    at div\n    at run"
+        );
+    }
+
+    #[test]
+    fn test_max_depth() {
+        // The deep recursion raises the same error as the evaluator instead of growing without limit.
+        let input = "func loop(n) { return 1 + loop(n + 1); } loop(0);";
+        let bytecode = Compiler::new().compile(&Parser::from_source(input).programs());
+        let report = Vm::new(bytecode).run().unwrap_err();
+        assert_eq!(
+            report.lines().next().unwrap(),
+            "RuntimeError: stack overflow, the maximum call depth is 1000"
+        );
+
+        let input = "
+            func loop(n) { return 1 + loop(n + 1); }
+            let m = 0;
+            try { loop(0); } catch (e) { let m = str(e); }
+            m;
+        ";
+        let bytecode = Compiler::new().compile(&Parser::from_source(input).programs());
+        match Vm::new(bytecode).with_max_depth(20).run().unwrap() {
+            Object::Str(s) => assert_eq!(
+                s.value(),
+                "RuntimeError: stack overflow, the maximum call depth is 20"
+            ),
+            obj => panic!("the result is not a string, got={:?}", obj),
+        }
+    }
+
+    #[test]
+    fn test_runtime_error() {
+        let tests = vec![
+            ("1 / 0;", "RuntimeError: division by zero"),
+            (
+                "let m = -9223372036854775807 - 1; -m;",
+                "RuntimeError: integer overflow",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let bytecode = Compiler::new().compile(&Parser::from_source(input).programs());
+            assert_eq!(Vm::new(bytecode).run().unwrap_err(), expected);
+            match eval(input) {
+                Object::Throw(err) => assert_eq!(err.to_string(), expected),
+                obj => panic!("the result is not an error, got={:?}", obj),
+            }
+        }
+    }
+
+    #[test]
+    fn test_builtin_errors() {
+        // The uncaught error of a builtin function stops both the evaluator and the virtual machine.
+        let tests = vec![
+            (
+                "int(\"x\");",
+                "ConversionError: cannot convert \"x\" to int",
+            ),
+            (
+                "int(1, 2);",
+                "ArgumentError: `int` takes 1 argument, but 2 were given",
+            ),
+            (
+                "func f(x) { return float(x); } f(\"x\"); 1;",
+                "ConversionError: cannot convert \"x\" to float",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let report = match eval(input) {
+                Object::Throw(err) => {
+                    assert_eq!(err.to_string(), expected);
+                    vm_report(&err)
+                }
+                obj => panic!("the result is not an error, got={:?}", obj),
+            };
+
+            let bytecode = Compiler::new().compile(&Parser::from_source(input).programs());
+            assert_eq!(Vm::new(bytecode).run().unwrap_err(), report);
+        }
+    }
+
+    #[test]
+    fn test_exceptions() {
+        // The errors are caught, unwound and traced the same as the evaluator.
+        let tests = vec![
+            "let r = 0; try { throw 1; } catch (e) { let r = 2; } r;",
+            "try { 1; } catch (e) { 2; }",
+            "try { throw 1; } catch (e) { 2; }",
+            "let e = 5; try { throw \"x\"; } catch (e) { 1; } e;",
+            "func f() { let e = 5; try { throw 1; } catch (e) { let e = 2; } return e; } f();",
+            "let m = 0; try { throw 42; } catch (e) { let m = str(e); } m;",
+            "let k = 0; try { int(\"x\"); } catch (e) { let k = error_kind(e); } k;",
+            "let m = 0; try { 1 / 0; } catch (e) { let m = str(e); } m;",
+            "
+            func inner(x) { throw error(\"ValueError\", \"bad\"); }
+            func outer(x) { return inner(x) + 1; }
+            let t = 0;
+            try { outer(1); } catch (e) { let t = error_trace(e); }
+            t;
+            ",
+            "
+            func g(x) { if (x == 0) { throw \"deep\"; } return g(x - 1); }
+            func h(x) { let a = 10; try { g(x); } catch (e) { return a + x; } }
+            let r = h(5);
+            let t = 0;
+            try { g(3); } catch (e) { let t = error_trace(e); }
+            \"${r} ${t}\";
+            ",
+            "func safe(x) { try { return int(x); } catch (e) { return -1; } } let a = safe(\"7\"); let b = safe(\"x\"); a + b;",
+            "func f(x) { throw x; } let s = 1 + if (true) { try { f(1); } catch (e) { 5; } }; s;",
+            // finally
+            "let n = 0; try { let n = 1; } finally { let n = n + 10; } n;",
+            "let n = 0; try { throw 1; } catch (e) { let n = 1; } finally { let n = n + 10; } n;",
+            "func f(x) { try { return x; } finally { return 2; } } let r = f(1); r;",
+            "func f(x) { try { throw x; } finally { return 2; } } let r = f(1); r;",
+            "func f(x) { try { throw x; } catch (e) { throw 2; } finally { return 3; } } let r = f(0); r;",
+            "func f(x) { try { try { return x; } finally { let x = x + 1; } } finally { return x * 10; } } let r = f(1); r;",
+            // rethrow
+            "let m = 0; try { try { throw \"a\"; } catch (e) { throw e; } } catch (e) { let m = error_message(e); } m;",
+            "let m = 0; let n = 0; try { try { throw \"a\"; } finally { let n = 1; } } catch (e) { let m = error_message(e); } \"${m}${n}\";",
+            // error values
+            "let e = error(\"KeyError\", \"k\"); \"${type(e)} ${is_error(e)} ${e}\";",
+            "error(\"KeyError\", \"k\") == error(\"KeyError\", \"k\");",
+        ];
+
+        for input in tests {
+            let expected = eval(input);
+            let got = run(input);
+            assert!(
+                same_object(&got, &expected),
+                "input: {}\ngot={:?}, want={:?}",
+                input,
+                got,
+                expected
+            );
+        }
+
+        // The error that isn't caught stops both of them with its kind and message.
+        let tests = vec![
+            ("throw error(\"E\", \"m\");", "E: m"),
+            ("throw 1 + 1;", "Error: 2"),
+            (
+                "func f(x) { try { throw \"a\"; } finally { let y = 1; } } f(1); 1;",
+                "Error: a",
+            ),
+            (
+                "try { throw 1; } catch (e) { 1 / 0; } finally { 1; }",
+                "RuntimeError: division by zero",
+            ),
+        ];
+
+        for (input, expected) in tests {
+            let report = match eval(input) {
+                Object::Throw(err) => {
+                    assert_eq!(err.to_string(), expected);
+                    vm_report(&err)
+                }
+                obj => panic!("the result is not an error, got={:?}", obj),
+            };
+
+            let bytecode = Compiler::new().compile(&Parser::from_source(input).programs());
+            assert_eq!(Vm::new(bytecode).run().unwrap_err(), report);
+        }
+    }
+
+    #[test]
+    fn test_compile() {
+        let bytecode =
+            Compiler::new().compile(&Parser::from_source("let x = 1 + 2; x;").programs());
+
+        assert_eq!(bytecode.num_globals(), 1);
+        assert_eq!(bytecode.constants().len(), 2);
+        assert_eq!(
+            bytecode.main().instructions(),
+            &vec![
+                OpCode::Constant(0),
+                OpCode::Constant(1),
+                OpCode::Add,
+                OpCode::SetGlobal(0),
+                OpCode::GetGlobal(0),
+                OpCode::Halt,
+            ]
+        );
+    }
+
+    fn eval(input: &str) -> Object {
+        let programs = Resolver::new()
+            .resolve(&Parser::from_source(input).programs())
+            .unwrap();
+        let evaluator = Evaluator::new(None);
+
+        let mut result = Object::Null;
+        for node in programs.iter() {
+            result = evaluator.eval(node);
+            if result.is_thrown() {
+                break;
+            }
+        }
+        result
+    }
+
+    fn run(input: &str) -> Object {
+        let programs = Parser::from_source(input).programs();
+        let bytecode = Compiler::new().compile(&programs);
+        Vm::new(bytecode).run().unwrap()
+    }
+
+    // The report of the virtual machine has the same backtrace without the columns of the call sites.
+    fn vm_report(err: &Error) -> String {
+        let trace: String = err
+            .trace()
+            .iter()
+            .map(|frame| format!("\n    {}", CallFrame::at_line(frame.name(), frame.line())))
+            .collect();
+        format!("{}{}", err, trace)
+    }
+
+    fn same_object(got: &Object, want: &Object) -> bool {
+        match (got, want) {
+            (Object::Null, Object::Null) => true,
+            (Object::Base(BaseValue::Integer(g)), Object::Base(BaseValue::Integer(w))) => {
+                g.value() == w.value()
+            }
+            (Object::Base(BaseValue::Float(g)), Object::Base(BaseValue::Float(w))) => {
+                g.value() == w.value()
+            }
+            (Object::Base(BaseValue::Boolean(g)), Object::Base(BaseValue::Boolean(w))) => {
+                g.value() == w.value()
+            }
+            (Object::Base(BaseValue::Char(g)), Object::Base(BaseValue::Char(w))) => {
+                g.value() == w.value()
+            }
+            (Object::Base(BaseValue::Byte(g)), Object::Base(BaseValue::Byte(w))) => {
+                g.value() == w.value()
+            }
+            (Object::Str(g), Object::Str(w)) => g.value() == w.value(),
+            (Object::Array(g), Object::Array(w)) => {
+                g.elements().len() == w.elements().len()
+                    && g.elements()
+                        .iter()
+                        .zip(w.elements())
+                        .all(|(g, w)| same_object(g, w))
+            }
+            (Object::Return(g), Object::Return(w)) => same_object(g, w),
+            (Object::Error(g), Object::Error(w)) => {
+                // The bytecode doesn't record the call sites, so only the functions are compared.
+                let names = |e: &Error| {
+                    e.trace()
+                        .iter()
+                        .map(|f| f.name().to_string())
+                        .collect::<Vec<_>>()
+                };
+                g.to_string() == w.to_string() && names(g) == names(w)
+            }
+            _ => false,
+        }
+    }
+}
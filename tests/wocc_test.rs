@@ -0,0 +1,133 @@
+#[cfg(test)]
+mod wocc_test {
+    use std::rc::Rc;
+
+    use woc_lang::{
+        compiler::{
+            compiler::{Bytecode, Compiler},
+            opcode::OpCode,
+            wocc::{self, FORMAT_VERSION},
+        },
+        object::object::{BaseValue, CompiledFunction, Object},
+        parser_v2::parser::Parser,
+        vm::vm::Vm,
+    };
+
+    const SOURCE: &str = "
+        let name = \"woc\";
+        func fib(n) {
+            if (n < 2 || n > 90 && true) { return n; }
+            return fib(n - 1) + fib(n - 2);
+        }
+        func outer(a) {
+            func inner(b) { return a + b; }
+            try { throw inner(0.5); } catch (e) { return float(error_message(e)); }
+        }
+        let arr = [fib(10), outer(1), name, true, char(int('a') + 1), b'y'];
+        arr;
+    ";
+
+    #[test]
+    fn test_round_trip() {
+        let bytecode = compile(SOURCE);
+        let decoded = wocc::decode(&wocc::encode(&bytecode, true)).unwrap();
+
+        assert_eq!(decoded.num_globals(), bytecode.num_globals());
+        assert_eq!(decoded.constants().len(), bytecode.constants().len());
+        assert_eq!(decoded.functions().len(), bytecode.functions().len());
+        for (d, b) in decoded.functions().iter().zip(bytecode.functions()) {
+            assert_eq!(d.name(), b.name());
+            assert_eq!(d.params(), b.params());
+            assert_eq!(d.num_locals(), b.num_locals());
+            assert_eq!(d.captures(), b.captures());
+            assert_eq!(d.instructions(), b.instructions());
+            assert_eq!(d.lines(), b.lines());
+        }
+
+        let result = Vm::new(decoded).run().unwrap();
+        assert_eq!(result.to_string(), "[55, 1.5, \"woc\", true, 'b', b'y']");
+    }
+
+    #[test]
+    fn test_debug_line_table() {
+        let bytecode = compile(SOURCE);
+
+        // The call of `fib(n - 1)` is at the line 5.
+        let fib = &bytecode.functions()[1];
+        let call = fib
+            .instructions()
+            .iter()
+            .position(|op| *op == OpCode::Call(1))
+            .unwrap();
+        assert_eq!(fib.line_of(call), Some(5));
+
+        let stripped = wocc::decode(&wocc::encode(&bytecode, false)).unwrap();
+        assert!(stripped.functions().iter().all(|f| f.lines().is_empty()));
+        assert!(wocc::encode(&bytecode, false).len() < wocc::encode(&bytecode, true).len());
+    }
+
+    #[test]
+    fn test_read_write_file() {
+        let path = std::env::temp_dir().join("woc_lang_wocc_test.wocc");
+        wocc::write_file(&path, &compile("let x = 822; x;"), true).unwrap();
+
+        let bytecode = wocc::read_file(&path).unwrap();
+        match Vm::new(bytecode).run().unwrap() {
+            Object::Base(BaseValue::Integer(v)) => assert_eq!(*v.value(), 822),
+            obj => panic!("the result is not an integer, got={:?}", obj),
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_reject_bad_magic() {
+        let err = wocc::decode(b"NOPE").unwrap_err();
+        assert!(err.contains("bad magic number"), "{}", err);
+    }
+
+    #[test]
+    fn test_reject_version_mismatch() {
+        let mut bytes = wocc::encode(&compile("1;"), true);
+        bytes[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+
+        let err = wocc::decode(&bytes).unwrap_err();
+        assert!(err.contains("unsupported wocc version"), "{}", err);
+    }
+
+    #[test]
+    fn test_reject_corrupt_file() {
+        let bytes = wocc::encode(&compile(SOURCE), true);
+
+        // Flip a byte in the body.
+        let mut corrupt = bytes.clone();
+        corrupt[20] ^= 0xff;
+        let err = wocc::decode(&corrupt).unwrap_err();
+        assert!(err.contains("checksum mismatch"), "{}", err);
+
+        // Truncate the file.
+        assert!(wocc::decode(&bytes[..8]).is_err());
+        assert!(wocc::decode(&bytes[..bytes.len() / 2]).is_err());
+    }
+
+    #[test]
+    fn test_reject_invalid_instruction() {
+        let main = CompiledFunction::new(
+            "<main>".to_string(),
+            Vec::new(),
+            0,
+            vec![OpCode::Constant(3), OpCode::Halt],
+            Vec::new(),
+            Vec::new(),
+        );
+        let bytecode = Bytecode::new(Vec::new(), vec![Rc::new(main)], 0);
+
+        let err = wocc::decode(&wocc::encode(&bytecode, false)).unwrap_err();
+        assert!(err.contains("invalid instruction"), "{}", err);
+    }
+
+    fn compile(input: &str) -> Bytecode {
+        let programs = Parser::from_source(input).programs();
+        Compiler::new().compile(&programs)
+    }
+}
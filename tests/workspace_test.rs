@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod workspace_test {
+    use std::path::Path;
+
+    use woc_lang::fs::manifest::Manifest;
+    use woc_lang::fs::tree::{Node, Tree};
+    use woc_lang::fs::woc_file::WocFile;
+    use woc_lang::fs::workspace::Workspace;
+
+    #[test]
+    fn test_scan_tree() {
+        let tree = Tree::scan(Path::new("woc_test_files/workspace")).unwrap();
+
+        let names: Vec<&str> = tree.root().children().iter().map(|n| n.name()).collect();
+        // The hidden directory and the directory without source files are skipped.
+        assert_eq!(names, vec!["lib", "src"]);
+        assert!(tree.root().children().iter().all(Node::is_dir));
+
+        assert_eq!(
+            tree.files(),
+            vec![
+                Path::new("woc_test_files/workspace/lib/version.woc"),
+                Path::new("woc_test_files/workspace/src/main.woc"),
+                Path::new("woc_test_files/workspace/src/utils/math.woc"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_woc_file_lines() {
+        let file = WocFile::from_source("a.woc", "\u{FEFF}  let a = 1;\r\n\r\nlet é = 2;\n");
+
+        assert!(file.has_bom());
+        assert_eq!(file.source(), "  let a = 1;\r\n\r\nlet é = 2;\n");
+        assert_eq!(file.line_count(), 4);
+        // The indentation is kept and the line breaks are removed.
+        assert_eq!(file.get_line(1).unwrap(), "  let a = 1;");
+        assert_eq!(file.get_line(2).unwrap(), "");
+        assert_eq!(file.get_line(3).unwrap(), "let é = 2;");
+        assert_eq!(file.get_line(4).unwrap(), "");
+        assert_eq!(file.get_line(5), None);
+        assert_eq!(file.get_line(0), None);
+
+        assert_eq!(file.position(0), (1, 1));
+        assert_eq!(file.position(2), (1, 3));
+        assert_eq!(file.position(14), (2, 1));
+        // The `=` after the two-byte `é`.
+        assert_eq!(file.position(23), (3, 7));
+        assert_eq!(file.position(file.source().len()), (4, 1));
+    }
+
+    #[test]
+    fn test_scan_not_dir() {
+        assert!(Tree::scan(Path::new("woc_test_files/hello_world.woc")).is_err());
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = Manifest::parse(
+            "
+            name = \"demo\" # the name
+
+            [project]
+            entry = \"app.woc\"
+            search_paths = [\"src\", \"lib\",]
+
+            [tools]
+            anything = 1
+
+            [lint]
+            unused_variable = false
+            shadowing = true
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(manifest.name().unwrap(), "demo");
+        assert_eq!(manifest.entry(), "app.woc");
+        assert_eq!(manifest.search_paths(), &vec!["src", "lib"]);
+        assert_eq!(
+            manifest.lint_rules(),
+            &[
+                ("unused_variable".to_string(), false),
+                ("shadowing".to_string(), true)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_manifest_error() {
+        assert!(Manifest::parse("entry = main.woc").is_err());
+        assert!(Manifest::parse("search_paths = \"src\"").is_err());
+        assert!(Manifest::parse("[project").is_err());
+        assert!(Manifest::parse("unknown = \"x\"").is_err());
+        assert!(Manifest::parse("entry").is_err());
+        assert!(Manifest::parse("[lint]\nshadowing = 1").is_err());
+    }
+
+    #[test]
+    fn test_workspace_entry() {
+        let workspace = Workspace::load(Path::new("woc_test_files/workspace")).unwrap();
+        assert_eq!(workspace.manifest().name().unwrap(), "demo");
+        assert_eq!(
+            workspace.entry().unwrap(),
+            Path::new("woc_test_files/workspace/src/main.woc")
+        );
+
+        // Without a manifest, the `main.woc` in the root is the entry.
+        let workspace = Workspace::load(Path::new("woc_test_files/workspace_default")).unwrap();
+        assert_eq!(
+            workspace.entry().unwrap(),
+            Path::new("woc_test_files/workspace_default/main.woc")
+        );
+
+        let workspace = Workspace::load(Path::new("woc_test_files/parser")).unwrap();
+        assert!(workspace.entry().is_err());
+    }
+
+    #[test]
+    fn test_workspace_resolve() {
+        let workspace = Workspace::load(Path::new("woc_test_files/workspace")).unwrap();
+
+        let file = workspace.resolve("utils.math").unwrap();
+        assert_eq!(
+            file,
+            Path::new("woc_test_files/workspace/src/utils/math.woc")
+        );
+
+        let file = workspace.resolve("version").unwrap();
+        assert_eq!(file, Path::new("woc_test_files/workspace/lib/version.woc"));
+
+        // The root of the project is searched last.
+        let file = workspace.resolve("src.main").unwrap();
+        assert_eq!(file, Path::new("woc_test_files/workspace/src/main.woc"));
+
+        assert!(workspace.resolve("not.exist").is_none());
+        assert!(workspace.resolve("hidden").is_none());
+    }
+}